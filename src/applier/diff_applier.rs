@@ -5,7 +5,7 @@ use crate::extractor::ParsedBlock;
 use crate::utils::{read_file_async, write_file_async};
 use async_trait::async_trait;
 use diffy::Patch;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 pub struct DiffApplier {
@@ -13,9 +13,9 @@ pub struct DiffApplier {
 }
 
 impl DiffApplier {
-    pub fn new(base_path: &PathBuf) -> Self {
+    pub fn new(base_path: &Path) -> Self {
         DiffApplier {
-            base_path: base_path.clone(),
+            base_path: base_path.to_path_buf(),
         }
     }
 }
@@ -38,7 +38,7 @@ impl Applier for DiffApplier {
 }
 
 pub async fn apply_diff(
-    path: &PathBuf,
+    path: &Path,
     current_content: &str,
     diff_content: &str,
 ) -> Result<String, ClipboardError> {
@@ -50,13 +50,13 @@ pub async fn apply_diff(
             Err(e) => Err(ClipboardError::DiffError(format!(
                 "Failed to apply diff for file {}: {}",
                 path.display(),
-                e.to_string()
+                e
             ))),
         },
         Err(e) => Err(ClipboardError::DiffError(format!(
             "Failed to parse diff for file {}: {}",
             path.display(),
-            e.to_string()
+            e
         ))),
     }
 }
@@ -4,7 +4,7 @@ use crate::errors::ClipboardError;
 use crate::extractor::ParsedBlock;
 use crate::utils::{read_file_async, write_file_async};
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 #[derive(Clone)]
@@ -13,9 +13,9 @@ pub struct FullContentApplier {
 }
 
 impl FullContentApplier {
-    pub fn new(base_path: &PathBuf) -> Self {
+    pub fn new(base_path: &Path) -> Self {
         FullContentApplier {
-            base_path: base_path.clone(),
+            base_path: base_path.to_path_buf(),
         }
     }
 }
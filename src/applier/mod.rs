@@ -4,11 +4,13 @@ use async_trait::async_trait;
 
 pub mod diff_applier;
 pub mod full_content_applier;
+pub mod notebook_applier;
 pub mod search_replace_applier;
 pub mod utils;
 
 pub use diff_applier::DiffApplier;
 pub use full_content_applier::FullContentApplier;
+pub use notebook_applier::NotebookCellApplier;
 pub use search_replace_applier::SearchReplaceApplier;
 
 #[async_trait]
@@ -0,0 +1,54 @@
+use crate::applier::utils::print_diff;
+use crate::applier::Applier;
+use crate::errors::ClipboardError;
+use crate::extractor::ParsedBlock;
+use crate::notebook::{parse_cell_address, Notebook};
+use crate::utils::{read_file_async, write_file_async};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Writes a block addressed to a notebook cell, such as `analysis.ipynb cell 4`,
+/// into the source of that cell.
+#[derive(Clone)]
+pub struct NotebookCellApplier {
+    base_path: PathBuf,
+}
+
+impl NotebookCellApplier {
+    pub fn new(base_path: &Path) -> Self {
+        NotebookCellApplier {
+            base_path: base_path.to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl Applier for NotebookCellApplier {
+    async fn apply(&self, block: &ParsedBlock) -> Result<(), ClipboardError> {
+        let (notebook_path, number) = parse_cell_address(&block.filename).ok_or_else(|| {
+            ClipboardError::ContentApplicationError(format!(
+                "{} does not name a notebook cell",
+                block.filename
+            ))
+        })?;
+        let file_path = self.base_path.join(&notebook_path);
+        debug!("Applying cell {} to notebook: {:?}", number, file_path);
+
+        let original_content = read_file_async(&file_path).await.map_err(|e| {
+            ClipboardError::FileError(format!("Failed to read {:?}: {}", file_path, e))
+        })?;
+        let mut notebook = Notebook::parse(&original_content)?;
+        let original_source = notebook
+            .cells()
+            .get(number - 1)
+            .map(|cell| cell.source.clone())
+            .unwrap_or_default();
+        notebook.set_cell_source(number, &block.content)?;
+        write_file_async(&file_path, &notebook.to_json()?).await?;
+
+        print_diff(&block.filename, &original_source, &block.content);
+        info!("Applied cell {} of {:?}", number, file_path);
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use crate::extractor::ParsedBlock;
 use crate::utils::{read_file_async, remove_file_async, write_file_async};
 use async_trait::async_trait;
 use regex::Regex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, error, info};
 
 #[derive(Clone)]
@@ -14,9 +14,9 @@ pub struct SearchReplaceApplier {
 }
 
 impl SearchReplaceApplier {
-    pub fn new(base_path: &PathBuf) -> Self {
+    pub fn new(base_path: &Path) -> Self {
         SearchReplaceApplier {
-            base_path: base_path.clone(),
+            base_path: base_path.to_path_buf(),
         }
     }
 }
@@ -0,0 +1,260 @@
+//! Named sets of file patterns and copy options, saved per project.
+//!
+//! Bundles are JSON files under `.snippy/bundles/` at the project root, the
+//! nearest directory with a `.snippy` or `.git` directory.
+
+use crate::compact::CompactOptions;
+use crate::copy::ClipboardCopierConfig;
+use crate::errors::ClipboardError;
+use crate::json::OutputFormat;
+use crate::remote::GitSource;
+use crate::xml::XmlAttribute;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Directory of bundles, relative to the project root.
+pub const BUNDLES_DIR: &str = ".snippy/bundles";
+
+/// File patterns with the ignore rules, filters and format options to copy them with.
+///
+/// Fields missing from a bundle file take the `copy` defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bundle {
+    /// File patterns, relative to the project root.
+    pub patterns: Vec<String>,
+    pub ignore_patterns: Option<Vec<String>>,
+    pub no_ignore_files: bool,
+    pub tracked_only: bool,
+    pub no_markdown: bool,
+    pub line_number: Option<usize>,
+    pub prefix: String,
+    pub model: String,
+    pub filename_format: String,
+    pub first_line: String,
+    pub xml: bool,
+    pub template: Option<String>,
+    pub xml_attributes: Vec<XmlAttribute>,
+    pub format: OutputFormat,
+    pub outline: bool,
+    pub compact: CompactOptions,
+    pub follow_imports: Option<usize>,
+    pub max_tokens: Option<usize>,
+    pub no_content_filters: bool,
+    pub max_file_bytes: Option<u64>,
+    pub max_file_tokens: Option<usize>,
+    pub no_redact: bool,
+    pub redact_rules: Vec<String>,
+    pub redact_allowlist: Vec<String>,
+    pub prompt: Option<String>,
+    pub task: Option<String>,
+    pub prompt_vars: Vec<String>,
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Bundle::from_config(Vec::new(), &ClipboardCopierConfig::default())
+    }
+}
+
+impl Bundle {
+    /// Records `patterns` with the ignore rules, filters and format options of `config`.
+    pub fn from_config(patterns: Vec<String>, config: &ClipboardCopierConfig) -> Self {
+        Bundle {
+            patterns,
+            ignore_patterns: config.ignore_patterns.clone(),
+            no_ignore_files: config.no_ignore_files,
+            tracked_only: config.tracked_only,
+            no_markdown: config.no_markdown,
+            line_number: config.line_number,
+            prefix: config.prefix.clone(),
+            model: config.model.clone(),
+            filename_format: config.filename_format.clone(),
+            first_line: config.first_line.clone(),
+            xml: config.xml,
+            template: config.template.clone(),
+            xml_attributes: config.xml_attributes.clone(),
+            format: config.format,
+            outline: config.outline,
+            compact: config.compact,
+            follow_imports: config.follow_imports,
+            max_tokens: config.max_tokens,
+            no_content_filters: config.no_content_filters,
+            max_file_bytes: config.max_file_bytes,
+            max_file_tokens: config.max_file_tokens,
+            no_redact: config.no_redact,
+            redact_rules: config.redact_rules.clone(),
+            redact_allowlist: config.redact_allowlist.clone(),
+            prompt: config.prompt.clone(),
+            task: config.task.clone(),
+            prompt_vars: config.prompt_vars.clone(),
+        }
+    }
+
+    /// Applies the bundle's options to `config`, keeping its other settings.
+    pub fn apply(&self, config: ClipboardCopierConfig) -> ClipboardCopierConfig {
+        ClipboardCopierConfig {
+            ignore_patterns: self.ignore_patterns.clone(),
+            no_ignore_files: self.no_ignore_files,
+            tracked_only: self.tracked_only,
+            no_markdown: self.no_markdown,
+            line_number: self.line_number,
+            prefix: self.prefix.clone(),
+            model: self.model.clone(),
+            filename_format: self.filename_format.clone(),
+            first_line: self.first_line.clone(),
+            xml: self.xml,
+            template: self.template.clone(),
+            xml_attributes: self.xml_attributes.clone(),
+            format: self.format,
+            outline: self.outline,
+            compact: self.compact,
+            follow_imports: self.follow_imports,
+            max_tokens: self.max_tokens,
+            no_content_filters: self.no_content_filters,
+            max_file_bytes: self.max_file_bytes,
+            max_file_tokens: self.max_file_tokens,
+            no_redact: self.no_redact,
+            redact_rules: self.redact_rules.clone(),
+            redact_allowlist: self.redact_allowlist.clone(),
+            prompt: self.prompt.clone(),
+            task: self.task.clone(),
+            prompt_vars: self.prompt_vars.clone(),
+            ..config
+        }
+    }
+
+    /// The bundle's patterns joined onto the project `root`.
+    ///
+    /// Absolute paths are left as they are, and so are the patterns of a
+    /// bundle that starts with a git source.
+    pub fn resolve_patterns(&self, root: &Path) -> Vec<String> {
+        if self
+            .patterns
+            .first()
+            .is_some_and(|p| GitSource::parse(p).is_some())
+        {
+            return self.patterns.clone();
+        }
+        self.patterns
+            .iter()
+            .map(|pattern| root.join(pattern).to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
+/// The nearest ancestor of `start` with a `.snippy` or `.git` directory, or `start`.
+pub fn project_root(start: &Path) -> PathBuf {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".snippy").is_dir() || dir.join(".git").exists())
+        .unwrap_or(start)
+        .to_path_buf()
+}
+
+/// Bundles saved in one directory.
+#[derive(Debug, Clone)]
+pub struct BundleStore {
+    dir: PathBuf,
+}
+
+impl BundleStore {
+    pub fn new(dir: PathBuf) -> Self {
+        BundleStore { dir }
+    }
+
+    /// The bundles of the project containing `start`.
+    pub fn for_project(start: &Path) -> Self {
+        BundleStore::new(project_root(start).join(BUNDLES_DIR))
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, ClipboardError> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(ClipboardError::ConfigError(format!(
+                "Invalid bundle name '{}'; use letters, digits, '-', '_' and '.'",
+                name
+            )));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Saves `bundle` as `name`, replacing any bundle of the same name.
+    pub fn save(&self, name: &str, bundle: &Bundle) -> Result<PathBuf, ClipboardError> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(bundle)
+            .map_err(|e| ClipboardError::ConfigError(e.to_string()))?;
+        std::fs::write(&path, format!("{}\n", json))?;
+        debug!("Saved bundle {} to {:?}", name, path);
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Bundle, ClipboardError> {
+        let path = self.path(name)?;
+        if !path.is_file() {
+            return Err(ClipboardError::ConfigError(format!(
+                "Bundle '{}' not found; available bundles: {}",
+                name,
+                self.list()?.join(", ")
+            )));
+        }
+        let json = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| ClipboardError::ConfigError(format!("Invalid bundle {:?}: {}", path, e)))
+    }
+
+    /// Names of the saved bundles, sorted.
+    pub fn list(&self) -> Result<Vec<String>, ClipboardError> {
+        let mut names = Vec::new();
+        if !self.dir.is_dir() {
+            return Ok(names);
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), ClipboardError> {
+        let path = self.path(name)?;
+        if !path.is_file() {
+            return Err(ClipboardError::ConfigError(format!(
+                "Bundle '{}' not found",
+                name
+            )));
+        }
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
+
+/// Rewrites `pattern`, written relative to `cwd`, to be relative to `root`.
+///
+/// Absolute paths and git sources are left as they are.
+pub fn root_relative_pattern(pattern: &str, cwd: &Path, root: &Path) -> String {
+    let prefix = match cwd.strip_prefix(root) {
+        Ok(prefix) if !prefix.as_os_str().is_empty() => {
+            prefix.to_string_lossy().replace('\\', "/")
+        }
+        _ => return pattern.to_string(),
+    };
+    if Path::new(pattern).is_absolute() || GitSource::parse(pattern).is_some() {
+        return pattern.to_string();
+    }
+    match pattern.trim_start_matches("./") {
+        "" | "." => prefix,
+        pattern => format!("{}/{}", prefix, pattern),
+    }
+}
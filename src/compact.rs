@@ -0,0 +1,454 @@
+//! Compaction passes that shrink copied files without changing what the code does.
+//!
+//! Every pass is opt-in: stripping comments (optionally keeping doc comments
+//! and docstrings), dropping license headers already copied from an earlier
+//! file, omitting test code, and collapsing runs of blank lines. Comment
+//! syntax follows the file type, see [`comment_syntax`].
+
+use crate::symbols::{declaration, item_end, preamble_start, ItemKind, Language};
+use crate::utils::{comment_syntax, CommentSyntax};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Attributes marking a Rust item as test code.
+static RUST_TEST_ATTRIBUTE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^#\[(?:cfg\(test\)|(?:\w+::)*test\b)").expect("Invalid regex"));
+/// Python test functions and classes, as collected by pytest and unittest.
+static PY_TEST_FUNCTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^test(?:_|$)").expect("Invalid regex"));
+static PY_TEST_CLASS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Test(?:[A-Z_]|$)").expect("Invalid regex"));
+/// Base classes marking a Python class as a unittest test case.
+static PY_TEST_CASE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[(,]\s*(?:\w+\.)*\w*TestCase\s*[,)]").expect("Invalid regex"));
+/// Top-level test suites and cases of JavaScript test runners.
+static JS_TEST_CALL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:describe|it|test|suite)(?:\.\w+)*\s*\(").expect("Invalid regex"));
+
+/// Which compaction passes to run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactOptions {
+    pub strip_comments: bool,
+    /// Keep doc comments and docstrings when stripping comments.
+    pub keep_doc_comments: bool,
+    pub collapse_blank_lines: bool,
+    /// Drop license headers identical to one in an earlier file.
+    pub strip_license_headers: bool,
+    /// Omit test modules and functions.
+    pub strip_tests: bool,
+}
+
+impl CompactOptions {
+    /// Every pass, doc comments included.
+    pub fn all() -> Self {
+        CompactOptions {
+            strip_comments: true,
+            keep_doc_comments: false,
+            collapse_blank_lines: true,
+            strip_license_headers: true,
+            strip_tests: true,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.strip_comments
+            || self.collapse_blank_lines
+            || self.strip_license_headers
+            || self.strip_tests
+    }
+}
+
+/// Runs the compaction passes over the files of one copy.
+///
+/// License headers are remembered across files, so the first copy of a
+/// header is kept and later ones are dropped.
+#[derive(Debug, Default)]
+pub struct Compactor {
+    options: CompactOptions,
+    seen_headers: HashSet<String>,
+}
+
+impl Compactor {
+    pub fn new(options: CompactOptions) -> Self {
+        Compactor {
+            options,
+            seen_headers: HashSet::new(),
+        }
+    }
+
+    /// Applies the enabled passes to `content`, the content of `path`.
+    pub fn compact(&mut self, path: &str, content: &str) -> String {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        let syntax = comment_syntax(ext);
+        let language = Language::from_path(path);
+
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        if let (true, Some(syntax)) = (self.options.strip_license_headers, &syntax) {
+            lines = self.strip_license_header(lines, syntax);
+        }
+        if let (true, Some(language)) = (self.options.strip_tests, language) {
+            lines = strip_tests(lines, language, is_python_test_file(path));
+        }
+        if let (true, Some(syntax)) = (self.options.strip_comments, &syntax) {
+            lines = strip_comments(&lines, syntax, language, self.options.keep_doc_comments);
+        }
+        if self.options.collapse_blank_lines {
+            lines = collapse_blank_lines(lines);
+        }
+
+        let mut compacted = lines.join("\n");
+        if content.ends_with('\n') && !compacted.is_empty() {
+            compacted.push('\n');
+        }
+        compacted
+    }
+
+    /// Drops the leading comment block of `lines` when it is a license header
+    /// that was already seen, along with the blank lines after it.
+    fn strip_license_header(
+        &mut self,
+        mut lines: Vec<String>,
+        syntax: &CommentSyntax,
+    ) -> Vec<String> {
+        let start = usize::from(lines.first().is_some_and(|line| line.starts_with("#!")));
+        let end = start + leading_comment_len(&lines[start..], syntax);
+        let header = &lines[start..end];
+        let text = header.join("\n").to_lowercase();
+        if !["license", "copyright", "spdx-license-identifier"]
+            .iter()
+            .any(|word| text.contains(word))
+        {
+            return lines;
+        }
+        // Years and spacing differ between otherwise identical headers
+        let key: String = header
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .chars()
+            .filter(|c| !c.is_ascii_digit())
+            .collect();
+        if self.seen_headers.insert(key) {
+            return lines;
+        }
+        let blank = lines[end..]
+            .iter()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        lines.drain(start..end + blank);
+        lines
+    }
+}
+
+/// Number of lines at the start of `lines` that are comments.
+fn leading_comment_len(lines: &[String], syntax: &CommentSyntax) -> usize {
+    let mut count = 0;
+    let mut block_end: Option<&str> = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(end) = block_end {
+            if trimmed.contains(end) {
+                block_end = None;
+            }
+        } else if let Some((open, close)) =
+            syntax.block.filter(|(open, _)| trimmed.starts_with(open))
+        {
+            if !trimmed[open.len()..].contains(close) {
+                block_end = Some(close);
+            }
+        } else if !syntax.line.iter().any(|marker| trimmed.starts_with(marker)) {
+            break;
+        }
+        count += 1;
+    }
+    count
+}
+
+/// Whether `path` is a Python test module, `test_*.py` or `*_test.py`.
+fn is_python_test_file(path: &str) -> bool {
+    let name = Path::new(path)
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py"))
+}
+
+/// Whether the item declared on `lines[start]` is test code.
+///
+/// Python functions and classes are only recognized by their names in
+/// `test_file`s; unittest test cases are recognized anywhere.
+fn is_test_item(
+    lines: &[&str],
+    start: usize,
+    preamble: usize,
+    language: Language,
+    test_file: bool,
+) -> bool {
+    let Some(decl) = declaration(lines[start], language, false) else {
+        return false;
+    };
+    match language {
+        Language::Rust => lines[preamble..start]
+            .iter()
+            .any(|line| RUST_TEST_ATTRIBUTE.is_match(line.trim())),
+        Language::Python => match decl.kind {
+            ItemKind::Function => test_file && PY_TEST_FUNCTION.is_match(&decl.name),
+            ItemKind::Container => {
+                PY_TEST_CASE.is_match(lines[start])
+                    || (test_file && PY_TEST_CLASS.is_match(&decl.name))
+            }
+            ItemKind::Other => false,
+        },
+        Language::Go => {
+            decl.kind == ItemKind::Function
+                && ((["Test", "Benchmark", "Fuzz"]
+                    .iter()
+                    .any(|prefix| decl.name.starts_with(prefix))
+                    && lines[start].contains("*testing."))
+                    || (decl.name.starts_with("Example") && lines[start].contains("()")))
+        }
+        Language::TypeScript => false,
+    }
+}
+
+/// Omits test modules, functions and suites, with their doc comments and attributes.
+fn strip_tests(lines: Vec<String>, language: Language, test_file: bool) -> Vec<String> {
+    let borrowed: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let mut kept = Vec::new();
+    // First line after the last omitted item
+    let mut floor = 0;
+    let mut i = 0;
+    while i < borrowed.len() {
+        let line = borrowed[i];
+        let preamble = preamble_start(&borrowed, language, i).max(floor);
+        let is_test = match language {
+            Language::TypeScript => JS_TEST_CALL.is_match(line),
+            _ if line.trim_start().starts_with(['#', '@', '/']) => false,
+            _ => is_test_item(&borrowed, i, preamble, language, test_file),
+        };
+        if !is_test {
+            kept.push(line.to_string());
+            i += 1;
+            continue;
+        }
+        // The doc comments and attributes above the item were already kept
+        kept.truncate(kept.len() - (i - preamble));
+        i = item_end(&borrowed, i, language) + 1;
+        floor = i;
+    }
+    kept
+}
+
+/// Where the scanner of [`strip_comments`] is.
+enum State {
+    Code,
+    /// Inside a string closed by `end`; dropped unless `keep`.
+    Str {
+        end: String,
+        escapes: bool,
+        keep: bool,
+    },
+    /// Inside a block comment closed by `end`.
+    Block {
+        end: &'static str,
+        keep: bool,
+    },
+}
+
+/// Removes comments, and docstrings unless `keep_doc`, leaving strings intact.
+///
+/// Lines that held nothing but comments are dropped entirely.
+fn strip_comments(
+    lines: &[String],
+    syntax: &CommentSyntax,
+    language: Option<Language>,
+    keep_doc: bool,
+) -> Vec<String> {
+    let is_doc = |rest: &str| {
+        syntax.doc.iter().any(|prefix| rest.starts_with(prefix))
+            && !rest.starts_with("/**/")
+            && !rest.starts_with("////")
+    };
+    let rust = language == Some(Language::Rust);
+    let mut state = State::Code;
+    let mut kept_lines: Vec<String> = Vec::new();
+    // Last line with code on it, to recognize Python docstrings
+    let mut last_code = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let mut kept = String::new();
+        let mut removed = false;
+        let mut j = 0;
+        while j < line.len() {
+            let rest = &line[j..];
+            let c = rest.chars().next().unwrap_or_default();
+            match &mut state {
+                State::Block { end, keep } => {
+                    let found = rest.find(*end);
+                    let close = found.map_or(line.len(), |pos| j + pos + end.len());
+                    if *keep {
+                        kept.push_str(&line[j..close]);
+                    } else {
+                        removed = true;
+                        if line[close..].starts_with(char::is_whitespace) {
+                            kept.truncate(kept.trim_end().len());
+                        }
+                    }
+                    if found.is_some() {
+                        state = State::Code;
+                    }
+                    j = close;
+                    continue;
+                }
+                State::Str { end, escapes, keep } => {
+                    let (taken, closed) = if *escapes && c == '\\' {
+                        let next = rest[1..].chars().next().map_or(0, char::len_utf8);
+                        (1 + next, false)
+                    } else if rest.starts_with(end.as_str()) {
+                        (end.len(), true)
+                    } else {
+                        (c.len_utf8(), false)
+                    };
+                    if *keep {
+                        kept.push_str(&rest[..taken]);
+                    } else {
+                        removed = true;
+                    }
+                    if closed {
+                        state = State::Code;
+                    }
+                    j += taken;
+                    continue;
+                }
+                State::Code => {}
+            }
+
+            if let Some((open, close)) = syntax.block.filter(|(open, _)| rest.starts_with(open)) {
+                let keep = keep_doc && is_doc(rest);
+                if keep {
+                    kept.push_str(open);
+                } else {
+                    removed = true;
+                }
+                state = State::Block { end: close, keep };
+                j += open.len();
+                continue;
+            }
+            let line_comment = syntax.line.iter().any(|marker| {
+                rest.starts_with(marker)
+                    // `#` only starts a comment at the start of a word, as in `$#` or `a#b`
+                    && (*marker != "#" || kept.is_empty() || kept.ends_with(char::is_whitespace))
+            });
+            if line_comment {
+                let shebang = index == 0 && j == 0 && rest.starts_with("#!");
+                if shebang || keep_doc && is_doc(rest) {
+                    kept.push_str(rest);
+                } else {
+                    removed = true;
+                }
+                break;
+            }
+
+            let triple = ["\"\"\"", "'''"].into_iter().find(|q| rest.starts_with(q));
+            if let Some(quote) = triple {
+                let docstring = language == Some(Language::Python)
+                    && kept.trim().is_empty()
+                    && (last_code.is_empty() || last_code.ends_with(':'));
+                let keep = keep_doc || !docstring;
+                if keep {
+                    kept.push_str(quote);
+                } else {
+                    removed = true;
+                }
+                state = State::Str {
+                    end: quote.to_string(),
+                    escapes: true,
+                    keep,
+                };
+                j += quote.len();
+                continue;
+            }
+            if rust && c == 'r' {
+                let after_ident = kept.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+                let hashes = rest[1..].chars().take_while(|&c| c == '#').count();
+                if !after_ident && rest[1 + hashes..].starts_with('"') {
+                    kept.push_str(&rest[..2 + hashes]);
+                    state = State::Str {
+                        end: format!("\"{}", "#".repeat(hashes)),
+                        escapes: false,
+                        keep: true,
+                    };
+                    j += 2 + hashes;
+                    continue;
+                }
+            }
+            if rust && c == '\'' {
+                // Char literals, not lifetimes
+                let literal = match rest[1..].chars().next() {
+                    Some('\\') => rest[2..].find('\'').map(|pos| pos + 3),
+                    Some(ch) if rest[1 + ch.len_utf8()..].starts_with('\'') => {
+                        Some(2 + ch.len_utf8())
+                    }
+                    _ => None,
+                };
+                let taken = literal.unwrap_or(1);
+                kept.push_str(&rest[..taken]);
+                j += taken;
+                continue;
+            }
+            if matches!(c, '"' | '\'' | '`') {
+                kept.push(c);
+                state = State::Str {
+                    end: c.to_string(),
+                    escapes: true,
+                    keep: true,
+                };
+                j += 1;
+                continue;
+            }
+            kept.push(c);
+            j += c.len_utf8();
+        }
+
+        // Quotes are often apostrophes; don't let one swallow the rest of the file
+        if matches!(&state, State::Str { end, .. } if end == "'") {
+            state = State::Code;
+        }
+        if removed {
+            let trimmed = kept.trim_end();
+            if trimmed.trim_start().is_empty() {
+                continue;
+            }
+            kept.truncate(trimmed.len());
+        }
+        let shebang = index == 0 && kept.starts_with("#!");
+        if !kept.trim().is_empty() && !shebang && matches!(state, State::Code) {
+            last_code = kept.trim_end().to_string();
+        }
+        kept_lines.push(kept);
+    }
+    kept_lines
+}
+
+/// Collapses runs of blank lines into one and trims blank lines at both ends.
+fn collapse_blank_lines(lines: Vec<String>) -> Vec<String> {
+    let mut collapsed: Vec<String> = Vec::with_capacity(lines.len());
+    for line in lines {
+        let blank = line.trim().is_empty();
+        if blank && collapsed.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        collapsed.push(if blank { String::new() } else { line });
+    }
+    if collapsed.last().is_some_and(|last| last.is_empty()) {
+        collapsed.pop();
+    }
+    collapsed
+}
@@ -1,26 +1,307 @@
+use crate::bundle::project_root;
+use crate::compact::{CompactOptions, Compactor};
 use crate::errors::ClipboardError;
-use crate::ignore::IgnorePatterns;
-use crate::reporting::print_stats;
-use crate::utils::{expand_patterns, format_content, read_file_content};
-use arboard::Clipboard;
+use crate::filter::{ContentFilter, SkipReason};
+use crate::git::{changed_files, file_diff, ChangeScope};
+use crate::grep::{context_regions, matching_lines, DEFAULT_GREP_CONTEXT};
+use crate::ignore::{IgnorePatterns, WalkOptions};
+use crate::imports::follow_imports;
+use crate::json::{write_records, HeaderRecord, OutputFormat, SkippedRecord};
+use crate::models::{ModelInfo, ModelRegistry};
+use crate::notebook::{is_notebook, Notebook, NotebookOptions};
+use crate::packing::{pack, PackCandidate, PackDecision};
+use crate::picker::{pick_files, PickerState};
+use crate::prompt::{default_prompts_dir, load_prompt, parse_prompt_var, render_prompt};
+use crate::redact::{RedactionOptions, Redactor};
+use crate::remote::{checkout, default_cache_dir, GitSource};
+use crate::reporting::{report_file_stats, FileStats, StatsFormat, StatsOptions};
+use crate::search::{SearchHit, SearchIndex, DEFAULT_QUERY_TOP, INDEX_FILE};
+use crate::selector::{parse_selector, Selection};
+use crate::sink::{build_sink, ClipboardBackend, OutputSink, SinkKind};
+use crate::symbols::Language;
+use crate::template::{render, OutputTemplate, TokenCounter};
+use crate::usages::{find_usages, UsageOptions};
+use crate::utils::{
+    expand_patterns_with_options, format_diff_at, format_regions_at, format_snippet_at,
+    normalize_path, pattern_matches, read_file_bytes, read_file_content, FormatOptions,
+};
+use crate::xml::XmlAttribute;
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::path::{PathBuf};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
-use tiktoken_rs::get_bpe_from_model;
 use tracing::{debug, info, trace, warn};
 
+/// Width of the line numbers of excerpts, which are always numbered.
+const EXCERPT_LINE_DIGITS: usize = 4;
+
 #[derive(Debug, Clone)]
 pub struct ClipboardCopierConfig {
     pub no_markdown: bool,
     pub line_number: Option<usize>,
     pub prefix: String,
     pub model: String,
+    /// Models file extending the built-in model registry; the user config file by default.
+    pub models_file: Option<PathBuf>,
     pub no_stats: bool,
+    /// Format, order and depth of the statistics.
+    pub stats: StatsOptions,
+    /// Only compute and print the statistics; nothing is written to the sink.
+    pub stats_only: bool,
     pub filename_format: String,
     pub first_line: String,
     pub xml: bool,
+    /// Template preset name or template file; replaces `filename_format` and `xml`.
+    pub template: Option<String>,
+    /// Metadata attributes added to XML `<file>` elements.
+    pub xml_attributes: Vec<XmlAttribute>,
+    /// Write JSON records instead of rendering the template.
+    pub format: OutputFormat,
     pub ignore_patterns: Option<Vec<String>>,
+    pub no_ignore_files: bool,
+    pub tracked_only: bool,
+    /// Token budget for the copied content; files that do not fit are omitted.
+    pub max_tokens: Option<usize>,
+    /// Copy files that look binary, minified, generated or duplicated.
+    pub no_content_filters: bool,
+    /// Skip walked files larger than this many bytes.
+    pub max_file_bytes: Option<u64>,
+    /// Skip walked files with more tokens than this.
+    pub max_file_tokens: Option<usize>,
+    /// Copy outlines (imports, types and signatures) of supported source files.
+    pub outline: bool,
+    /// Compaction passes applied to whole files.
+    pub compact: CompactOptions,
+    /// Whether and how Jupyter notebooks are rendered as cells.
+    pub notebook: NotebookOptions,
+    /// Choose the files to copy in an interactive picker.
+    pub interactive: bool,
+    /// Add the local files imported by the copied files, this many levels deep.
+    pub follow_imports: Option<usize>,
+    /// Copy the references to these symbols instead of whole files.
+    pub usages: UsageOptions,
+    /// Only copy the regions of files around the lines matching this regex.
+    pub grep: Option<String>,
+    /// Lines copied above and below each match of `grep`.
+    pub grep_context: usize,
+    /// Copy the files that best match this natural-language query.
+    pub query: Option<String>,
+    /// Number of best matching files copied for `query`.
+    pub query_top: usize,
+    /// Copy secrets as they are instead of replacing them with placeholders.
+    pub no_redact: bool,
+    /// Extra redaction rules written as `name=regex`.
+    pub redact_rules: Vec<String>,
+    /// Patterns of values that are never redacted.
+    pub redact_allowlist: Vec<String>,
+    /// Prompt name or file to wrap the copied files in.
+    pub prompt: Option<String>,
+    /// Fills the `{$TASK}` and `{$CODING_TASK}` prompt variables.
+    pub task: Option<String>,
+    /// Extra prompt variables written as `key=value`.
+    pub prompt_vars: Vec<String>,
+    /// Where named prompts are looked up; the user config directory by default.
+    pub prompts_dir: Option<PathBuf>,
+    /// Only copy files changed in this git scope.
+    pub change_scope: Option<ChangeScope>,
+    pub diff_mode: DiffMode,
+    pub sink: SinkKind,
+    /// Output file for the file sink.
+    pub output: Option<PathBuf>,
+    pub clipboard_backend: ClipboardBackend,
+    /// Where mirrors of git sources are cached; the user cache directory by default.
+    pub git_cache_dir: Option<PathBuf>,
+}
+
+/// Whether to emit each file's diff alongside or instead of its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DiffMode {
+    #[default]
+    None,
+    WithContent,
+    Only,
+}
+
+impl Default for ClipboardCopierConfig {
+    fn default() -> Self {
+        Self {
+            no_markdown: false,
+            line_number: None,
+            prefix: "|".to_string(),
+            model: "gpt-4o".to_string(),
+            models_file: None,
+            no_stats: false,
+            stats: StatsOptions::default(),
+            stats_only: false,
+            filename_format: "MarkdownHeading".to_string(),
+            first_line: "# Relevant Code\n".to_string(),
+            xml: false,
+            template: None,
+            xml_attributes: Vec::new(),
+            format: OutputFormat::Text,
+            ignore_patterns: None,
+            no_ignore_files: false,
+            tracked_only: false,
+            max_tokens: None,
+            no_content_filters: false,
+            max_file_bytes: None,
+            max_file_tokens: None,
+            outline: false,
+            compact: CompactOptions::default(),
+            notebook: NotebookOptions::default(),
+            interactive: false,
+            follow_imports: None,
+            usages: UsageOptions::default(),
+            grep: None,
+            grep_context: DEFAULT_GREP_CONTEXT,
+            query: None,
+            query_top: DEFAULT_QUERY_TOP,
+            no_redact: false,
+            redact_rules: Vec::new(),
+            redact_allowlist: Vec::new(),
+            prompt: None,
+            task: None,
+            prompt_vars: Vec::new(),
+            prompts_dir: None,
+            change_scope: None,
+            diff_mode: DiffMode::None,
+            sink: SinkKind::Clipboard,
+            output: None,
+            clipboard_backend: ClipboardBackend::Auto,
+            git_cache_dir: None,
+        }
+    }
+}
+
+impl ClipboardCopierConfig {
+    pub fn format_options(&self) -> FormatOptions {
+        FormatOptions {
+            no_markdown: self.no_markdown,
+            line_number: self.line_number,
+            prefix: self.prefix.clone(),
+            filename_format: self.filename_format.clone(),
+            xml: self.xml,
+            json: self.format.is_json(),
+            outline: self.outline,
+            template: None,
+            token_counter: None,
+            source_path: None,
+        }
+    }
+}
+
+/// Formats the line ranges of `content` named by `selections`, numbering them from `index`.
+///
+/// Returns the formatted snippets and a comma-separated list of the ranges.
+fn format_selections(
+    content: &str,
+    file: &str,
+    selections: &[Selection],
+    index: &mut usize,
+    options: &FormatOptions,
+) -> Result<(String, String), ClipboardError> {
+    let mut formatted_content = String::new();
+    let mut ranges = Vec::new();
+    for selection in selections {
+        let Some(range) = selection.resolve(content) else {
+            warn!("Selection {:?} was not found in {}", selection, file);
+            continue;
+        };
+        let snippet = range.slice(content).join("\n");
+        let label = selection.label(file, range);
+        formatted_content.push_str(&format_snippet_at(
+            &snippet,
+            file,
+            range.start,
+            Some(&label),
+            *index,
+            options,
+        )?);
+        *index += 1;
+        ranges.push(range.to_string());
+    }
+    Ok((formatted_content, ranges.join(",")))
+}
+
+/// Keys `selections` by the files their patterns expanded to, so a selector on
+/// a directory or glob applies to every file it matches.
+fn spread_selections(
+    selections: HashMap<String, Vec<Selection>>,
+    file_list: &[String],
+) -> Result<HashMap<String, Vec<Selection>>, ClipboardError> {
+    let mut spread: HashMap<String, Vec<Selection>> = HashMap::new();
+    for (pattern, pattern_selections) in selections {
+        if Path::new(&pattern).is_file() {
+            spread.entry(pattern).or_default().extend(pattern_selections);
+            continue;
+        }
+        let mut matched = false;
+        for file in file_list {
+            if pattern_matches(&pattern, file)? {
+                matched = true;
+                spread
+                    .entry(file.clone())
+                    .or_default()
+                    .extend(pattern_selections.iter().cloned());
+            }
+        }
+        if !matched {
+            warn!("No files match {}; ignoring its selection", pattern);
+        }
+    }
+    Ok(spread)
+}
+
+/// A file read and formatted for copying, before budget packing.
+struct CopiedFile {
+    relative_path: PathBuf,
+    formatted_content: String,
+    token_count: usize,
+    /// Formatted outline and its token count, for languages that support one.
+    outline: Option<(String, usize)>,
+    explicit: bool,
+    /// Position in the `--query` ranking, best first.
+    rank: Option<usize>,
+    /// A one-line placeholder for a file that was skipped.
+    stub: bool,
+    /// Number of secrets redacted per rule.
+    redactions: BTreeMap<String, usize>,
+    /// Tokens removed by compaction.
+    compacted_tokens: usize,
+    /// Why the file was added, such as the file importing it.
+    notes: Vec<String>,
+}
+
+/// The files chosen for a copy, with what the selection learned about them.
+struct SelectedFiles {
+    files: Vec<String>,
+    /// Line ranges to copy instead of whole files.
+    selections: HashMap<String, Vec<Selection>>,
+    /// Files named on the command line or chosen in the picker.
+    explicit: HashSet<String>,
+    /// Positions in the `--query` ranking, best first.
+    ranks: HashMap<String, usize>,
+    /// Why files were added, such as the file importing them.
+    notes: HashMap<String, Vec<String>>,
+    /// The change scope, resolved against the base path.
+    change_scope: Option<ChangeScope>,
+}
+
+/// Files rendered for a copy, and the files skipped with their reasons.
+#[derive(Default)]
+struct RenderedFiles {
+    copied: Vec<CopiedFile>,
+    skipped: Vec<(PathBuf, SkipReason)>,
+}
+
+/// The content and statistics of the files that made it into the copy.
+struct PackedFiles {
+    content: String,
+    files: usize,
+    tokens: usize,
+    stats: HashMap<PathBuf, FileStats>,
+    skipped: Vec<SkippedRecord>,
 }
 
 #[async_trait]
@@ -34,6 +315,7 @@ pub struct BasicClipboardCopier {
     base_path: String,
     temp_dir: Option<TempDir>,
     ignore_patterns: IgnorePatterns,
+    sink: Option<Box<dyn OutputSink>>,
 }
 
 impl BasicClipboardCopier {
@@ -43,176 +325,1052 @@ impl BasicClipboardCopier {
             config, 
             base_path, 
             temp_dir,
-            ignore_patterns
+            ignore_patterns,
+            sink: None,
         }
     }
+
+    /// Writes to `sink` instead of the one configured by `config.sink`.
+    pub fn with_sink(mut self, sink: Box<dyn OutputSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
 }
 
-#[async_trait]
-impl ClipboardCopier for BasicClipboardCopier {
-    async fn copy_files_to_clipboard(&self, files: Vec<String>) -> Result<(), ClipboardError> {
+impl BasicClipboardCopier {
+    /// Path of `file` relative to the base path.
+    fn relative_path<'a>(&self, file: &'a str) -> &'a str {
+        let relative_path = file.strip_prefix(&self.base_path).unwrap_or(file);
+        relative_path.strip_prefix("/").unwrap_or(relative_path)
+    }
+
+    /// Lets the user choose among the files of `file_list` that are not
+    /// ignored and pass the content filters.
+    async fn pick_files(
+        &self,
+        file_list: Vec<String>,
+        explicit_files: &HashSet<String>,
+        tokenizer: &TokenCounter,
+    ) -> Result<Vec<String>, ClipboardError> {
+        let mut content_filter = ContentFilter::new(
+            !self.config.no_content_filters,
+            self.config.max_file_bytes,
+        );
+        let mut candidates = Vec::new();
+        let mut files_by_path = HashMap::new();
+        for file in file_list {
+            let relative_path = self.relative_path(&file);
+            if self.ignore_patterns.should_ignore(relative_path) {
+                continue;
+            }
+            let explicit = explicit_files.contains(&file);
+            let size = tokio::fs::metadata(&file).await.map_or(0, |m| m.len());
+            if let Err(reason) = content_filter.check_size(size, explicit) {
+                debug!("Not offering {}: {}", file, reason);
+                continue;
+            }
+            let bytes = match read_file_bytes(&file).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    debug!("Not offering {}: {}", file, e);
+                    continue;
+                }
+            };
+            match content_filter.check(relative_path, &bytes, explicit) {
+                Ok(content) => {
+                    let relative_path = PathBuf::from(relative_path);
+                    candidates.push((relative_path.clone(), tokenizer.count(&content)));
+                    files_by_path.insert(relative_path, file);
+                }
+                Err(reason) => debug!("Not offering {}: {}", file, reason),
+            }
+        }
+
+        let mut state = PickerState::new(candidates)?;
+        state.budget = self.config.max_tokens;
+        let chosen = tokio::task::spawn_blocking(move || pick_files(state))
+            .await
+            .map_err(|e| ClipboardError::TaskJoinError(e.to_string()))??;
+        let Some(chosen) = chosen else {
+            return Err(ClipboardError::Cancelled("No files were selected".to_string()));
+        };
+        Ok(chosen
+            .into_iter()
+            .filter_map(|path| files_by_path.remove(&path))
+            .collect())
+    }
+
+    /// Keeps the files of `file_list` that reference the usage symbols and
+    /// selects the references in them, noting how many each file has.
+    async fn select_usages(
+        &self,
+        file_list: Vec<String>,
+        selections: &mut HashMap<String, Vec<Selection>>,
+        file_notes: &mut HashMap<String, Vec<String>>,
+    ) -> Vec<String> {
+        let options = &self.config.usages;
+        let mut referencing = Vec::new();
+        for file in file_list {
+            let Some(language) = Language::from_path(&file) else {
+                continue;
+            };
+            let content = match read_file_content(&file).await {
+                Ok(content) => content,
+                Err(e) => {
+                    debug!("Not searching {}: {}", file, e);
+                    continue;
+                }
+            };
+            let usages = find_usages(&content, language, options);
+            if usages.ranges.is_empty() {
+                continue;
+            }
+            let notes = file_notes.entry(file.clone()).or_default();
+            for (symbol, count) in usages.counts.into_iter().filter(|(_, count)| *count > 0) {
+                let plural = if count == 1 { "" } else { "s" };
+                notes.push(format!("{} reference{} to {}", count, plural, symbol));
+            }
+            selections.entry(file.clone()).or_default().extend(
+                usages.ranges.into_iter().map(|range| Selection::Lines {
+                    start: range.start,
+                    end: Some(range.end),
+                }),
+            );
+            referencing.push(file);
+        }
+        info!(
+            "Found references to {} in {} files",
+            options.symbols.join(", "),
+            referencing.len()
+        );
+        referencing
+    }
+
+    /// Ranks the non-ignored files of `file_list` against `query` and keeps the
+    /// best ones, refreshing the search index of the project holding `patterns` first.
+    ///
+    /// Files are indexed as they would be copied: files the content filters
+    /// reject are left out, and secrets are redacted even with `no_redact`.
+    fn rank_files(
+        &self,
+        query: &str,
+        patterns: &[String],
+        file_list: Vec<String>,
+    ) -> Result<Vec<SearchHit>, ClipboardError> {
+        let config = &self.config;
+        let content_filter =
+            ContentFilter::new(!config.no_content_filters, config.max_file_bytes);
+        let redaction = RedactionOptions {
+            rules: config.redact_rules.clone(),
+            allowlist: config.redact_allowlist.clone(),
+        };
+        let mut redactor = Redactor::new(&redaction)?;
+        let settings = format!(
+            "filters={} max_bytes={:?} redaction={:?}",
+            !config.no_content_filters, config.max_file_bytes, redaction
+        );
+        let file_list: Vec<String> = file_list
+            .into_iter()
+            .filter(|file| !self.ignore_patterns.should_ignore(self.relative_path(file)))
+            .collect();
+        let start = Path::new(&self.base_path).join(patterns.first().map_or(".", String::as_str));
+        let root = match start.is_dir() {
+            true => project_root(&start),
+            false => project_root(start.parent().unwrap_or(&start)),
+        };
+        let index_path = root.join(INDEX_FILE);
+        let mut index = SearchIndex::load(&index_path).for_settings(&settings);
+        let changed = index.update_with(&root, &file_list, |path, bytes| {
+            match content_filter.check_content(path, bytes, false) {
+                Ok(content) => Some(redactor.redact(path, &content).0),
+                Err(reason) => {
+                    debug!("Not indexing {}: {}", path, reason);
+                    None
+                }
+            }
+        });
+        debug!("Indexed {} changed files of {}", changed, index.len());
+        if changed > 0 {
+            if let Err(e) = index.save(&index_path) {
+                warn!("Failed to save the search index {:?}: {}", index_path, e);
+            }
+        }
+
+        let mut hits = index.search(&root, query, &file_list);
+        info!("Query matched {} of {} files", hits.len(), file_list.len());
+        hits.truncate(self.config.query_top);
+        Ok(hits)
+    }
+
+    /// Returns the files changed in `scope` that `patterns` name.
+    ///
+    /// Without any file patterns, every changed file is selected.
+    fn select_changed_files(
+        &self,
+        patterns: &[String],
+        scope: &ChangeScope,
+    ) -> Result<Vec<String>, ClipboardError> {
+        let base_path = Path::new(&self.base_path);
+        let changed = changed_files(base_path, scope)?;
+        info!("Found {} changed files", changed.len());
+
+        if patterns.iter().all(|p| p.is_empty()) {
+            return Ok(changed
+                .iter()
+                .map(|f| base_path.join(f).to_string_lossy().into_owned())
+                .collect());
+        }
+
+        // Patterns are matched against the changed paths, so deleted files are kept
+        let mut files = Vec::new();
+        for file in &changed {
+            for pattern in patterns {
+                let path = if Path::new(pattern).is_absolute() {
+                    base_path.join(file)
+                } else {
+                    file.clone()
+                };
+                let path = path.to_string_lossy().replace('\\', "/");
+                if pattern_matches(pattern, &path)? {
+                    files.push(path);
+                    break;
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Expands the patterns of `files` into the files to copy, narrows them by
+    /// `--query`, the picker and `--usages`, and adds the files they import.
+    async fn select_files(
+        &self,
+        files: Vec<String>,
+        tokenizer: &TokenCounter,
+    ) -> Result<SelectedFiles, ClipboardError> {
         let copier_config = &self.config;
+        let excerpts = copier_config.usages.is_enabled() || copier_config.grep.is_some();
+        // The picker and searches start from the whole workspace when no patterns are given
+        let whole_workspace =
+            copier_config.interactive || excerpts || copier_config.query.is_some();
+        let files: Vec<String> =
+            if whole_workspace && copier_config.change_scope.is_none() {
+                files
+                    .into_iter()
+                    .map(|f| if f.is_empty() { ".".to_string() } else { f })
+                    .collect()
+            } else {
+                files
+            };
+        let mut selections: HashMap<String, Vec<Selection>> = HashMap::new();
+        let files: Vec<String> = files
+            .iter()
+            .map(|f| {
+                let selector = parse_selector(f);
+                if let Some(selection) = selector.selection {
+                    selections
+                        .entry(normalize_path(&selector.pattern))
+                        .or_default()
+                        .push(selection);
+                }
+                selector.pattern
+            })
+            .collect();
+
         debug!("Expanding file patterns");
-        let file_list =
-            expand_patterns(&files).map_err(|e| ClipboardError::IoError(e.to_string()))?;
+        let walk_options = WalkOptions {
+            respect_ignore_files: !copier_config.no_ignore_files,
+            tracked_only: copier_config.tracked_only,
+            ..WalkOptions::default()
+        };
+        let change_scope = match &copier_config.change_scope {
+            Some(scope) => Some(scope.resolve(Path::new(&self.base_path))?),
+            None => None,
+        };
+        let file_list = match &change_scope {
+            Some(scope) => self.select_changed_files(&files, scope)?,
+            None => expand_patterns_with_options(&files, &walk_options)?,
+        };
         debug!("Expanded file list: {:?}", file_list);
+        let mut selections = spread_selections(selections, &file_list)?;
+
+        let mut explicit_files: HashSet<String> = files
+            .iter()
+            .map(|f| normalize_path(f))
+            .filter(|f| Path::new(f).is_file())
+            .collect();
 
-        debug!("Initializing clipboard");
-        let mut clipboard =
-            Clipboard::new().map_err(|e| ClipboardError::ClipboardInitError(e.to_string()))?;
+        let mut file_notes: HashMap<String, Vec<String>> = HashMap::new();
+        let mut ranks: HashMap<String, usize> = HashMap::new();
+        let file_list = match &copier_config.query {
+            Some(query) => {
+                let hits = self.rank_files(query, &files, file_list)?;
+                let mut ranked = Vec::new();
+                for (rank, hit) in hits.into_iter().enumerate() {
+                    file_notes
+                        .entry(hit.file.clone())
+                        .or_default()
+                        .push(format!("query rank {} (score {:.2})", rank + 1, hit.score));
+                    ranks.insert(hit.file.clone(), rank);
+                    ranked.push(hit.file);
+                }
+                ranked
+            }
+            None => file_list,
+        };
+        let file_list = if copier_config.interactive {
+            let chosen = self
+                .pick_files(file_list, &explicit_files, tokenizer)
+                .await?;
+            explicit_files.extend(chosen.iter().cloned());
+            chosen
+        } else {
+            file_list
+        };
+        let file_list = if copier_config.usages.is_enabled() {
+            self.select_usages(file_list, &mut selections, &mut file_notes)
+                .await
+        } else {
+            file_list
+        };
+        let file_list = match copier_config.follow_imports {
+            Some(depth) => {
+                let mut file_list = file_list;
+                let imported = follow_imports(&file_list, depth);
+                info!("Following imports added {} files", imported.len());
+                for file in imported {
+                    let note = format!("imported by {}", self.relative_path(&file.importer));
+                    file_notes.entry(file.path.clone()).or_default().push(note);
+                    file_list.push(file.path);
+                }
+                file_list
+            }
+            None => file_list,
+        };
+
+        Ok(SelectedFiles {
+            files: file_list,
+            selections,
+            explicit: explicit_files,
+            ranks,
+            notes: file_notes,
+            change_scope,
+        })
+    }
+
+    /// Decides which of the `copied` files fit into `budget` tokens.
+    fn pack_files(&self, copied: &[CopiedFile], budget: usize) -> Vec<PackDecision> {
+        let candidates: Vec<PackCandidate> = copied
+            .iter()
+            .map(|f| {
+                let outline_tokens = f.outline.as_ref().map(|(_, tokens)| *tokens);
+                match outline_tokens {
+                    // In outline mode the outline is all there is to pack
+                    Some(tokens) if self.config.outline => PackCandidate {
+                        path: f.relative_path.clone(),
+                        tokens,
+                        outline_tokens: None,
+                        explicit: f.explicit,
+                        rank: f.rank,
+                    },
+                    _ => PackCandidate {
+                        path: f.relative_path.clone(),
+                        tokens: f.token_count,
+                        outline_tokens,
+                        explicit: f.explicit,
+                        rank: f.rank,
+                    },
+                }
+            })
+            .collect();
+        pack(&candidates, budget)
+    }
 
+    /// Joins the rendered files as `decisions` says, full or as outlines, and
+    /// collects the statistics of every file.
+    fn assemble_files(&self, rendered: RenderedFiles, decisions: Vec<PackDecision>) -> PackedFiles {
         let mut all_content = String::new();
+        let mut included_files = 0;
+        let mut included_tokens = 0;
+        let mut file_stats: HashMap<PathBuf, FileStats> = HashMap::new();
+        let mut skipped = skipped_records(&rendered.skipped);
+        for (copied_file, decision) in rendered.copied.into_iter().zip(decisions) {
+            let omitted = decision == PackDecision::Dropped;
+            if copied_file.stub {
+                // Stubs are reported with the skipped files
+                if !omitted {
+                    all_content.push_str(&copied_file.formatted_content);
+                }
+                continue;
+            }
+            let outlined = copied_file.outline.is_some()
+                && (decision == PackDecision::Outlined
+                    || (decision == PackDecision::Included && self.config.outline));
+            let (outline_content, outline_tokens) = match copied_file.outline {
+                Some((content, tokens)) => (Some(content), Some(tokens)),
+                None => (None, None),
+            };
+            let mut notes = copied_file.notes;
+            if omitted {
+                info!(
+                    "Omitting {} ({} tokens) to stay within the token budget",
+                    copied_file.relative_path.display(),
+                    copied_file.token_count
+                );
+                notes.push("over token budget".to_string());
+                skipped.push(SkippedRecord {
+                    path: copied_file.relative_path.to_string_lossy().into_owned(),
+                    reason: "over token budget".to_string(),
+                });
+            } else if decision == PackDecision::Outlined {
+                info!(
+                    "Outlining {} ({} tokens) to stay within the token budget",
+                    copied_file.relative_path.display(),
+                    copied_file.token_count
+                );
+                notes.push("outlined to fit token budget".to_string());
+            }
+            match outline_content {
+                Some(outline_content) if outlined => all_content.push_str(&outline_content),
+                _ if !omitted => all_content.push_str(&copied_file.formatted_content),
+                _ => {}
+            }
+            if !omitted {
+                included_files += 1;
+                included_tokens += match outline_tokens {
+                    Some(tokens) if outlined => tokens,
+                    _ => copied_file.token_count,
+                };
+            }
+            file_stats.insert(
+                copied_file.relative_path,
+                FileStats {
+                    tokens: copied_file.token_count,
+                    omitted,
+                    outline_tokens,
+                    outlined,
+                    notes,
+                    redactions: copied_file.redactions,
+                    compacted_tokens: copied_file.compacted_tokens,
+                    ..FileStats::default()
+                },
+            );
+        }
+        for (path, reason) in rendered.skipped {
+            file_stats.insert(
+                path,
+                FileStats {
+                    skipped: Some(reason),
+                    ..FileStats::default()
+                },
+            );
+        }
+        PackedFiles {
+            content: all_content,
+            files: included_files,
+            tokens: included_tokens,
+            stats: file_stats,
+            skipped,
+        }
+    }
+}
+
+/// How the files of a copy are rendered.
+struct RenderOptions<'a> {
+    format_options: &'a FormatOptions,
+    tokenizer: &'a TokenCounter,
+    grep: Option<&'a Regex>,
+    count_tokens: bool,
+    /// Also make outlines, for the stats and budget packing.
+    make_outlines: bool,
+    json: bool,
+}
 
-        let tokenizer = get_bpe_from_model(&copier_config.model)
-            .map_err(|e| ClipboardError::TokenizerError(e.to_string()))?;
-        let mut token_counts: HashMap<PathBuf, usize> = HashMap::new();
+/// Reads, filters and formats the selected files one at a time.
+struct FileRenderer<'a> {
+    copier: &'a BasicClipboardCopier,
+    options: &'a RenderOptions<'a>,
+    selected: SelectedFiles,
+    content_filter: ContentFilter,
+    redactor: Option<Redactor>,
+    compactor: Option<Compactor>,
+    /// Index of the next formatted snippet.
+    next_index: usize,
+    rendered: RenderedFiles,
+}
 
-        // If XML formatting is enabled, wrap all file contents within a root XML element
-        if copier_config.xml {
-            all_content.push_str("<files>\n");
+impl<'a> FileRenderer<'a> {
+    fn new(
+        copier: &'a BasicClipboardCopier,
+        options: &'a RenderOptions<'a>,
+        selected: SelectedFiles,
+    ) -> Result<Self, ClipboardError> {
+        let copier_config = &copier.config;
+        let content_filter = ContentFilter::new(
+            !copier_config.no_content_filters,
+            copier_config.max_file_bytes,
+        );
+        let redactor = if copier_config.no_redact {
+            None
+        } else {
+            Some(Redactor::new(&RedactionOptions {
+                rules: copier_config.redact_rules.clone(),
+                allowlist: copier_config.redact_allowlist.clone(),
+            })?)
+        };
+        let compactor = copier_config
+            .compact
+            .is_enabled()
+            .then(|| Compactor::new(copier_config.compact));
+        Ok(FileRenderer {
+            copier,
+            options,
+            selected,
+            content_filter,
+            redactor,
+            compactor,
+            next_index: 1,
+            rendered: RenderedFiles::default(),
+        })
+    }
+
+    /// Renders every selected file, in order.
+    async fn render_all(mut self) -> Result<RenderedFiles, ClipboardError> {
+        for file in std::mem::take(&mut self.selected.files) {
+            self.render_file(file).await?;
         }
+        Ok(self.rendered)
+    }
 
-        for file in file_list {
-            debug!("Processing file: {}", file);
-            if self.ignore_patterns.should_ignore(&file) {
-                debug!("Skipping file: {}", file);
-                continue;
+    /// Renders `file`, or records why it was skipped.
+    async fn render_file(&mut self, file: String) -> Result<(), ClipboardError> {
+        let copier = self.copier;
+        let copier_config = &copier.config;
+        let options = self.options;
+        debug!("Processing file: {}", file);
+        let relative_path = copier.relative_path(&file);
+        if copier.ignore_patterns.should_ignore(relative_path) {
+            debug!("Skipping file: {}", file);
+            return Ok(());
+        }
+        let full_options = FormatOptions {
+            source_path: Some(PathBuf::from(&file)),
+            ..options.format_options.clone()
+        };
+        let outline_options = FormatOptions {
+            outline: true,
+            ..full_options.clone()
+        };
+
+        let explicit = self.selected.explicit.contains(&file);
+        let mut formatted_content = String::new();
+        let mut outline_content: Option<String> = None;
+        let mut label = relative_path.to_string();
+        let diff = match (&self.selected.change_scope, copier_config.diff_mode) {
+            (Some(scope), DiffMode::WithContent | DiffMode::Only) => {
+                file_diff(Path::new(&copier.base_path), scope, Path::new(relative_path))?
             }
-            match read_file_content(&file).await {
-                Ok(content) => {
-                    let mut relative_path = file.strip_prefix(&self.base_path).unwrap_or(&file);
-                    relative_path = relative_path.strip_prefix("/").unwrap_or(relative_path);
+            _ => String::new(),
+        };
+        let include_content = copier_config.diff_mode != DiffMode::Only || diff.is_empty();
+        let mut index = self.next_index;
+        let mut compacted_tokens = 0;
+        let mut redactions: BTreeMap<String, usize> = BTreeMap::new();
+        let mut redact = |text: &str| match &mut self.redactor {
+            Some(redactor) => {
+                let (redacted, counts) = redactor.redact(relative_path, text);
+                for (rule, count) in counts {
+                    *redactions.entry(rule).or_insert(0) += count;
+                }
+                redacted
+            }
+            None => text.to_string(),
+        };
+
+        if include_content && Path::new(&file).is_file() {
+            // Oversized files are skipped without reading them
+            let size = tokio::fs::metadata(&file).await.map_or(0, |m| m.len());
+            if let Err(reason) = self.content_filter.check_size(size, explicit) {
+                info!("Skipping {}: {}", relative_path, reason);
+                self.rendered.skipped.push((PathBuf::from(relative_path), reason));
+                return Ok(());
+            }
+            match read_file_bytes(&file).await {
+                Ok(bytes) => {
                     debug!("Read content for file: {}", file);
-                    let formatted_content = format_content(
-                        &content,
-                        &relative_path,
-                        copier_config.no_markdown,
-                        copier_config.line_number,
-                        &copier_config.prefix,
-                        copier_config.filename_format.clone(),
-                        copier_config.xml,
-                    )?;
-                    trace!("Formatted content for file: {}", file);
-
-                    if copier_config.xml {
-                        all_content.push_str(&formatted_content);
+                    let content = match self.content_filter.check(relative_path, &bytes, explicit) {
+                        Ok(content) => redact(&content),
+                        Err(reason) => {
+                            info!("Skipping {}: {}", relative_path, reason);
+                            // Binary files leave a one-line stub so the reader knows they
+                            // exist; JSON headers already list them
+                            if reason == SkipReason::Binary && !options.json {
+                                let stub = format!("(binary file, {} bytes, not copied)", size);
+                                let stub_options = FormatOptions {
+                                    line_number: None,
+                                    ..full_options.clone()
+                                };
+                                let formatted_content = format_snippet_at(
+                                    &stub,
+                                    relative_path,
+                                    1,
+                                    None,
+                                    index,
+                                    &stub_options,
+                                )?;
+                                self.next_index = index + 1;
+                                self.rendered.copied.push(CopiedFile {
+                                    relative_path: PathBuf::from(relative_path),
+                                    token_count: options.tokenizer.count(&formatted_content),
+                                    formatted_content,
+                                    outline: None,
+                                    explicit: true,
+                                    rank: None,
+                                    stub: true,
+                                    redactions: BTreeMap::new(),
+                                    compacted_tokens: 0,
+                                    notes: Vec::new(),
+                                });
+                            }
+                            self.rendered.skipped.push((PathBuf::from(relative_path), reason));
+                            return Ok(());
+                        }
+                    };
+                    let regions = match options.grep {
+                        Some(regex) => {
+                            let matches = matching_lines(&content, regex);
+                            if matches.is_empty() {
+                                debug!("No matches in {}", relative_path);
+                                return Ok(());
+                            }
+                            let plural = if matches.len() == 1 { "" } else { "s" };
+                            self.selected.notes
+                                .entry(file.clone())
+                                .or_default()
+                                .push(format!("{} matching line{}", matches.len(), plural));
+                            let total = content.lines().count();
+                            Some(context_regions(&matches, copier_config.grep_context, total))
+                        }
+                        None => None,
+                    };
+                    // Selected line ranges and matches refer to the file as it is
+                    let whole_file =
+                        !self.selected.selections.contains_key(&file) && regions.is_none();
+                    let render_notebook = copier_config.notebook.render
+                        && whole_file
+                        && is_notebook(relative_path);
+                    let content = if render_notebook {
+                        match Notebook::parse(&content) {
+                            Ok(notebook) => notebook.render(&copier_config.notebook),
+                            Err(e) => {
+                                warn!("Copying {} as it is: {}", relative_path, e);
+                                content
+                            }
+                        }
                     } else {
-                        all_content.push_str(&formatted_content);
-                    }
-
-                    if !copier_config.no_stats {
-                        trace!("Encoding content to get token count for file: {}", file);
-                        let tokens = tokenizer.encode_ordinary(&formatted_content);
-                        let token_count = tokens.len();
-                        token_counts.insert(PathBuf::from(&relative_path), token_count);
-                        trace!("File {} has {} tokens", &file, token_count);
+                        content
+                    };
+                    let content = match &mut self.compactor {
+                        Some(compactor) if whole_file => {
+                            let compacted = compactor.compact(relative_path, &content);
+                            if options.count_tokens {
+                                compacted_tokens = options
+                                    .tokenizer
+                                    .count(&content)
+                                    .saturating_sub(options.tokenizer.count(&compacted));
+                            }
+                            compacted
+                        }
+                        _ => content,
+                    };
+                    let content = content.as_str();
+                    match (&regions, self.selected.selections.get(&file)) {
+                        (Some(regions), _) => {
+                            formatted_content.push_str(&format_regions_at(
+                                content,
+                                relative_path,
+                                regions,
+                                index,
+                                &full_options,
+                            )?);
+                            let ranges: Vec<String> =
+                                regions.iter().map(|region| region.to_string()).collect();
+                            label = format!("{}:{}", relative_path, ranges.join(","));
+                            index += 1;
+                        }
+                        (None, Some(file_selections)) => {
+                            let (snippets, ranges) = format_selections(
+                                content,
+                                relative_path,
+                                file_selections,
+                                &mut index,
+                                &full_options,
+                            )?;
+                            formatted_content.push_str(&snippets);
+                            label = format!("{}:{}", relative_path, ranges);
+                        }
+                        (None, None) => {
+                            formatted_content.push_str(&format_snippet_at(
+                                content,
+                                relative_path,
+                                1,
+                                None,
+                                index,
+                                &full_options,
+                            )?);
+                            if options.make_outlines
+                                && Language::from_path(relative_path).is_some()
+                            {
+                                outline_content = Some(format_snippet_at(
+                                    content,
+                                    relative_path,
+                                    1,
+                                    None,
+                                    index,
+                                    &outline_options,
+                                )?);
+                            }
+                            index += 1;
+                        }
                     }
                 }
                 Err(e) => {
                     warn!("Failed to read file {}: {}", &file, e);
+                    return Ok(());
                 }
             }
         }
+        if !diff.is_empty() {
+            let formatted_diff =
+                format_diff_at(&redact(&diff), relative_path, index, &full_options);
+            index += 1;
+            formatted_content.push_str(&formatted_diff);
+            if let Some(outline_content) = &mut outline_content {
+                outline_content.push_str(&formatted_diff);
+            }
+        }
+        if formatted_content.is_empty() {
+            debug!("Nothing to copy for file: {}", file);
+            return Ok(());
+        }
+        trace!("Formatted content for file: {}", file);
+
+        let mut token_count = 0;
+        if options.count_tokens {
+            trace!("Encoding content to get token count for file: {}", file);
+            token_count = options.tokenizer.count(&formatted_content);
+            trace!("File {} has {} tokens", &file, token_count);
+        }
+        if let Some(limit) = copier_config.max_file_tokens {
+            if token_count > limit && !explicit {
+                let reason = SkipReason::TooManyTokens {
+                    tokens: token_count,
+                    limit,
+                };
+                info!("Skipping {}: {}", label, reason);
+                self.rendered.skipped.push((PathBuf::from(label), reason));
+                return Ok(());
+            }
+        }
+        let outline = outline_content.map(|outline_content| {
+            let outline_tokens = if options.count_tokens {
+                options.tokenizer.count(&outline_content)
+            } else {
+                0
+            };
+            (outline_content, outline_tokens)
+        });
+
+        self.next_index = index;
+        self.rendered.copied.push(CopiedFile {
+            relative_path: PathBuf::from(label),
+            formatted_content,
+            token_count,
+            outline,
+            explicit,
+            rank: self.selected.ranks.get(&file).copied(),
+            stub: false,
+            redactions,
+            compacted_tokens,
+            notes: self.selected.notes.remove(&file).unwrap_or_default(),
+        });
+        Ok(())
+    }
+}
 
-        if copier_config.xml {
-            all_content.push_str("</files>\n");
+/// The header and footer around the copied files.
+struct Frame<'a> {
+    header: &'a str,
+    footer: &'a str,
+    model: &'a str,
+    format: OutputFormat,
+}
+
+impl Frame<'_> {
+    /// Wraps `content`, made of `files` files and `tokens` tokens, in the
+    /// header and footer, or in the JSON header record.
+    fn wrap(
+        &self,
+        files: usize,
+        tokens: usize,
+        skipped: Vec<SkippedRecord>,
+        content: &str,
+    ) -> String {
+        if self.format.is_json() {
+            let header = HeaderRecord {
+                model: self.model.to_string(),
+                files,
+                total_tokens: tokens,
+                skipped,
+            };
+            return write_records(self.format, &header, content);
         }
+        let (files, tokens) = (files.to_string(), tokens.to_string());
+        let vars = [("files", files.as_str()), ("tokens", tokens.as_str())];
+        format!(
+            "{}{}{}",
+            render(self.header, &vars),
+            content,
+            render(self.footer, &vars)
+        )
+    }
+}
 
-        let final_content = if copier_config.xml {
-            all_content
-        } else {
-            format!("{}{}", copier_config.first_line, all_content)
+/// Records of the files that were skipped, for the JSON header.
+fn skipped_records(skipped: &[(PathBuf, SkipReason)]) -> Vec<SkippedRecord> {
+    skipped
+        .iter()
+        .map(|(path, reason)| SkippedRecord {
+            path: path.to_string_lossy().into_owned(),
+            reason: reason.to_string(),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ClipboardCopier for BasicClipboardCopier {
+    async fn copy_files_to_clipboard(&self, files: Vec<String>) -> Result<(), ClipboardError> {
+        let copier_config = &self.config;
+        let json = copier_config.format.is_json();
+        if json && (copier_config.template.is_some() || copier_config.prompt.is_some()) {
+            return Err(ClipboardError::ConfigError(
+                "JSON output cannot be combined with a template or prompt".to_string(),
+            ));
+        }
+        let template = match &copier_config.template {
+            Some(spec) => OutputTemplate::load(spec, !copier_config.no_markdown)?,
+            None => OutputTemplate::from_format(
+                &copier_config.filename_format,
+                copier_config.xml,
+                !copier_config.no_markdown,
+            ),
+        }
+        .with_xml_attributes(&copier_config.xml_attributes);
+        let prompt = match &copier_config.prompt {
+            Some(name) => {
+                let prompts_dir = copier_config.prompts_dir.clone().or_else(default_prompts_dir);
+                Some(load_prompt(name, prompts_dir.as_deref())?)
+            }
+            None => None,
+        };
+        let mut prompt_vars = Vec::new();
+        if let Some(task) = &copier_config.task {
+            prompt_vars.push(("TASK".to_string(), task.clone()));
+            prompt_vars.push(("CODING_TASK".to_string(), task.clone()));
+        }
+        for var in &copier_config.prompt_vars {
+            prompt_vars.push(parse_prompt_var(var)?);
+        }
+
+        let registry = match &copier_config.models_file {
+            Some(path) => ModelRegistry::load(Some(path))?,
+            None => ModelRegistry::global().clone(),
+        };
+        let model = registry.resolve(&copier_config.model);
+        let tokenizer = model.tokenizer.encoding().counter()?;
+        let grep = match &copier_config.grep {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                ClipboardError::RegexError(format!("Invalid grep pattern {}: {}", pattern, e))
+            })?),
+            None => None,
         };
+        let excerpts = copier_config.usages.is_enabled() || grep.is_some();
+        let format_options = FormatOptions {
+            outline: false,
+            // Excerpts keep the line numbers of the files they come from
+            line_number: copier_config
+                .line_number
+                .or(excerpts.then_some(EXCERPT_LINE_DIGITS)),
+            token_counter: (json || template.uses("tokens")).then(|| tokenizer.clone()),
+            template: Some(template.clone()),
+            ..copier_config.format_options()
+        };
+
+        let stats_to_stdout =
+            !copier_config.no_stats && copier_config.stats.format == StatsFormat::Json;
+        let sink_to_stdout = copier_config.sink == SinkKind::Stdout
+            || (copier_config.sink == SinkKind::Both && copier_config.output.is_none());
+        if stats_to_stdout && sink_to_stdout && self.sink.is_none() && !copier_config.stats_only {
+            return Err(ClipboardError::ConfigError(
+                "JSON statistics are written to stdout; write the output elsewhere".to_string(),
+            ));
+        }
+
+        let built_sink;
+        let sink = match &self.sink {
+            Some(sink) => Some(sink.as_ref()),
+            None if copier_config.stats_only => None,
+            None => {
+                built_sink = build_sink(
+                    copier_config.sink,
+                    copier_config.output.clone(),
+                    copier_config.clipboard_backend,
+                )?;
+                Some(built_sink.as_ref())
+            }
+        };
+
+        let selected = self.select_files(files, &tokenizer).await?;
+
+        let count_tokens = !copier_config.no_stats
+            || copier_config.max_tokens.is_some()
+            || copier_config.max_file_tokens.is_some()
+            || json
+            || template.uses("tokens");
+        let render_options = RenderOptions {
+            format_options: &format_options,
+            tokenizer: &tokenizer,
+            grep: grep.as_ref(),
+            count_tokens,
+            // Outlines are also made to show their size in the stats and for budget packing
+            make_outlines: copier_config.outline || count_tokens,
+            json,
+        };
+        let rendered = FileRenderer::new(self, &render_options, selected)?
+            .render_all()
+            .await?;
+
+        let header_template = template
+            .header
+            .clone()
+            .unwrap_or_else(|| copier_config.first_line.clone());
+        let frame = Frame {
+            header: &header_template,
+            footer: &template.footer,
+            model: &copier_config.model,
+            format: copier_config.format,
+        };
+
+        let decisions = match copier_config.max_tokens {
+            Some(max_tokens) => {
+                let total_tokens = rendered.copied.iter().map(|f| f.token_count).sum();
+                let prompt_text = prompt
+                    .as_deref()
+                    .map(|prompt| render_prompt(prompt, "", &prompt_vars))
+                    .unwrap_or_default();
+                let skipped = skipped_records(&rendered.skipped);
+                let frame_text = frame.wrap(rendered.copied.len(), total_tokens, skipped, "");
+                let overhead = tokenizer.count(&format!("{}{}", prompt_text, frame_text));
+                self.pack_files(&rendered.copied, max_tokens.saturating_sub(overhead))
+            }
+            None => vec![PackDecision::Included; rendered.copied.len()],
+        };
+
+        let packed = self.assemble_files(rendered, decisions);
+        let mut final_content =
+            frame.wrap(packed.files, packed.tokens, packed.skipped, &packed.content);
+        if let Some(prompt) = &prompt {
+            final_content = render_prompt(prompt, &final_content, &prompt_vars);
+        }
 
         trace!("Final content length: {}", final_content.len());
 
         if !copier_config.no_stats {
-            print_stats(&token_counts)?;
+            report_file_stats(&packed.stats, &copier_config.stats)?;
+            print_model_estimate(&copier_config.model, &model, tokenizer.count(&final_content));
         }
 
-        clipboard
-            .set_text(final_content)
-            .map_err(|e| ClipboardError::ClipboardWriteError(e.to_string()))?;
-
-        info!("Files copied to clipboard successfully.");
+        if let Some(sink) = sink {
+            sink.write(&final_content).await?;
+            info!("Files copied to {} successfully.", sink.describe());
+        }
         Ok(())
     }
 }
 
+/// Logs the estimated input cost of `tokens` and warns when they won't fit `model`.
+fn print_model_estimate(name: &str, model: &ModelInfo, tokens: usize) {
+    let margin = match model.tokenizer.margin() {
+        margin if margin > 0.0 => format!(" (±{:.0}%)", margin * 100.0),
+        _ => String::new(),
+    };
+    let cost = match model.input_cost(tokens) {
+        Some(cost) => format!(", estimated input cost ${:.4}", cost),
+        None => String::new(),
+    };
+    info!("{}: {} input tokens{}{}", name, tokens, margin, cost);
+    if let Some(warning) = model.fit_warning(tokens) {
+        warn!("{}: {}", name, warning);
+    }
+}
+
 pub async fn copy_files_to_clipboard(
+    config: ClipboardCopierConfig,
+    files: Vec<String>,
+) -> Result<(), ClipboardError> {
+    let cwd = std::env::current_dir().map_err(|e| ClipboardError::IoError(e.to_string()))?;
+    copy_files_from(&cwd, config, files).await
+}
+
+/// Copies `files` with output paths relative to `base_path`.
+///
+/// Relative patterns are still resolved against the current directory.
+pub async fn copy_files_from(
+    base_path: &Path,
     config: ClipboardCopierConfig,
     mut files: Vec<String>,
 ) -> Result<(), ClipboardError> {
     let mut temp_dir: Option<tempfile::TempDir> = None;
-    let mut base_path = std::env::current_dir()
-        .map_err(|e| ClipboardError::IoError(e.to_string()))?
-        .to_str()
-        .unwrap()
-        .into();
+    let mut base_path: String = base_path.to_str().unwrap().into();
 
     if files.is_empty() {
         files.push("".to_string());
     }
 
-    if let Some(first_file) = files.get(0) {
-        if is_git_url(first_file) {
-            let git_url = first_file.clone();
-            // Remove the git URL from the files list
-            files.remove(0);
+    if let Some(source) = files.first().and_then(|f| GitSource::parse(f)) {
+        // Remove the git URL from the files list
+        files.remove(0);
 
-            if files.is_empty() {
-                files.push("".to_string());
-            }
-
-            // Clone the repository to a temporary directory
-            let tmp_dir =
-                tempfile::tempdir().map_err(|e| ClipboardError::IoError(e.to_string()))?;
-
-            use tokio::process::Command;
-
-            info!("Cloning repository from {}", &git_url);
-
-            let status = Command::new("git")
-                .arg("clone")
-                .arg("--depth")
-                .arg("1")
-                .arg(&git_url)
-                .arg(tmp_dir.path())
-                .status()
-                .await
-                .map_err(|e| ClipboardError::CloneError(e.to_string()))?;
+        if files.is_empty() {
+            files.push("".to_string());
+        }
 
-            if !status.success() {
-                return Err(ClipboardError::CloneError(format!(
-                    "Failed to clone repository from {}",
-                    &git_url
-                )));
+        let cache_dir = match config.git_cache_dir.clone().or_else(default_cache_dir) {
+            Some(cache_dir) => cache_dir,
+            None => {
+                return Err(ClipboardError::ConfigError(
+                    "No cache directory for git sources; set --git-cache-dir".to_string(),
+                ))
             }
+        };
 
-            // Update file paths to include the temporary directory
-            let temp_dir_path = tmp_dir.path().to_path_buf();
-            files = files
-                .iter()
-                .map(|f| {
-                    let mut p = temp_dir_path.clone();
-                    p.push(f);
-                    p.to_string_lossy().into_owned()
-                })
-                .collect();
-            debug!("Updated file paths: {:?}", files);
+        // Check out the repository into a temporary directory
+        let tmp_dir =
+            tempfile::tempdir().map_err(|e| ClipboardError::IoError(e.to_string()))?;
+        let checkout_path = tmp_dir.path().join("repo");
+        let patterns: Vec<String> = files.iter().map(|f| source.repo_path(f)).collect();
+        checkout(&source, &patterns, &cache_dir, &checkout_path)?;
 
-            base_path = tmp_dir.path().to_str().unwrap().into();
-            temp_dir = Some(tmp_dir);
-        }
+        // Update file paths to include the checkout directory
+        files = patterns
+            .iter()
+            .map(|f| checkout_path.join(f).to_string_lossy().into_owned())
+            .collect();
+        debug!("Updated file paths: {:?}", files);
+
+        base_path = checkout_path.to_str().unwrap().into();
+        temp_dir = Some(tmp_dir);
     }
 
     let copier = BasicClipboardCopier::new(config, base_path, temp_dir);
     copier.copy_files_to_clipboard(files).await
 }
-
-fn is_git_url(url: &str) -> bool {
-    url.starts_with("git@")
-        || url.starts_with("https://")
-        || url.starts_with("git://")
-        || url.starts_with("ssh://")
-}
@@ -32,6 +32,9 @@ pub enum ClipboardError {
     #[error("Git Clone Error: {0}")]
     CloneError(String),
 
+    #[error("Git Error: {0}")]
+    GitError(String),
+
     #[error("File operation error: {0}")]
     FileError(String),
 
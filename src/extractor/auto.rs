@@ -0,0 +1,32 @@
+use super::markdown::MarkdownExtractor;
+use super::xml::XmlExtractor;
+use super::{Extractor, ParsedBlock};
+use crate::errors::ClipboardError;
+use tracing::debug;
+
+/// Reads snippy's XML output with `XmlExtractor` and anything else as Markdown.
+#[derive(Default)]
+pub struct AutoExtractor {
+    markdown: MarkdownExtractor,
+    xml: XmlExtractor,
+}
+
+impl AutoExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Extractor for AutoExtractor {
+    fn extract(&self, content: &str) -> Result<Vec<ParsedBlock>, ClipboardError> {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with("<files>") || trimmed.starts_with("<file ") {
+            debug!("Content looks like XML output");
+            let blocks = self.xml.extract(content)?;
+            if !blocks.is_empty() {
+                return Ok(blocks);
+            }
+        }
+        self.markdown.extract(content)
+    }
+}
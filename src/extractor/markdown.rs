@@ -1,5 +1,6 @@
 use super::{BlockType, Extractor, ParsedBlock};
 use crate::errors::ClipboardError;
+use crate::notebook::parse_cell_address;
 use async_trait::async_trait;
 use markdown::mdast::Node;
 use markdown::{to_mdast, Constructs, ParseOptions};
@@ -8,6 +9,12 @@ use tracing::{debug, trace};
 
 pub struct MarkdownExtractor {}
 
+impl Default for MarkdownExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MarkdownExtractor {
     pub fn new() -> Self {
         debug!("Initializing MarkdownExtractor");
@@ -47,11 +54,11 @@ impl Extractor for MarkdownExtractor {
                         "diff" => parse_diff_block(&code_content)?,
                         "replace" => parse_block(
                             &code_content,
-                            &children,
+                            children,
                             index,
                             BlockType::SearchReplaceBlock,
                         )?,
-                        _ => parse_block(&code_content, &children, index, BlockType::FullContent)?,
+                        _ => parse_block(&code_content, children, index, BlockType::FullContent)?,
                     };
 
                     if let Some(block) = block {
@@ -150,11 +157,28 @@ fn extract_block_from_content(
     Ok(None)
 }
 
+/// Reads headings such as ``### `analysis.ipynb` cell 4`` as a notebook cell address.
+fn notebook_cell_heading(children: &[Node]) -> Option<String> {
+    let [.., name, Node::Text(cell)] = children else {
+        return None;
+    };
+    let name = match name {
+        Node::InlineCode(code) => &code.value,
+        Node::Text(text) => &text.value,
+        _ => return None,
+    };
+    let address = format!("{} {}", name.trim(), cell.value.trim());
+    parse_cell_address(&address).map(|_| address)
+}
+
 fn extract_filename_from_context(children: &[Node], index: usize) -> Option<String> {
     if index > 0 {
         let prev_child = &children[index - 1];
         match prev_child {
             Node::Heading(heading) => {
+                if let Some(address) = notebook_cell_heading(&heading.children) {
+                    return Some(address);
+                }
                 if let Some(Node::Text(text)) = heading.children.last() {
                     return Some(text.value.clone());
                 } else if let Some(Node::InlineCode(code)) = heading.children.last() {
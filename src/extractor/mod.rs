@@ -1,5 +1,7 @@
 use crate::errors::ClipboardError;
+pub mod auto;
 pub mod markdown;
+pub mod xml;
 
 #[derive(Debug, Clone)]
 pub struct ParsedBlock {
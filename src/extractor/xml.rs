@@ -0,0 +1,119 @@
+use super::{BlockType, Extractor, ParsedBlock};
+use crate::errors::ClipboardError;
+use crate::xml::unescape;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::{debug, warn};
+
+static START_TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<(?P<tag>file|diff)\b(?P<attrs>[^>]*)>").expect("Invalid regex"));
+static ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?P<name>[\w-]+)\s*=\s*(?:"(?P<double>[^"]*)"|'(?P<single>[^']*)')"#)
+        .expect("Invalid regex")
+});
+
+/// Reads back the `<file>` and `<diff>` elements of snippy's XML output.
+///
+/// CDATA content is returned byte for byte; numbered `<line>` elements are
+/// joined with newlines.
+pub struct XmlExtractor {}
+
+impl Default for XmlExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XmlExtractor {
+    pub fn new() -> Self {
+        debug!("Initializing XmlExtractor");
+        XmlExtractor {}
+    }
+}
+
+impl Extractor for XmlExtractor {
+    fn extract(&self, content: &str) -> Result<Vec<ParsedBlock>, ClipboardError> {
+        debug!("Extracting XML file elements");
+        let mut blocks = Vec::new();
+        let mut position = 0;
+        while let Some(caps) = START_TAG_REGEX.captures_at(content, position) {
+            let start_tag = caps.get(0).expect("match");
+            let tag = &caps["tag"];
+            let path = ATTRIBUTE_REGEX
+                .captures_iter(&caps["attrs"])
+                .find(|attr| &attr["name"] == "path")
+                .and_then(|attr| attr.name("double").or_else(|| attr.name("single")))
+                .map(|value| unescape(value.as_str()));
+
+            let (body, end) = read_element(content, start_tag.end(), tag)?;
+            position = end;
+            let Some(filename) = path else {
+                warn!("Skipping <{}> element without a path", tag);
+                continue;
+            };
+            blocks.push(ParsedBlock {
+                filename,
+                content: body,
+                block_type: if tag == "diff" {
+                    BlockType::UnifiedDiff
+                } else {
+                    BlockType::FullContent
+                },
+            });
+        }
+        debug!("Extraction complete. Found {} blocks", blocks.len());
+        Ok(blocks)
+    }
+}
+
+/// Reads the body of a `tag` element starting at `start`, up to its end tag.
+///
+/// Returns the decoded body and the position after the end tag.
+fn read_element(content: &str, start: usize, tag: &str) -> Result<(String, usize), ClipboardError> {
+    let end_tag = format!("</{}>", tag);
+    let unterminated =
+        || ClipboardError::ContentExtractionError(format!("Unterminated <{}> element", tag));
+    let mut body = String::new();
+    let mut rest = &content[start..];
+    // The newline after the start tag is formatting
+    rest = rest.strip_prefix('\n').unwrap_or(rest);
+
+    loop {
+        if let Some(after) = rest.strip_prefix(end_tag.as_str()) {
+            let end = content.len() - after.len();
+            return Ok((body, end));
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let close = after.find("]]>").ok_or_else(unterminated)?;
+            body.push_str(&after[..close]);
+            rest = &after[close + 3..];
+            // Only whitespace separates a CDATA section from the end tag
+            let trimmed = rest.trim_start();
+            if trimmed.starts_with(end_tag.as_str()) {
+                rest = trimmed;
+            }
+            continue;
+        }
+        if rest.starts_with("<line") {
+            let open_end = rest.find('>').ok_or_else(unterminated)?;
+            let after = &rest[open_end + 1..];
+            let close = after.find("</line>").ok_or_else(unterminated)?;
+            body.push_str(&unescape(&after[..close]));
+            body.push('\n');
+            rest = after[close + "</line>".len()..].trim_start_matches(['\r', '\n']);
+            continue;
+        }
+
+        if rest.is_empty() {
+            return Err(unterminated());
+        }
+        // Escaped text up to the next markup
+        let next = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '<')
+            .map_or(rest.len(), |(i, _)| i);
+        body.push_str(&unescape(&rest[..next]));
+        rest = &rest[next..];
+    }
+}
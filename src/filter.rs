@@ -0,0 +1,171 @@
+use crate::notebook::is_notebook;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::debug;
+
+/// Bytes inspected when looking for NUL bytes.
+const BINARY_SNIFF_BYTES: usize = 8000;
+/// Share of control characters above which a file is considered binary.
+const BINARY_MAX_CONTROL_RATIO: f64 = 0.1;
+/// Lines inspected for `@generated` and "DO NOT EDIT" markers.
+const GENERATED_HEADER_LINES: usize = 20;
+/// Files smaller than this are never considered minified.
+const MINIFIED_MIN_BYTES: usize = 1024;
+const MINIFIED_AVG_LINE_LENGTH: usize = 300;
+const MINIFIED_MAX_WHITESPACE_RATIO: f64 = 0.15;
+
+/// Why a file was left out of the copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Binary,
+    Minified,
+    Generated,
+    /// Byte-identical to a file copied earlier.
+    Duplicate(String),
+    TooLarge {
+        bytes: u64,
+        limit: u64,
+    },
+    TooManyTokens {
+        tokens: usize,
+        limit: usize,
+    },
+}
+
+impl SkipReason {
+    /// Short name used to group skipped files in the stats summary.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SkipReason::Binary => "binary",
+            SkipReason::Minified => "minified",
+            SkipReason::Generated => "generated",
+            SkipReason::Duplicate(_) => "duplicate",
+            SkipReason::TooLarge { .. } => "too large",
+            SkipReason::TooManyTokens { .. } => "too many tokens",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Duplicate(original) => write!(f, "duplicate of {}", original),
+            SkipReason::TooLarge { bytes, limit } => {
+                write!(f, "{} bytes, over the {} byte limit", bytes, limit)
+            }
+            SkipReason::TooManyTokens { tokens, limit } => {
+                write!(f, "{} tokens, over the {} token limit", tokens, limit)
+            }
+            reason => write!(f, "{}", reason.kind()),
+        }
+    }
+}
+
+/// Returns true if `bytes` look like a binary file: the start has a NUL byte
+/// or many control characters. Text in legacy encodings such as Latin-1 passes.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let is_control =
+        |b: u8| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)) || b == 0x7f;
+    let control = sample.iter().filter(|&&b| is_control(b)).count();
+    control as f64 > sample.len() as f64 * BINARY_MAX_CONTROL_RATIO
+}
+
+/// Returns true for minified bundles: long lines with little whitespace.
+pub fn is_minified(text: &str) -> bool {
+    if text.len() < MINIFIED_MIN_BYTES {
+        return false;
+    }
+    let lines = text.lines().count().max(1);
+    let whitespace = text.chars().filter(|c| c.is_whitespace()).count();
+    text.len() / lines > MINIFIED_AVG_LINE_LENGTH
+        && (whitespace as f64) / (text.len() as f64) < MINIFIED_MAX_WHITESPACE_RATIO
+}
+
+/// Returns true if the header of `text` marks it as generated code.
+pub fn is_generated(text: &str) -> bool {
+    text.lines()
+        .take(GENERATED_HEADER_LINES)
+        .any(|line| line.contains("@generated") || line.contains("DO NOT EDIT"))
+}
+
+/// Decides which files are worth copying based on their content.
+///
+/// Binary files are always skipped, and text that is not valid UTF-8 is decoded
+/// lossily. Explicitly named files bypass every other check except for binary
+/// and duplicate content.
+pub struct ContentFilter {
+    /// Detect minified, generated and duplicate files.
+    detect: bool,
+    max_bytes: Option<u64>,
+    /// Content hashes of the files accepted so far, with their paths.
+    seen: HashMap<Vec<u8>, String>,
+}
+
+impl ContentFilter {
+    pub fn new(detect: bool, max_bytes: Option<u64>) -> Self {
+        ContentFilter {
+            detect,
+            max_bytes,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Checks the size limit before a file of `bytes` bytes is read.
+    pub fn check_size(&self, bytes: u64, explicit: bool) -> Result<(), SkipReason> {
+        match self.max_bytes.filter(|&limit| bytes > limit) {
+            Some(limit) if !explicit => Err(SkipReason::TooLarge { bytes, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the text of `bytes` if the file at `path` passes the binary,
+    /// size, generated and minified checks; duplicates are not looked for.
+    pub fn check_content<'a>(
+        &self,
+        path: &str,
+        bytes: &'a [u8],
+        explicit: bool,
+    ) -> Result<Cow<'a, str>, SkipReason> {
+        if is_binary(bytes) {
+            return Err(SkipReason::Binary);
+        }
+        let text = String::from_utf8_lossy(bytes);
+
+        self.check_size(bytes.len() as u64, explicit)?;
+        if !explicit {
+            if self.detect && is_generated(&text) {
+                return Err(SkipReason::Generated);
+            }
+            // Notebooks embed long base64 outputs, which rendering leaves out
+            if self.detect && is_minified(&text) && !is_notebook(path) {
+                return Err(SkipReason::Minified);
+            }
+        }
+        Ok(text)
+    }
+
+    /// Returns the text of `bytes` if the file at `path` should be copied.
+    pub fn check<'a>(
+        &mut self,
+        path: &str,
+        bytes: &'a [u8],
+        explicit: bool,
+    ) -> Result<Cow<'a, str>, SkipReason> {
+        let text = self.check_content(path, bytes, explicit)?;
+        if self.detect {
+            let hash = Sha256::digest(bytes).to_vec();
+            if let Some(original) = self.seen.get(&hash) {
+                debug!("{} has the same content as {}", path, original);
+                return Err(SkipReason::Duplicate(original.clone()));
+            }
+            self.seen.insert(hash, path.to_string());
+        }
+        Ok(text)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::errors::ClipboardError;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, trace};
+
+/// Runs a git command in `dir` and returns its stdout.
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, ClipboardError> {
+    trace!("Running git {:?} in {:?}", args, dir);
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| ClipboardError::GitError(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(ClipboardError::GitError(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Splits NUL-separated git output into paths.
+pub fn split_nul_paths(output: &[u8]) -> Vec<PathBuf> {
+    output
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| PathBuf::from(String::from_utf8_lossy(p).into_owned()))
+        .collect()
+}
+
+/// Returns the files tracked by git under `dir`, relative to `dir`.
+pub fn tracked_files(dir: &Path) -> Result<HashSet<PathBuf>, ClipboardError> {
+    let output = run_git(dir, &["ls-files", "-z"])?;
+    let files: HashSet<PathBuf> = split_nul_paths(&output).into_iter().collect();
+    debug!("Found {} tracked files under {:?}", files.len(), dir);
+    Ok(files)
+}
+
+/// Whether `HEAD` resolves to a commit in the repository at `dir`.
+fn has_commits(dir: &Path) -> bool {
+    run_git(dir, &["rev-parse", "--verify", "--quiet", "HEAD^{commit}"]).is_ok()
+}
+
+/// Which git changes select the files to copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeScope {
+    /// Uncommitted changes (staged and unstaged) plus untracked files.
+    WorkingTree,
+    /// Changes staged in the index.
+    Staged,
+    /// Everything that differs from `reference`, or from its merge-base with `HEAD`.
+    Since { reference: String, merge_base: bool },
+}
+
+impl ChangeScope {
+    /// Replaces a merge-base comparison with the commit it resolves to, and
+    /// compares a repository without commits against the empty tree.
+    pub fn resolve(&self, dir: &Path) -> Result<ChangeScope, ClipboardError> {
+        if let ChangeScope::Since { reference, .. } = self {
+            // Git would parse such a reference as an option
+            if reference.starts_with('-') {
+                return Err(ClipboardError::ConfigError(format!(
+                    "Invalid git reference {}",
+                    reference
+                )));
+            }
+        }
+        match self {
+            ChangeScope::WorkingTree if !has_commits(dir) => {
+                let output = run_git(dir, &["hash-object", "-t", "tree", "--stdin"])?;
+                Ok(ChangeScope::Since {
+                    reference: String::from_utf8_lossy(&output).trim().to_string(),
+                    merge_base: false,
+                })
+            }
+            ChangeScope::Since {
+                reference,
+                merge_base: true,
+            } => {
+                let output = run_git(dir, &["merge-base", reference, "HEAD"])?;
+                Ok(ChangeScope::Since {
+                    reference: String::from_utf8_lossy(&output).trim().to_string(),
+                    merge_base: false,
+                })
+            }
+            scope => Ok(scope.clone()),
+        }
+    }
+
+    /// Arguments passed to `git diff` to compare against this scope, once resolved.
+    fn diff_args(&self) -> Vec<&str> {
+        match self {
+            ChangeScope::WorkingTree => vec!["HEAD"],
+            ChangeScope::Staged => vec!["--cached"],
+            ChangeScope::Since { reference, .. } => vec![reference.as_str()],
+        }
+    }
+
+    fn includes_untracked(&self) -> bool {
+        !matches!(self, ChangeScope::Staged)
+    }
+}
+
+/// Returns the files changed in `scope`, relative to `dir`.
+///
+/// Deleted files are included so their diffs can still be shown.
+pub fn changed_files(dir: &Path, scope: &ChangeScope) -> Result<Vec<PathBuf>, ClipboardError> {
+    let scope = &scope.resolve(dir)?;
+    let mut args = vec!["diff", "--name-only", "-z", "--relative"];
+    args.extend(scope.diff_args());
+    let mut files = split_nul_paths(&run_git(dir, &args)?);
+
+    if scope.includes_untracked() {
+        let untracked = run_git(dir, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+        for file in split_nul_paths(&untracked) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    debug!("Found {} changed files for {:?}", files.len(), scope);
+    Ok(files)
+}
+
+/// Returns the unified diff of `file` (relative to `dir`) for `scope`.
+pub fn file_diff(dir: &Path, scope: &ChangeScope, file: &Path) -> Result<String, ClipboardError> {
+    let scope = &scope.resolve(dir)?;
+    let mut args = vec!["diff", "--no-color", "--relative"];
+    args.extend(scope.diff_args());
+    args.push("--");
+    let file = file.to_string_lossy();
+    args.push(&file);
+    Ok(String::from_utf8_lossy(&run_git(dir, &args)?).into_owned())
+}
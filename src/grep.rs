@@ -0,0 +1,33 @@
+//! Regions of files around the lines matching a regex, for copying only the
+//! parts of files that matter.
+
+use crate::selector::LineRange;
+use crate::usages::merge_ranges;
+use regex::Regex;
+
+/// Lines copied above and below each match by default.
+pub const DEFAULT_GREP_CONTEXT: usize = 3;
+
+/// 1-based numbers of the lines of `content` matching `regex`.
+pub fn matching_lines(content: &str, regex: &Regex) -> Vec<usize> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Merged windows of `context` lines around each of `lines`, within a file of
+/// `total` lines.
+pub fn context_regions(lines: &[usize], context: usize, total: usize) -> Vec<LineRange> {
+    merge_ranges(
+        lines
+            .iter()
+            .map(|&line| LineRange {
+                start: line.saturating_sub(context).max(1),
+                end: (line + context).min(total),
+            })
+            .collect(),
+    )
+}
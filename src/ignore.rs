@@ -1,7 +1,29 @@
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ::ignore::WalkBuilder;
 use glob::Pattern;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
+/// Project-level ignore file read alongside `.gitignore` and `.ignore`.
+pub const SNIPPY_IGNORE_FILENAME: &str = ".snippyignore";
+
+/// Ignore files honored in every directory, in increasing precedence.
+const IGNORE_FILENAMES: &[&str] = &[".gitignore", ".ignore", SNIPPY_IGNORE_FILENAME];
+
+/// Directories that are never descended into while walking.
+pub const DEFAULT_SKIP_DIRS: &[&str] = &[
+    ".git",
+    ".snippy",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "__pycache__",
+    "venv",
+    "obj",
+    "bin",
+];
+
 pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
     "target/**",
     "node_modules/**",
@@ -41,8 +63,55 @@ pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
     "go.sum"
 ];
 
+/// Controls which files are visited when walking directories.
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Honor `.gitignore` (nested and global), `.ignore` and `.snippyignore`.
+    pub respect_ignore_files: bool,
+    /// Only keep files tracked by git.
+    pub tracked_only: bool,
+    pub skip_dirs: Vec<String>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            respect_ignore_files: true,
+            tracked_only: false,
+            skip_dirs: DEFAULT_SKIP_DIRS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Creates a walker rooted at `root` that applies these options.
+    pub fn walker(&self, root: &Path) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .hidden(false)
+            .parents(self.respect_ignore_files)
+            .ignore(self.respect_ignore_files)
+            .git_ignore(self.respect_ignore_files)
+            .git_global(self.respect_ignore_files)
+            .git_exclude(self.respect_ignore_files)
+            .require_git(false);
+        if self.respect_ignore_files {
+            builder.add_custom_ignore_filename(SNIPPY_IGNORE_FILENAME);
+        }
+
+        let skip_dirs = self.skip_dirs.clone();
+        builder.filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && skip_dirs.iter().any(|d| entry.file_name() == d.as_str()))
+        });
+        builder
+    }
+}
+
 pub struct IgnorePatterns {
     patterns: Vec<Pattern>,
+    /// Rules of the ignore files of each directory, relative to the root, deepest last.
+    ignore_files: Vec<(PathBuf, Gitignore)>,
 }
 
 impl IgnorePatterns {
@@ -64,12 +133,67 @@ impl IgnorePatterns {
         debug!("Using ignore patterns: {:?}", patterns_to_use);
         
         IgnorePatterns { 
-            patterns: compiled_patterns 
+            patterns: compiled_patterns,
+            ignore_files: Vec::new(),
+        }
+    }
+
+    /// Also matches relative paths against the ignore files found in `root`
+    /// and the directories below it, as the walker does.
+    pub fn with_ignore_files(mut self, root: &Path) -> Self {
+        let mut dirs: Vec<PathBuf> = WalkOptions::default()
+            .walker(root)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+            .filter_map(|entry| Some(entry.path().strip_prefix(root).ok()?.to_path_buf()))
+            .collect();
+        dirs.sort_by_key(|dir| dir.components().count());
+
+        for dir in dirs {
+            let mut builder = GitignoreBuilder::new(root.join(&dir));
+            let mut found = false;
+            for name in IGNORE_FILENAMES {
+                let file = root.join(&dir).join(name);
+                if file.is_file() {
+                    found = true;
+                    if let Some(e) = builder.add(&file) {
+                        warn!("Failed to read ignore file {:?}: {}", file, e);
+                    }
+                }
+            }
+            if !found {
+                continue;
+            }
+            match builder.build() {
+                Ok(gitignore) => self.ignore_files.push((dir, gitignore)),
+                Err(e) => warn!("Failed to build ignore rules for {:?}: {}", dir, e),
+            }
         }
+        self
     }
     
     pub fn should_ignore<P: AsRef<Path>>(&self, path: P) -> bool {
         let path_str = path.as_ref().to_string_lossy().replace("\\", "/");
-        self.patterns.iter().any(|pattern| pattern.matches(&path_str))
+        if self.patterns.iter().any(|pattern| pattern.matches(&path_str)) {
+            return true;
+        }
+        if path.as_ref().has_root() {
+            return false;
+        }
+        // Deeper ignore files override the rules of their parents
+        for (dir, gitignore) in self.ignore_files.iter().rev() {
+            let Ok(relative) = path.as_ref().strip_prefix(dir) else {
+                continue;
+            };
+            let matched = gitignore.matched_path_or_any_parents(relative, false);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
     }
-}
\ No newline at end of file
+}
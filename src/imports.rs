@@ -0,0 +1,445 @@
+//! Local imports of source files, used to add the files a copied file depends on.
+//!
+//! Rust `mod` declarations and `use crate::`/`self::`/`super::` paths, Python
+//! imports, relative JavaScript/TypeScript imports and `require` calls, and Go
+//! imports of packages in the same module are resolved to files on disk.
+//! Imports of other crates, packages or modules are ignored.
+
+use crate::symbols::Language;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+static RUST_MOD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub(?:\s*\([^)]*\))?\s+)?mod\s+([A-Za-z_]\w*)\s*;")
+        .expect("Invalid regex")
+});
+static RUST_USE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub(?:\s*\([^)]*\))?\s+)?use\s+([^;]+);").expect("Invalid regex")
+});
+static RUST_ALIAS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+as\s+\w+").expect("Invalid regex"));
+static CARGO_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^\s*name\s*=\s*"([^"]+)""#).expect("Invalid regex"));
+static PYTHON_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*import[ \t]+([\w.]+(?:[ \t]+as[ \t]+\w+)?(?:[ \t]*,[ \t]*[\w.]+(?:[ \t]+as[ \t]+\w+)?)*)")
+        .expect("Invalid regex")
+});
+static PYTHON_FROM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^[ \t]*from[ \t]+(\.*[\w.]*)[ \t]+import[ \t]+(\([^)]*\)|[^\n#]+)")
+        .expect("Invalid regex")
+});
+static JS_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?:\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)["'](\.\.?(?:/[^"'\n]*)?)["']"#)
+        .expect("Invalid regex")
+});
+static GO_IMPORT_BLOCK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^import\s*\(([^)]*)\)").expect("Invalid regex"));
+static GO_IMPORT_LINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^import\s+(?:[\w.]+\s+)?"([^"]+)""#).expect("Invalid regex"));
+static GO_IMPORT_SPEC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""([^"]+)""#).expect("Invalid regex"));
+static GO_MODULE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*module\s+(\S+)").expect("Invalid regex"));
+
+/// Extensions tried, in order, for extensionless JavaScript/TypeScript imports.
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "js", "jsx", "mjs", "cjs"];
+
+/// A file added because another file imports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFile {
+    pub path: String,
+    /// The file whose import added this one.
+    pub importer: String,
+}
+
+/// Follows the local imports of `files` up to `depth` levels deep.
+///
+/// Returns the files not already in `files`, in the order they were found,
+/// each with the file that first imported it.
+pub fn follow_imports(files: &[String], depth: usize) -> Vec<ImportedFile> {
+    let mut seen: HashSet<PathBuf> = files.iter().map(|f| clean(Path::new(f))).collect();
+    let mut imported = Vec::new();
+    let mut frontier: Vec<String> = files.to_vec();
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for file in &frontier {
+            let Ok(content) = fs::read_to_string(file) else {
+                continue;
+            };
+            for path in local_imports(file, &content) {
+                if seen.insert(PathBuf::from(&path)) {
+                    debug!("{} imports {}", file, path);
+                    imported.push(ImportedFile {
+                        path: path.clone(),
+                        importer: file.clone(),
+                    });
+                    next.push(path);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+    imported
+}
+
+/// Files on disk imported by `content`, the source of the file at `path`.
+pub fn local_imports(path: &str, content: &str) -> Vec<String> {
+    let path = clean(Path::new(path));
+    let resolved = match Language::from_path(&path.to_string_lossy()) {
+        Some(Language::Rust) => rust_imports(&path, content),
+        Some(Language::Python) => python_imports(&path, content),
+        Some(Language::TypeScript) => js_imports(&path, content),
+        Some(Language::Go) => go_imports(&path, content),
+        None => Vec::new(),
+    };
+    let mut seen = HashSet::new();
+    resolved
+        .into_iter()
+        .map(|p| clean(&p))
+        .filter(|p| *p != path && seen.insert(p.clone()))
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+/// Resolves `.` and `..` components without touching the file system.
+fn clean(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match cleaned.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    cleaned.pop();
+                }
+                Some(Component::RootDir) => {}
+                _ => cleaned.push(".."),
+            },
+            other => cleaned.push(other),
+        }
+    }
+    cleaned
+}
+
+fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn first_file(candidates: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+/// `path` with `.ext` appended to its file name.
+fn with_suffix(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+/// The nearest ancestor directory of `path` containing `marker`.
+fn find_root<'a>(path: &'a Path, marker: &str) -> Option<&'a Path> {
+    parent(path)
+        .ancestors()
+        .find(|dir| dir.join(marker).is_file())
+}
+
+/// Imported files ordered by the offset of the statement importing them.
+fn in_source_order(mut imports: Vec<(usize, PathBuf)>) -> Vec<PathBuf> {
+    imports.sort_by_key(|(start, _)| *start);
+    imports.into_iter().map(|(_, file)| file).collect()
+}
+
+/// Directory holding the submodules of the Rust module in `path`.
+fn rust_module_dir(path: &Path) -> PathBuf {
+    let dir = parent(path);
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod" | "lib" | "main") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    }
+}
+
+/// The file of the Rust module `dir/name`.
+fn rust_module_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    first_file([
+        dir.join(format!("{}.rs", name)),
+        dir.join(name).join("mod.rs"),
+    ])
+}
+
+/// Expands a `use` tree such as `crate::a::{b, c::{d as e, *}}` into its paths.
+fn use_paths(tree: &str) -> Vec<Vec<String>> {
+    let tree: String = RUST_ALIAS
+        .replace_all(tree, "")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let mut paths = Vec::new();
+    expand_use_tree(&[], &tree, &mut paths);
+    paths
+}
+
+fn expand_use_tree(prefix: &[String], tree: &str, paths: &mut Vec<Vec<String>>) {
+    let tree = tree.trim_start_matches("::");
+    let (head, group) = match tree.find('{') {
+        Some(open) => {
+            let close = tree.rfind('}').unwrap_or(tree.len());
+            (&tree[..open], Some(&tree[open + 1..close.max(open + 1)]))
+        }
+        None => (tree, None),
+    };
+    let mut path = prefix.to_vec();
+    for segment in head.split("::") {
+        // `a::{self, b}` names `a` itself, while a leading `self` is the current module
+        if segment.is_empty() || segment == "*" || (segment == "self" && !path.is_empty()) {
+            continue;
+        }
+        path.push(segment.to_string());
+    }
+    let Some(group) = group else {
+        paths.push(path);
+        return;
+    };
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in group.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                expand_use_tree(&path, &group[start..i], paths);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < group.len() {
+        expand_use_tree(&path, &group[start..], paths);
+    }
+}
+
+fn rust_imports(path: &Path, content: &str) -> Vec<PathBuf> {
+    let module_dir = rust_module_dir(path);
+    let mut imports: Vec<(usize, PathBuf)> = RUST_MOD
+        .captures_iter(content)
+        .filter_map(|caps| {
+            let file = rust_module_file(&module_dir, &caps[1])?;
+            Some((caps.get(0)?.start(), file))
+        })
+        .collect();
+
+    let crate_dir = find_root(path, "Cargo.toml");
+    let crate_name = crate_dir
+        .and_then(|dir| fs::read_to_string(dir.join("Cargo.toml")).ok())
+        .and_then(|manifest| {
+            CARGO_NAME
+                .captures(&manifest)
+                .map(|caps| caps[1].replace('-', "_"))
+        });
+    for caps in RUST_USE.captures_iter(content) {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        for segments in use_paths(&caps[1]) {
+            let Some((first, mut rest)) = segments.split_first() else {
+                continue;
+            };
+            let mut dir = match first.as_str() {
+                "crate" => match crate_dir {
+                    Some(crate_dir) => crate_dir.join("src"),
+                    None => continue,
+                },
+                name if Some(name) == crate_name.as_deref() => match crate_dir {
+                    Some(crate_dir) => crate_dir.join("src"),
+                    None => continue,
+                },
+                "self" => module_dir.clone(),
+                "super" => parent(&module_dir).to_path_buf(),
+                _ => continue,
+            };
+            while let Some((segment, tail)) = rest.split_first() {
+                if segment != "super" {
+                    break;
+                }
+                dir = parent(&dir).to_path_buf();
+                rest = tail;
+            }
+            // The longest prefix naming a module file; the rest are items in it
+            let resolved = (1..=rest.len()).rev().find_map(|len| {
+                let (name, modules) = rest[..len].split_last()?;
+                let dir = modules.iter().fold(dir.clone(), |dir, m| dir.join(m));
+                rust_module_file(&dir, name)
+            });
+            imports.extend(resolved.map(|file| (start, file)));
+        }
+    }
+    in_source_order(imports)
+}
+
+/// The file of the Python module `dir/a/b` named by `segments`.
+fn python_module_file(dir: &Path, segments: &[&str]) -> Option<PathBuf> {
+    if segments.is_empty() {
+        return first_file([dir.join("__init__.py")]);
+    }
+    let module = segments
+        .iter()
+        .fold(dir.to_path_buf(), |dir, s| dir.join(s));
+    first_file([with_suffix(&module, "py"), module.join("__init__.py")])
+}
+
+/// Directories absolute Python imports in `path` are resolved against: its own
+/// directory and the directory above its outermost package.
+fn python_roots(path: &Path) -> Vec<PathBuf> {
+    let dir = parent(path);
+    let mut root = dir;
+    while root.join("__init__.py").is_file() {
+        match root.parent() {
+            Some(up) => root = up,
+            None => break,
+        }
+    }
+    let mut roots = vec![dir.to_path_buf()];
+    if root != dir {
+        roots.push(root.to_path_buf());
+    }
+    roots
+}
+
+fn python_imports(path: &Path, content: &str) -> Vec<PathBuf> {
+    let roots = python_roots(path);
+    let mut imports = Vec::new();
+    for caps in PYTHON_IMPORT.captures_iter(content) {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        for module in caps[1].split(',') {
+            let module = module.split_whitespace().next().unwrap_or("");
+            let segments: Vec<&str> = module.split('.').collect();
+            // `import a.b.c` also imports `a.b` and `a`; the innermost local one is kept
+            let resolved = roots.iter().find_map(|root| {
+                (1..=segments.len())
+                    .rev()
+                    .find_map(|len| python_module_file(root, &segments[..len]))
+            });
+            imports.extend(resolved.map(|file| (start, file)));
+        }
+    }
+    for caps in PYTHON_FROM.captures_iter(content) {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        let module = &caps[1];
+        let dots = module.len() - module.trim_start_matches('.').len();
+        let segments: Vec<&str> = module[dots..]
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let names: Vec<&str> = caps[2]
+            .trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace())
+            .split(',')
+            .filter_map(|name| name.split_whitespace().next())
+            .filter(|name| *name != "*")
+            .collect();
+        let bases = if dots > 0 {
+            let base = (1..dots).fold(parent(path).to_path_buf(), |dir, _| {
+                parent(&dir).to_path_buf()
+            });
+            vec![base]
+        } else {
+            roots.clone()
+        };
+        for base in bases {
+            // Imported names may be submodules rather than items of the module
+            let submodules: Vec<PathBuf> = names
+                .iter()
+                .filter_map(|name| {
+                    let mut segments = segments.clone();
+                    segments.push(name);
+                    python_module_file(&base, &segments)
+                })
+                .collect();
+            if !submodules.is_empty() {
+                imports.extend(submodules.into_iter().map(|file| (start, file)));
+                break;
+            }
+            if let Some(module) = python_module_file(&base, &segments) {
+                imports.push((start, module));
+                break;
+            }
+        }
+    }
+    in_source_order(imports)
+}
+
+fn js_imports(path: &Path, content: &str) -> Vec<PathBuf> {
+    let dir = parent(path);
+    JS_IMPORT
+        .captures_iter(content)
+        .filter_map(|caps| {
+            let target = dir.join(&caps[1]);
+            let mut candidates = vec![target.clone()];
+            candidates.extend(JS_EXTENSIONS.iter().map(|ext| with_suffix(&target, ext)));
+            // TypeScript sources are imported by the name of their compiled `.js` files
+            if let Some(ext @ ("js" | "jsx" | "mjs")) = target.extension().and_then(|e| e.to_str())
+            {
+                let sources: &[&str] = match ext {
+                    "mjs" => &["mts"],
+                    _ => &["ts", "tsx"],
+                };
+                candidates.extend(sources.iter().map(|source| target.with_extension(source)));
+            }
+            candidates.extend(
+                JS_EXTENSIONS
+                    .iter()
+                    .map(|ext| target.join(format!("index.{}", ext))),
+            );
+            first_file(candidates)
+        })
+        .collect()
+}
+
+fn go_imports(path: &Path, content: &str) -> Vec<PathBuf> {
+    let Some(module_dir) = find_root(path, "go.mod") else {
+        return Vec::new();
+    };
+    let Some(module) = fs::read_to_string(module_dir.join("go.mod"))
+        .ok()
+        .and_then(|go_mod| GO_MODULE.captures(&go_mod).map(|caps| caps[1].to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut packages: Vec<String> = GO_IMPORT_LINE
+        .captures_iter(content)
+        .map(|caps| caps[1].to_string())
+        .collect();
+    for block in GO_IMPORT_BLOCK.captures_iter(content) {
+        packages.extend(
+            GO_IMPORT_SPEC
+                .captures_iter(&block[1])
+                .map(|caps| caps[1].to_string()),
+        );
+    }
+
+    let mut imports = Vec::new();
+    for package in packages {
+        let dir = match package.strip_prefix(&module) {
+            Some("") if module_dir.as_os_str().is_empty() => PathBuf::from("."),
+            Some("") => module_dir.to_path_buf(),
+            Some(rest) if rest.starts_with('/') => module_dir.join(&rest[1..]),
+            _ => continue,
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|file| {
+                let name = file.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                name.ends_with(".go") && !name.ends_with("_test.go") && file.is_file()
+            })
+            .collect();
+        files.sort();
+        imports.extend(files);
+    }
+    imports
+}
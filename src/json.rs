@@ -0,0 +1,88 @@
+//! JSON and JSON Lines output.
+//!
+//! A copy emits a header record followed by one record per file:
+//!
+//! ```json
+//! {"type":"header","model":"gpt-4o","files":1,"total_tokens":42,"skipped":[]}
+//! {"type":"file","path":"src/a.rs","language":"rust","content":"fn a() {}\n","tokens":4,"lines":1,"sha256":"509a..."}
+//! ```
+//!
+//! `jsonl` writes one record per line; `json` writes the same records as an
+//! array.
+
+use serde::{Deserialize, Serialize};
+
+/// How the copied files are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Render the output template.
+    #[default]
+    Text,
+    /// A JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn is_json(&self) -> bool {
+        *self != OutputFormat::Text
+    }
+}
+
+/// A record of JSON output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record {
+    Header(HeaderRecord),
+    File(FileRecord),
+}
+
+/// Summary of a copy, written before the file records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeaderRecord {
+    pub model: String,
+    /// Number of files copied.
+    pub files: usize,
+    /// Tokens of the file records, as counted for the token budget.
+    pub total_tokens: usize,
+    pub skipped: Vec<SkippedRecord>,
+}
+
+/// A file left out of the copy and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedRecord {
+    pub path: String,
+    pub reason: String,
+}
+
+/// A copied file, or a diff or line range of one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub path: String,
+    pub language: String,
+    pub content: String,
+    /// Tokens of `content`, when a tokenizer was available.
+    pub tokens: Option<usize>,
+    pub lines: usize,
+    pub sha256: String,
+    /// First line of a selected range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+}
+
+/// Serializes `record` as a single line, without the trailing newline.
+pub fn to_line(record: &Record) -> String {
+    serde_json::to_string(record).expect("records always serialize")
+}
+
+/// Joins the header and the file record lines in `records` into the output.
+pub fn write_records(format: OutputFormat, header: &HeaderRecord, records: &str) -> String {
+    let header = to_line(&Record::Header(header.clone()));
+    let lines = std::iter::once(header.as_str()).chain(records.lines());
+    match format {
+        OutputFormat::Json => format!("[\n{}\n]\n", lines.collect::<Vec<_>>().join(",\n")),
+        _ => lines.map(|line| format!("{}\n", line)).collect(),
+    }
+}
@@ -1,14 +1,37 @@
 pub mod applier;
+pub mod bundle;
+pub mod compact;
 pub mod copy;
 pub mod errors;
 pub mod extractor;
+pub mod filter;
+pub mod git;
+pub mod grep;
 pub mod ignore;
+pub mod imports;
+pub mod json;
 pub mod logger;
+pub mod models;
+pub mod notebook;
+pub mod osc52;
+pub mod outline;
+pub mod packing;
+pub mod picker;
+pub mod prompt;
+pub mod redact;
+pub mod remote;
 pub mod reporting;
+pub mod search;
+pub mod selector;
+pub mod sink;
+pub mod symbols;
+pub mod template;
 pub mod trie;
+pub mod usages;
 pub mod utils;
 pub mod watch;
+pub mod xml;
 pub mod llm;
 
 pub use copy::copy_files_to_clipboard;
-pub use ignore::IgnorePatterns;
+pub use crate::ignore::IgnorePatterns;
@@ -1,31 +1,16 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use crate::errors::ClipboardError;
+use crate::models::ModelRegistry;
 use reqwest::Client;
 use std::env;
 use tracing::info;
 use std::ops::Add;
 use std::collections::HashMap;
-use once_cell::sync::Lazy;
 use std::time::Instant;
 use tokio::select;
 use tokio::signal;
 
-pub static MODEL_PRICING: Lazy<HashMap<&'static str, ModelPricing>> = Lazy::new(|| {
-    let mut m = HashMap::new();
-    m.insert("gpt-4o", ModelPricing::new(2.50, 1.25, 10.0));
-    m.insert("gpt-4o-2024-11-20", ModelPricing::new(2.50, 1.25, 10.0));
-    m.insert("gpt-4o-2024-08-06", ModelPricing::new(2.50, 1.25, 10.0));
-    m.insert("gpt-4o-2024-05-13", ModelPricing::new(5.00, 2.50, 15.0));
-    m.insert("gpt-4o-mini", ModelPricing::new(0.150, 0.075, 0.600));
-    m.insert("gpt-4o-mini-2024-07-18", ModelPricing::new(0.150, 0.075, 0.600));
-    m.insert("o1-preview", ModelPricing::new(15.0, 7.50, 60.0));
-    m.insert("o1-preview-2024-09-12", ModelPricing::new(15.0, 7.50, 60.0));
-    m.insert("o1-mini", ModelPricing::new(3.0, 1.50, 12.0));
-    m.insert("o1-mini-2024-09-12", ModelPricing::new(3.0, 1.50, 12.0));
-    m
-});
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ModelPricing {
     pub input_price: f64,    // per 1M tokens
     pub cached_price: f64,   // per 1M tokens
@@ -80,11 +65,14 @@ impl ModelPricing {
 }
 
 impl TokenUsage {
-    pub fn get_cost(&self, model: &str) -> Option<f64> {
-        MODEL_PRICING.get(model).map(|pricing| pricing.calculate_cost(self))
+    pub fn get_cost(&self, registry: &ModelRegistry, model: &str) -> Option<f64> {
+        registry
+            .get(model)
+            .and_then(|info| info.pricing)
+            .map(|pricing| pricing.calculate_cost(self))
     }
 
-    pub fn format_details(&self, model: &str) -> String {
+    pub fn format_details(&self, registry: &ModelRegistry, model: &str) -> String {
         let mut details = format!("prompt={}, completion={}, total={}", 
             self.prompt_tokens, 
             self.completion_tokens, 
@@ -104,7 +92,7 @@ impl TokenUsage {
             ));
         }
 
-        if let Some(cost) = self.get_cost(model) {
+        if let Some(cost) = self.get_cost(registry, model) {
             details.push_str(&format!(", cost=${:.6}", cost));
         }
 
@@ -143,6 +131,7 @@ pub struct LLMClient {
     model: String,
     store_enabled: bool,
     metadata: HashMap<String, String>,
+    registry: ModelRegistry,
 }
 
 impl LLMClient {
@@ -152,9 +141,16 @@ impl LLMClient {
             model,
             store_enabled,
             metadata,
+            registry: ModelRegistry::global().clone(),
         }
     }
 
+    /// Prices usage with the models of `registry`.
+    pub fn with_registry(mut self, registry: ModelRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
     fn build_request_body(&self, messages: serde_json::Value, prediction: Option<&str>) -> serde_json::Value {
         let mut body = serde_json::json!({
             "model": self.model,
@@ -222,7 +218,7 @@ impl LLMClient {
         let response_time = start_time.elapsed();
 
         info!("Token usage: {} (response time: {:?}{})", 
-            usage.format_details(&self.model),
+            usage.format_details(&self.registry, &self.model),
             response_time,
             if prediction.is_some() { " [with prediction]" } else { "" }
         );
@@ -279,7 +275,7 @@ impl LLMClient {
         let response_time = start_time.elapsed();
 
         info!("Token usage: {} (response time: {:?})", 
-            usage.format_details(&self.model),
+            usage.format_details(&self.registry, &self.model),
             response_time
         );
 
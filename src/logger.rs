@@ -6,6 +6,7 @@ pub fn initialize_logger() {
     let subscriber = fmt()
         .with_env_filter(env_filter)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set global logger");
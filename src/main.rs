@@ -1,9 +1,24 @@
-use clap::{Parser, Subcommand};
-use snippy::copy::ClipboardCopierConfig;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use snippy::bundle::{project_root, root_relative_pattern, Bundle, BundleStore};
+use snippy::compact::CompactOptions;
+use snippy::copy::{ClipboardCopierConfig, DiffMode};
+use snippy::copy::copy_files_from;
 use snippy::copy_files_to_clipboard;
-use snippy::extractor::markdown::MarkdownExtractor;
+use snippy::errors::ClipboardError;
+use snippy::extractor::auto::AutoExtractor;
+use snippy::git::ChangeScope;
+use snippy::json::OutputFormat;
+use snippy::notebook::{NotebookOptions, DEFAULT_OUTPUT_LINES};
+use snippy::grep::DEFAULT_GREP_CONTEXT;
+use snippy::search::DEFAULT_QUERY_TOP;
+use snippy::usages::{UsageOptions, DEFAULT_USAGE_CONTEXT};
+use snippy::reporting::{StatsFormat, StatsOptions, StatsSort};
+use snippy::sink::{ClipboardBackend, SinkKind};
 use snippy::logger::initialize_logger;
+use snippy::models::ModelRegistry;
 use snippy::watch::{ClipboardWatcher, WatcherConfig};
+use snippy::xml::XmlAttribute;
 use std::path::PathBuf;
 use std::collections::HashMap;
 use tracing::{error, info, warn};
@@ -16,15 +31,159 @@ struct CliArgs {
 }
 
 #[derive(Subcommand, Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 enum SubCommands {
     Copy(CopyArgs),
     Watch(WatchArgs),
+    /// Save, list and copy named sets of file patterns
+    #[command(subcommand)]
+    Bundle(BundleCommand),
+    /// Print token statistics without copying anything
+    Stats(StatsCommandArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+struct StatsArgs {
+    #[arg(long = "stats", value_enum, default_value = "tree", help = "Print the statistics as a tree or as JSON on stdout")]
+    pub stats_format: StatsFormat,
+    #[arg(long, value_enum, default_value = "name", help = "Order of the entries of each directory")]
+    pub sort: StatsSort,
+    #[arg(long, value_name = "N", help = "List the N heaviest files")]
+    pub top: Option<usize>,
+    #[arg(long, value_name = "DEPTH", help = "Collapse directories below this depth")]
+    pub depth: Option<usize>,
+    #[arg(long, help = "Print the tree in plain ASCII")]
+    pub no_emoji: bool,
+}
+
+impl StatsArgs {
+    fn options(&self) -> StatsOptions {
+        StatsOptions {
+            format: self.stats_format,
+            sort: self.sort,
+            top: self.top,
+            max_depth: self.depth,
+            emoji: !self.no_emoji,
+        }
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+struct CompactArgs {
+    #[arg(long, help = "Apply every compaction pass below")]
+    pub compact: bool,
+    #[arg(long, help = "Remove comments and docstrings")]
+    pub strip_comments: bool,
+    #[arg(long, help = "Keep doc comments and docstrings when removing comments")]
+    pub keep_doc_comments: bool,
+    #[arg(long, help = "Collapse runs of blank lines into one")]
+    pub collapse_blank_lines: bool,
+    #[arg(long, help = "Drop license headers already copied from another file")]
+    pub strip_license_headers: bool,
+    #[arg(long, help = "Omit test modules, functions and suites")]
+    pub strip_tests: bool,
+}
+
+impl CompactArgs {
+    fn options(&self) -> CompactOptions {
+        let all = if self.compact {
+            CompactOptions::all()
+        } else {
+            CompactOptions::default()
+        };
+        CompactOptions {
+            strip_comments: all.strip_comments || self.strip_comments,
+            keep_doc_comments: self.keep_doc_comments,
+            collapse_blank_lines: all.collapse_blank_lines || self.collapse_blank_lines,
+            strip_license_headers: all.strip_license_headers || self.strip_license_headers,
+            strip_tests: all.strip_tests || self.strip_tests,
+        }
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+struct StatsCommandArgs {
+    patterns: Vec<String>,
+    #[arg(short = 'M', long, default_value = "gpt-4o")]
+    model: String,
+    #[arg(long, env = "SNIPPY_MODELS_FILE", help = "JSON file of extra models [default: <config dir>/snippy/models.json]")]
+    pub models_file: Option<PathBuf>,
+    #[arg(long = "ignore", help = "Patterns to ignore (e.g., 'target/**', '**/*.pyc')")]
+    pub ignore_patterns: Option<Vec<String>>,
+    #[arg(long, help = "Do not read .gitignore, .ignore or .snippyignore files")]
+    pub no_ignore_files: bool,
+    #[arg(long, help = "Only count files tracked by git")]
+    pub tracked_only: bool,
+    #[arg(long, help = "Token budget; lower-priority files that do not fit are marked omitted")]
+    pub max_tokens: Option<usize>,
+    #[arg(long, help = "Count only imports, types and signatures of supported source files")]
+    pub outline: bool,
+    #[command(flatten)]
+    pub compact: CompactArgs,
+    #[command(flatten)]
+    pub stats: StatsArgs,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+enum BundleCommand {
+    /// Save the file patterns and options of a copy under a name
+    Save {
+        name: String,
+        #[command(flatten)]
+        copy: CopyArgs,
+    },
+    /// Copy the files of a saved bundle
+    Copy(BundleCopyArgs),
+    /// List the saved bundles
+    List,
+    /// Delete a saved bundle
+    Rm { name: String },
+}
+
+#[derive(Parser, Debug, Clone)]
+struct BundleCopyArgs {
+    name: String,
+    #[arg(short = 's', long, default_value = "false")]
+    no_stats: bool,
+    #[arg(long, value_enum, env = "SNIPPY_SINK", help = "Where to write the output [default: clipboard, or file with --output]")]
+    pub sink: Option<SinkKind>,
+    #[arg(short = 'o', long, help = "Write the output to this file")]
+    pub output: Option<PathBuf>,
+    #[arg(long, value_enum, default_value = "auto", env = "SNIPPY_CLIPBOARD_BACKEND", help = "Clipboard mechanism; auto uses OSC 52 over SSH without a display")]
+    pub clipboard_backend: ClipboardBackend,
+    #[command(flatten)]
+    pub stats: StatsArgs,
+    #[arg(long, env = "SNIPPY_MODELS_FILE", help = "JSON file of extra models [default: <config dir>/snippy/models.json]")]
+    pub models_file: Option<PathBuf>,
+    #[arg(long, env = "SNIPPY_PROMPTS_DIR", help = "Directory of named prompts [default: <config dir>/snippy/prompts]")]
+    pub prompts_dir: Option<PathBuf>,
+    #[arg(long, env = "SNIPPY_GIT_CACHE_DIR", help = "Where to cache mirrors of git sources [default: user cache dir]")]
+    pub git_cache_dir: Option<PathBuf>,
 }
 
 #[derive(Parser, Debug, Clone)]
 struct CopyArgs {
-    #[arg(required = true)]
+    #[arg(required_unless_present_any = ["changed", "staged", "since", "interactive", "usages", "grep", "query"])]
     files: Vec<String>,
+    #[arg(short = 'i', long, help = "Choose the files in an interactive picker, starting from the given patterns")]
+    pub interactive: bool,
+    #[arg(long, value_name = "DEPTH", num_args = 0..=1, require_equals = true, default_missing_value = "1", help = "Add the local files the copied files import, following imports DEPTH levels deep")]
+    pub follow_imports: Option<usize>,
+    #[arg(long, value_name = "SYMBOL", help = "Copy the references to a symbol such as LLMClient::call, searching the given patterns or the workspace; matched by name with the qualifier in scope, not resolved")]
+    pub usages: Vec<String>,
+    #[arg(long, value_name = "LINES", default_value_t = DEFAULT_USAGE_CONTEXT, help = "Lines copied above and below each reference")]
+    pub usage_context: usize,
+    #[arg(long, help = "Copy the functions enclosing the references instead of the lines around them")]
+    pub usage_functions: bool,
+    #[arg(long, value_name = "REGEX", help = "Only copy the regions of files around the lines matching REGEX")]
+    pub grep: Option<String>,
+    #[arg(short = 'C', long, value_name = "LINES", default_value_t = DEFAULT_GREP_CONTEXT, help = "Lines copied above and below each --grep match")]
+    pub grep_context: usize,
+    #[arg(long, value_name = "TEXT", help = "Copy the files that best match a natural-language query, ranked by a local search index")]
+    pub query: Option<String>,
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_QUERY_TOP, help = "Number of best matching files copied for --query")]
+    pub top_k: usize,
     #[arg(short = 'm', long, default_value = "false")]
     no_markdown: bool,
     #[arg(short = 'l', long, default_value = None)]
@@ -33,16 +192,80 @@ struct CopyArgs {
     prefix: String,
     #[arg(short = 'M', long, default_value = "gpt-4o")]
     model: String,
+    #[arg(long, env = "SNIPPY_MODELS_FILE", help = "JSON file of extra models [default: <config dir>/snippy/models.json]")]
+    pub models_file: Option<PathBuf>,
     #[arg(short = 's', long, default_value = "false")]
     no_stats: bool,
+    #[command(flatten)]
+    pub stats: StatsArgs,
     #[arg(long, default_value = "MarkdownHeading")]
     filename_format: Option<String>,
     #[arg(long, default_value = "# Relevant Code\n")]
     pub first_line: String,
     #[arg(long, help = "Format the output as XML")]
     pub xml: bool,
+    #[arg(long, value_name = "PRESET|FILE", env = "SNIPPY_TEMPLATE", help = "Output template: markdown, markdown-first-line, plain, xml, documents, or a template file")]
+    pub template: Option<String>,
+    #[arg(long, value_enum, value_delimiter = ',', help = "Metadata attributes for XML <file> elements")]
+    pub xml_attrs: Vec<XmlAttribute>,
+    #[arg(long, value_enum, default_value = "text", help = "Write JSON records instead of rendering the template")]
+    pub format: OutputFormat,
     #[arg(long = "ignore", help = "Patterns to ignore (e.g., 'target/**', '**/*.pyc')")]
     pub ignore_patterns: Option<Vec<String>>,
+    #[arg(long, help = "Do not read .gitignore, .ignore or .snippyignore files")]
+    pub no_ignore_files: bool,
+    #[arg(long, help = "Only copy files tracked by git")]
+    pub tracked_only: bool,
+    #[arg(long, help = "Token budget; lower-priority files that do not fit are omitted")]
+    pub max_tokens: Option<usize>,
+    #[arg(long, help = "Also copy files that look minified, generated or duplicated")]
+    pub no_content_filters: bool,
+    #[arg(long, value_name = "BYTES", help = "Skip files larger than this, unless named explicitly")]
+    pub max_file_bytes: Option<u64>,
+    #[arg(long, value_name = "TOKENS", help = "Skip files with more tokens than this, unless named explicitly")]
+    pub max_file_tokens: Option<usize>,
+    #[arg(long, help = "Copy only imports, types and signatures of Rust, Python, TypeScript and Go files")]
+    pub outline: bool,
+    #[command(flatten)]
+    pub compact: CompactArgs,
+    #[arg(long, help = "Copy Jupyter notebooks as JSON instead of numbered cells")]
+    pub raw_notebooks: bool,
+    #[arg(long, help = "Leave cell outputs out of rendered notebooks")]
+    pub no_notebook_outputs: bool,
+    #[arg(long, value_name = "LINES", default_value_t = DEFAULT_OUTPUT_LINES, help = "Lines kept of each notebook cell output; 0 keeps them all")]
+    pub notebook_output_lines: usize,
+    #[arg(long, help = "Copy secrets as they are instead of replacing them with placeholders")]
+    pub no_redact: bool,
+    #[arg(long = "redact-rule", value_name = "NAME=REGEX", help = "Extra redaction rule; a (?P<secret>...) group limits what is replaced")]
+    pub redact_rules: Vec<String>,
+    #[arg(long = "redact-allow", value_name = "REGEX", help = "Never redact values matching this pattern")]
+    pub redact_allowlist: Vec<String>,
+    #[arg(long, value_name = "NAME|FILE", help = "Wrap the copied files in a prompt, inserted at its {$CONTEXT} slot")]
+    pub prompt: Option<String>,
+    #[arg(long, requires = "prompt", help = "Task for the prompt's {$TASK} and {$CODING_TASK} slots")]
+    pub task: Option<String>,
+    #[arg(long = "var", value_name = "KEY=VALUE", requires = "prompt", help = "Fill a {$KEY} slot of the prompt")]
+    pub prompt_vars: Vec<String>,
+    #[arg(long, env = "SNIPPY_PROMPTS_DIR", help = "Directory of named prompts [default: <config dir>/snippy/prompts]")]
+    pub prompts_dir: Option<PathBuf>,
+    #[arg(long, conflicts_with_all = ["staged", "since"], help = "Copy files with uncommitted changes, including untracked files")]
+    pub changed: bool,
+    #[arg(long, conflicts_with = "since", help = "Copy files with staged changes")]
+    pub staged: bool,
+    #[arg(long, value_name = "REF", help = "Copy files that differ from a git ref")]
+    pub since: Option<String>,
+    #[arg(long, requires = "since", help = "Compare against the merge-base of --since and HEAD")]
+    pub merge_base: bool,
+    #[arg(long, value_enum, default_value = "none", help = "Emit each file's diff with or instead of its content")]
+    pub diff: DiffMode,
+    #[arg(long, value_enum, env = "SNIPPY_SINK", help = "Where to write the output [default: clipboard, or file with --output]")]
+    pub sink: Option<SinkKind>,
+    #[arg(short = 'o', long, help = "Write the output to this file")]
+    pub output: Option<PathBuf>,
+    #[arg(long, value_enum, default_value = "auto", env = "SNIPPY_CLIPBOARD_BACKEND", help = "Clipboard mechanism; auto uses OSC 52 over SSH without a display")]
+    pub clipboard_backend: ClipboardBackend,
+    #[arg(long, env = "SNIPPY_GIT_CACHE_DIR", help = "Where to cache mirrors of git sources [default: user cache dir]")]
+    pub git_cache_dir: Option<PathBuf>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -57,6 +280,8 @@ struct WatchArgs {
     pub ai: bool,
     #[arg(long, default_value = "gpt-4o-mini")]
     pub model: String,
+    #[arg(long, env = "SNIPPY_MODELS_FILE", help = "JSON file of extra models [default: <config dir>/snippy/models.json]")]
+    pub models_file: Option<PathBuf>,
     #[arg(long = "ignore", help = "Patterns to ignore (e.g., 'target/**', '**/*.pyc')")]
     pub ignore_patterns: Option<Vec<String>>,
     #[arg(long, help = "Enable predictions for faster responses when files exist")]
@@ -69,43 +294,215 @@ struct WatchArgs {
     pub once: bool,
 }
 
+/// Builds the copy configuration from the command line.
+fn copier_config(args: CopyArgs) -> ClipboardCopierConfig {
+    ClipboardCopierConfig {
+        no_markdown: args.no_markdown,
+        line_number: args.line_number,
+        prefix: args.prefix.clone(),
+        model: args.model.clone(),
+        models_file: args.models_file,
+        no_stats: args.no_stats,
+        stats: args.stats.options(),
+        stats_only: false,
+        filename_format: args
+            .filename_format
+            .clone()
+            .unwrap_or_else(|| "None".to_owned()),
+        first_line: args.first_line,
+        xml: args.xml,
+        template: args.template,
+        xml_attributes: args.xml_attrs,
+        format: args.format,
+        ignore_patterns: args.ignore_patterns,
+        no_ignore_files: args.no_ignore_files,
+        tracked_only: args.tracked_only,
+        max_tokens: args.max_tokens,
+        no_content_filters: args.no_content_filters,
+        max_file_bytes: args.max_file_bytes,
+        max_file_tokens: args.max_file_tokens,
+        outline: args.outline,
+        compact: args.compact.options(),
+        notebook: NotebookOptions {
+            render: !args.raw_notebooks,
+            outputs: !args.no_notebook_outputs,
+            max_output_lines: Some(args.notebook_output_lines).filter(|&lines| lines > 0),
+        },
+        interactive: args.interactive,
+        follow_imports: args.follow_imports,
+        usages: UsageOptions {
+            symbols: args.usages,
+            context: args.usage_context,
+            functions: args.usage_functions,
+        },
+        grep: args.grep,
+        grep_context: args.grep_context,
+        query: args.query,
+        query_top: args.top_k,
+        no_redact: args.no_redact,
+        redact_rules: args.redact_rules,
+        redact_allowlist: args.redact_allowlist,
+        prompt: args.prompt,
+        task: args.task,
+        prompt_vars: args.prompt_vars,
+        prompts_dir: args.prompts_dir,
+        change_scope: if args.changed {
+            Some(ChangeScope::WorkingTree)
+        } else if args.staged {
+            Some(ChangeScope::Staged)
+        } else {
+            args.since.map(|reference| ChangeScope::Since {
+                reference,
+                merge_base: args.merge_base,
+            })
+        },
+        diff_mode: args.diff,
+        sink: args.sink.unwrap_or(if args.output.is_some() {
+            SinkKind::File
+        } else {
+            SinkKind::Clipboard
+        }),
+        output: args.output,
+        clipboard_backend: args.clipboard_backend,
+        git_cache_dir: args.git_cache_dir,
+    }
+}
+
+/// Flags of `args` that are not stored in bundles.
+///
+/// Flags also read from the environment only count when given on the command
+/// line, since `bundle copy` reads the same variables.
+fn unsaved_flags(args: &CopyArgs, matches: &ArgMatches) -> Vec<&'static str> {
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let flags = [
+        ("--interactive", args.interactive),
+        ("--usages", !args.usages.is_empty()),
+        ("--grep", args.grep.is_some()),
+        ("--query", args.query.is_some()),
+        ("--changed", args.changed),
+        ("--staged", args.staged),
+        ("--since", args.since.is_some()),
+        ("--diff", args.diff != DiffMode::None),
+        ("--raw-notebooks", args.raw_notebooks),
+        ("--no-notebook-outputs", args.no_notebook_outputs),
+        ("--notebook-output-lines", args.notebook_output_lines != DEFAULT_OUTPUT_LINES),
+        ("--output", args.output.is_some()),
+        ("--sink", given("sink")),
+        ("--clipboard-backend", given("clipboard_backend")),
+        ("--no-stats", args.no_stats),
+        ("--stats", given("stats_format")),
+        ("--sort", given("sort")),
+        ("--top", args.stats.top.is_some()),
+        ("--depth", args.stats.depth.is_some()),
+        ("--no-emoji", args.stats.no_emoji),
+        ("--models-file", given("models_file")),
+        ("--prompts-dir", given("prompts_dir")),
+        ("--git-cache-dir", given("git_cache_dir")),
+    ];
+    flags
+        .into_iter()
+        .filter(|&(_, set)| set)
+        .map(|(flag, _)| flag)
+        .collect()
+}
+
+/// Runs a `bundle` subcommand from the project containing the working directory.
+///
+/// `matches` are the parsed arguments of the subcommand.
+async fn run_bundle(command: BundleCommand, matches: &ArgMatches) -> Result<(), ClipboardError> {
+    let cwd = std::env::current_dir()?;
+    let root = project_root(&cwd);
+    let store = BundleStore::for_project(&cwd);
+    match command {
+        BundleCommand::Save { name, copy } => {
+            let unsaved = unsaved_flags(&copy, matches);
+            if !unsaved.is_empty() {
+                return Err(ClipboardError::ConfigError(format!(
+                    "Bundles do not store {}; pass them when copying instead",
+                    unsaved.join(", ")
+                )));
+            }
+            let patterns = copy
+                .files
+                .iter()
+                .map(|pattern| root_relative_pattern(pattern, &cwd, &root))
+                .collect();
+            let bundle = Bundle::from_config(patterns, &copier_config(copy));
+            let path = store.save(&name, &bundle)?;
+            info!("Saved bundle {} to {}", name, path.display());
+        }
+        BundleCommand::Copy(args) => {
+            let bundle = store.load(&args.name)?;
+            let config = bundle.apply(ClipboardCopierConfig {
+                no_stats: args.no_stats,
+                sink: args.sink.unwrap_or(if args.output.is_some() {
+                    SinkKind::File
+                } else {
+                    SinkKind::Clipboard
+                }),
+                output: args.output.map(|output| cwd.join(output)),
+                clipboard_backend: args.clipboard_backend,
+                stats: args.stats.options(),
+                models_file: args.models_file,
+                prompts_dir: args.prompts_dir,
+                git_cache_dir: args.git_cache_dir,
+                ..ClipboardCopierConfig::default()
+            });
+            // Bundle patterns are relative to the project root
+            copy_files_from(&root, config, bundle.resolve_patterns(&root)).await?;
+        }
+        BundleCommand::List => {
+            for name in store.list()? {
+                let bundle = store.load(&name)?;
+                println!("{}\t{}", name, bundle.patterns.join(" "));
+            }
+        }
+        BundleCommand::Rm { name } => {
+            store.remove(&name)?;
+            info!("Removed bundle {}", name);
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
-    let cli_args = CliArgs::parse();
+    let matches = CliArgs::command().get_matches();
+    let cli_args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     initialize_logger();
 
     match cli_args.cmd {
         SubCommands::Copy(args) => {
-            let copier_config = ClipboardCopierConfig {
-                no_markdown: args.no_markdown,
-                line_number: args.line_number,
-                prefix: args.prefix.clone(),
-                model: args.model.clone(),
-                no_stats: args.no_stats,
-                filename_format: args
-                    .filename_format
-                    .clone()
-                    .unwrap_or_else(|| "None".to_owned()),
-                first_line: args.first_line,
-                xml: args.xml,
-                ignore_patterns: args.ignore_patterns,
-            };
-            if let Err(e) = copy_files_to_clipboard(copier_config, args.files).await {
-                eprintln!("Error copying files to clipboard: {}", e);
+            let files = args.files.clone();
+            let copier_config = copier_config(args);
+            if let Err(e) = copy_files_to_clipboard(copier_config, files).await {
+                eprintln!("Error copying files: {}", e);
+                std::process::exit(1);
             }
         }
         SubCommands::Watch(args) => {
             info!("Starting Clipboard Watcher");
 
-            let mut watcher_config = WatcherConfig::default();
-            watcher_config.interval_ms = args.interval_ms;
-            watcher_config.watch_path = PathBuf::from(args.watch_path.unwrap_or_else(|| ".".to_owned()));
-            watcher_config.first_line_identifier = args.first_line;
-            watcher_config.ai_enabled = args.ai;
-            watcher_config.model = args.model;
-            watcher_config.predictions_enabled = args.predictions;
-            watcher_config.store_enabled = args.store;
-            watcher_config.one_shot = args.once;
+            let registry = match &args.models_file {
+                Some(path) => ModelRegistry::load(Some(path)).unwrap_or_else(|e| {
+                    eprintln!("Error loading models: {}", e);
+                    std::process::exit(1);
+                }),
+                None => ModelRegistry::global().clone(),
+            };
+
+            let mut watcher_config = WatcherConfig {
+                interval_ms: args.interval_ms,
+                watch_path: PathBuf::from(args.watch_path.unwrap_or_else(|| ".".to_owned())),
+                first_line_identifier: args.first_line,
+                ai_enabled: args.ai,
+                model: args.model,
+                predictions_enabled: args.predictions,
+                store_enabled: args.store,
+                one_shot: args.once,
+                registry,
+                ..WatcherConfig::default()
+            };
             
             // Parse metadata
             let mut metadata = HashMap::new();
@@ -132,7 +529,7 @@ async fn main() {
                 watcher_config.ignore_patterns = patterns;
             }
 
-            let mut watcher = ClipboardWatcher::new(watcher_config, MarkdownExtractor::new());
+            let mut watcher = ClipboardWatcher::new(watcher_config, AutoExtractor::new());
 
             if let Err(e) = watcher.run().await {
                 error!("Clipboard watcher terminated with error: {}", e);
@@ -140,5 +537,34 @@ async fn main() {
 
             info!("Clipboard Watcher has stopped.");
         }
+        SubCommands::Stats(args) => {
+            let config = ClipboardCopierConfig {
+                model: args.model,
+                models_file: args.models_file,
+                ignore_patterns: args.ignore_patterns,
+                no_ignore_files: args.no_ignore_files,
+                tracked_only: args.tracked_only,
+                max_tokens: args.max_tokens,
+                outline: args.outline,
+                compact: args.compact.options(),
+                stats: args.stats.options(),
+                stats_only: true,
+                ..ClipboardCopierConfig::default()
+            };
+            if let Err(e) = copy_files_to_clipboard(config, args.patterns).await {
+                eprintln!("Error computing statistics: {}", e);
+                std::process::exit(1);
+            }
+        }
+        SubCommands::Bundle(command) => {
+            let matches = matches
+                .subcommand_matches("bundle")
+                .and_then(|m| m.subcommand())
+                .map_or(&matches, |(_, m)| m);
+            if let Err(e) = run_bundle(command, matches).await {
+                eprintln!("Error running bundle command: {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
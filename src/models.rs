@@ -0,0 +1,272 @@
+//! Registry of models: tokenizer, context window, output limit and pricing.
+//!
+//! The built-in models can be extended or overridden by a JSON file, by
+//! default `models.json` in the snippy config directory:
+//!
+//! ```json
+//! {
+//!   "models": {
+//!     "my-model": {
+//!       "tokenizer": { "kind": "approximate", "encoding": "cl100k_base", "margin": 0.15 },
+//!       "context_window": 32768,
+//!       "max_output_tokens": 4096,
+//!       "pricing": { "input_price": 0.5, "cached_price": 0.25, "output_price": 1.5 }
+//!     }
+//!   }
+//! }
+//! ```
+
+use crate::errors::ClipboardError;
+use crate::llm::ModelPricing;
+use crate::template::TokenCounter;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer as TiktokenTokenizer};
+use tracing::{debug, warn};
+
+/// Error margin assumed for models that are neither built in nor configured.
+pub const UNKNOWN_MODEL_MARGIN: f64 = 0.25;
+
+/// Models of the built-in registry and the default models file.
+static REGISTRY: Lazy<ModelRegistry> = Lazy::new(|| {
+    ModelRegistry::load(None).unwrap_or_else(|e| {
+        warn!("Ignoring models file: {}", e);
+        ModelRegistry::builtin()
+    })
+});
+
+/// A BPE encoding shipped with tiktoken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    O200kBase,
+    Cl100kBase,
+    P50kBase,
+    R50kBase,
+}
+
+impl Encoding {
+    pub fn counter(&self) -> Result<TokenCounter, ClipboardError> {
+        let bpe = match self {
+            Encoding::O200kBase => tiktoken_rs::o200k_base(),
+            Encoding::Cl100kBase => tiktoken_rs::cl100k_base(),
+            Encoding::P50kBase => tiktoken_rs::p50k_base(),
+            Encoding::R50kBase => tiktoken_rs::r50k_base(),
+        };
+        bpe.map(TokenCounter::new)
+            .map_err(|e| ClipboardError::TokenizerError(e.to_string()))
+    }
+}
+
+/// How tokens are counted for a model.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TokenizerSpec {
+    /// The model's own encoding.
+    Exact { encoding: Encoding },
+    /// A similar encoding, off by up to `margin` (0.2 is ±20%).
+    Approximate { encoding: Encoding, margin: f64 },
+}
+
+impl TokenizerSpec {
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            TokenizerSpec::Exact { encoding } | TokenizerSpec::Approximate { encoding, .. } => {
+                *encoding
+            }
+        }
+    }
+
+    /// Error margin of the counts, zero for exact tokenizers.
+    pub fn margin(&self) -> f64 {
+        match self {
+            TokenizerSpec::Exact { .. } => 0.0,
+            TokenizerSpec::Approximate { margin, .. } => *margin,
+        }
+    }
+}
+
+/// What snippy knows about a model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub tokenizer: TokenizerSpec,
+    /// Input and output tokens the model accepts in total.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    #[serde(default)]
+    pub max_output_tokens: Option<usize>,
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
+}
+
+impl ModelInfo {
+    /// Estimated cost in dollars of `tokens` input tokens.
+    pub fn input_cost(&self, tokens: usize) -> Option<f64> {
+        self.pricing
+            .map(|pricing| tokens as f64 / 1_000_000.0 * pricing.input_price)
+    }
+
+    /// Explains why `tokens` input tokens do not fit the context window, if they don't.
+    ///
+    /// Input that fits but leaves less room than the output limit is reported too.
+    pub fn fit_warning(&self, tokens: usize) -> Option<String> {
+        let window = self.context_window?;
+        if tokens > window {
+            return Some(format!(
+                "{} tokens do not fit the {}-token context window",
+                tokens, window
+            ));
+        }
+        match self.max_output_tokens {
+            Some(max_output) if window - tokens < max_output => Some(format!(
+                "{} tokens leave only {} of the {}-token context window for the response",
+                tokens,
+                window - tokens,
+                window
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsFile {
+    models: BTreeMap<String, ModelInfo>,
+}
+
+/// Models by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelRegistry {
+    models: BTreeMap<String, ModelInfo>,
+}
+
+impl ModelRegistry {
+    /// The models snippy knows about out of the box.
+    #[rustfmt::skip]
+    pub fn builtin() -> Self {
+        use Encoding::*;
+        let exact = |encoding| TokenizerSpec::Exact { encoding };
+        let approximate = |encoding, margin| TokenizerSpec::Approximate { encoding, margin };
+        let priced = |input, cached, output| Some(ModelPricing::new(input, cached, output));
+        let mut models = BTreeMap::new();
+        let mut add = |names: &[&str], tokenizer, window, output, pricing| {
+            for name in names {
+                models.insert(
+                    name.to_string(),
+                    ModelInfo {
+                        tokenizer,
+                        context_window: Some(window),
+                        max_output_tokens: Some(output),
+                        pricing,
+                    },
+                );
+            }
+        };
+
+        // OpenAI
+        add(&["gpt-4o", "gpt-4o-2024-11-20", "gpt-4o-2024-08-06"], exact(O200kBase), 128_000, 16_384, priced(2.50, 1.25, 10.0));
+        add(&["gpt-4o-2024-05-13"], exact(O200kBase), 128_000, 4_096, priced(5.00, 2.50, 15.0));
+        add(&["gpt-4o-mini", "gpt-4o-mini-2024-07-18"], exact(O200kBase), 128_000, 16_384, priced(0.150, 0.075, 0.600));
+        add(&["o1-preview", "o1-preview-2024-09-12"], exact(O200kBase), 128_000, 32_768, priced(15.0, 7.50, 60.0));
+        add(&["o1-mini", "o1-mini-2024-09-12"], exact(O200kBase), 128_000, 65_536, priced(3.0, 1.50, 12.0));
+        add(&["gpt-4-turbo"], exact(Cl100kBase), 128_000, 4_096, priced(10.0, 10.0, 30.0));
+        add(&["gpt-4"], exact(Cl100kBase), 8_192, 8_192, priced(30.0, 30.0, 60.0));
+        add(&["gpt-3.5-turbo"], exact(Cl100kBase), 16_385, 4_096, priced(0.50, 0.50, 1.50));
+
+        // Anthropic
+        add(&["claude-3-5-sonnet-latest", "claude-3-5-sonnet-20241022"], approximate(Cl100kBase, 0.2), 200_000, 8_192, priced(3.0, 0.30, 15.0));
+        add(&["claude-3-5-haiku-latest", "claude-3-5-haiku-20241022"], approximate(Cl100kBase, 0.2), 200_000, 8_192, priced(0.80, 0.08, 4.0));
+        add(&["claude-3-opus-latest", "claude-3-opus-20240229"], approximate(Cl100kBase, 0.2), 200_000, 4_096, priced(15.0, 1.50, 75.0));
+
+        // Google
+        add(&["gemini-1.5-pro"], approximate(O200kBase, 0.25), 2_097_152, 8_192, priced(1.25, 0.3125, 5.0));
+        add(&["gemini-1.5-flash"], approximate(O200kBase, 0.25), 1_048_576, 8_192, priced(0.075, 0.01875, 0.30));
+
+        // Meta; pricing depends on the host
+        add(&["llama-3.1-8b", "llama-3.1-70b", "llama-3.1-405b"], approximate(Cl100kBase, 0.1), 128_000, 4_096, None);
+
+        ModelRegistry { models }
+    }
+
+    /// The default models file, in the user config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snippy").join("models.json"))
+    }
+
+    /// The built-in models, extended by `path` or else by the default models file.
+    ///
+    /// Models in the file replace built-in models of the same name. `path`
+    /// must exist; the default file is optional.
+    pub fn load(path: Option<&Path>) -> Result<Self, ClipboardError> {
+        let mut registry = ModelRegistry::builtin();
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match ModelRegistry::default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(registry),
+            },
+        };
+        debug!("Loading models from {:?}", path);
+        let json = std::fs::read_to_string(&path).map_err(|e| {
+            ClipboardError::ConfigError(format!("Failed to read models file {:?}: {}", path, e))
+        })?;
+        let file: ModelsFile = serde_json::from_str(&json).map_err(|e| {
+            ClipboardError::ConfigError(format!("Invalid models file {:?}: {}", path, e))
+        })?;
+        registry.models.extend(file.models);
+        Ok(registry)
+    }
+
+    /// The registry of built-in models and the default models file.
+    pub fn global() -> &'static ModelRegistry {
+        &REGISTRY
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModelInfo> {
+        self.models.get(name)
+    }
+
+    /// Names of the registered models, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.models.keys().map(String::as_str)
+    }
+
+    /// Information about `name`, falling back to tiktoken's model list and
+    /// then to an approximate count for models nobody knows about.
+    pub fn resolve(&self, name: &str) -> ModelInfo {
+        if let Some(info) = self.get(name) {
+            return info.clone();
+        }
+        let tokenizer = match get_tokenizer(name) {
+            Some(TiktokenTokenizer::O200kBase) => Some(Encoding::O200kBase),
+            Some(TiktokenTokenizer::Cl100kBase) => Some(Encoding::Cl100kBase),
+            Some(TiktokenTokenizer::P50kBase | TiktokenTokenizer::P50kEdit) => {
+                Some(Encoding::P50kBase)
+            }
+            Some(TiktokenTokenizer::R50kBase | TiktokenTokenizer::Gpt2) => Some(Encoding::R50kBase),
+            None => None,
+        };
+        let tokenizer = match tokenizer {
+            Some(encoding) => TokenizerSpec::Exact { encoding },
+            None => {
+                warn!(
+                    "Unknown model {}; token counts are approximate (±{:.0}%)",
+                    name,
+                    UNKNOWN_MODEL_MARGIN * 100.0
+                );
+                TokenizerSpec::Approximate {
+                    encoding: Encoding::O200kBase,
+                    margin: UNKNOWN_MODEL_MARGIN,
+                }
+            }
+        };
+        ModelInfo {
+            tokenizer,
+            context_window: None,
+            max_output_tokens: None,
+            pricing: None,
+        }
+    }
+}
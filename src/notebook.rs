@@ -0,0 +1,277 @@
+//! Jupyter notebooks: rendering them as numbered cells, and editing one cell
+//! while keeping the rest of the notebook JSON, outputs included.
+
+use crate::errors::ClipboardError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Output lines kept per output by default.
+pub const DEFAULT_OUTPUT_LINES: usize = 20;
+
+/// A notebook name followed by a cell number, e.g. `analysis.ipynb cell 4`,
+/// `analysis.ipynb:cell 4` or `analysis.ipynb#cell4`.
+static CELL_ADDRESS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^\s*(.+?\.ipynb)\s*(?:[:#]\s*|\s+)cell\s*(\d+)\s*$").expect("Invalid regex")
+});
+
+/// How notebooks are copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotebookOptions {
+    /// Render notebooks as cells instead of copying their JSON.
+    pub render: bool,
+    pub outputs: bool,
+    /// Lines kept of each output; all of them when unset.
+    pub max_output_lines: Option<usize>,
+}
+
+impl Default for NotebookOptions {
+    fn default() -> Self {
+        NotebookOptions {
+            render: true,
+            outputs: true,
+            max_output_lines: Some(DEFAULT_OUTPUT_LINES),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Markdown,
+    Code,
+    Raw,
+}
+
+impl CellKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellKind::Markdown => "markdown",
+            CellKind::Code => "code",
+            CellKind::Raw => "raw",
+        }
+    }
+}
+
+/// A cell with its source and the text of its outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub kind: CellKind,
+    pub source: String,
+    pub outputs: Vec<String>,
+}
+
+/// Whether `path` names a notebook.
+pub fn is_notebook(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Splits a cell address such as `analysis.ipynb cell 4` into the notebook
+/// path and the 1-based cell number.
+pub fn parse_cell_address(name: &str) -> Option<(String, usize)> {
+    let caps = CELL_ADDRESS.captures(name)?;
+    let number = caps[2].parse().ok().filter(|&number| number > 0)?;
+    Some((caps[1].to_string(), number))
+}
+
+/// Joins a multiline notebook string, stored either as a string or a list of lines.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Splits `text` into lines the way Jupyter stores it, keeping the newlines.
+fn lines(text: &str) -> Value {
+    Value::Array(
+        text.split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Text shown for a cell output.
+fn output_text(output: &Value) -> String {
+    match output["output_type"].as_str() {
+        Some("stream") => text(&output["text"]),
+        Some("error") => format!(
+            "{}: {}",
+            output["ename"].as_str().unwrap_or("Error"),
+            output["evalue"].as_str().unwrap_or("")
+        ),
+        _ => {
+            let data = &output["data"];
+            match data.get("text/plain") {
+                Some(plain) => text(plain),
+                None => match data.as_object().and_then(|data| data.keys().next()) {
+                    Some(mime) => format!("[{} output]", mime),
+                    None => String::new(),
+                },
+            }
+        }
+    }
+}
+
+/// Keeps the first `max_lines` lines of `text`, noting how many were cut.
+fn truncate_lines(text: &str, max_lines: Option<usize>) -> String {
+    let total = text.lines().count();
+    match max_lines {
+        Some(max_lines) if total > max_lines => {
+            let mut kept: Vec<&str> = text.lines().take(max_lines).collect();
+            let more = format!("... ({} more lines)", total - max_lines);
+            kept.push(&more);
+            kept.join("\n")
+        }
+        _ => text.trim_end_matches('\n').to_string(),
+    }
+}
+
+/// A parsed notebook.
+///
+/// The JSON is kept as it is, so editing a cell leaves metadata, outputs and
+/// unknown fields untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    json: Value,
+}
+
+impl Notebook {
+    pub fn parse(text: &str) -> Result<Self, ClipboardError> {
+        let json: Value = serde_json::from_str(text).map_err(|e| {
+            ClipboardError::ContentExtractionError(format!("Invalid notebook: {}", e))
+        })?;
+        if !json["cells"].is_array() {
+            return Err(ClipboardError::ContentExtractionError(
+                "Invalid notebook: no cells".to_string(),
+            ));
+        }
+        Ok(Notebook { json })
+    }
+
+    /// Language of the code cells, from the notebook metadata.
+    pub fn language(&self) -> &str {
+        let metadata = &self.json["metadata"];
+        metadata["kernelspec"]["language"]
+            .as_str()
+            .or_else(|| metadata["language_info"]["name"].as_str())
+            .unwrap_or("python")
+    }
+
+    fn raw_cells(&self) -> &[Value] {
+        self.json["cells"].as_array().map_or(&[], Vec::as_slice)
+    }
+
+    pub fn cells(&self) -> Vec<Cell> {
+        self.raw_cells()
+            .iter()
+            .map(|cell| Cell {
+                kind: match cell["cell_type"].as_str() {
+                    Some("markdown") => CellKind::Markdown,
+                    Some("code") => CellKind::Code,
+                    _ => CellKind::Raw,
+                },
+                source: text(&cell["source"]),
+                outputs: cell["outputs"]
+                    .as_array()
+                    .map(|outputs| outputs.iter().map(output_text).collect())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Renders the cells in order, numbered from 1: markdown as prose, code
+    /// in fences, followed by their outputs when `options.outputs` is set.
+    ///
+    /// Fences use tildes so the notebook can sit inside a backtick fence.
+    pub fn render(&self, options: &NotebookOptions) -> String {
+        let mut rendered = Vec::new();
+        for (i, cell) in self.cells().iter().enumerate() {
+            let source = cell.source.trim_end_matches('\n');
+            let mut section = format!("## Cell {} ({})\n", i + 1, cell.kind.as_str());
+            match cell.kind {
+                CellKind::Markdown => {
+                    for line in source.lines() {
+                        // Backtick fences in the prose would close the enclosing fence
+                        match line.strip_prefix("```") {
+                            Some(rest) => section.push_str(&format!("~~~{}\n", rest)),
+                            None => section.push_str(&format!("{}\n", line)),
+                        }
+                    }
+                }
+                CellKind::Code => {
+                    section.push_str(&format!("~~~{}\n{}\n~~~\n", self.language(), source));
+                }
+                CellKind::Raw => section.push_str(&format!("~~~\n{}\n~~~\n", source)),
+            }
+            let outputs: Vec<&String> = cell
+                .outputs
+                .iter()
+                .filter(|output| !output.trim().is_empty())
+                .collect();
+            if options.outputs && !outputs.is_empty() {
+                section.push_str("Output:\n~~~\n");
+                for output in outputs {
+                    section.push_str(&truncate_lines(output, options.max_output_lines));
+                    section.push('\n');
+                }
+                section.push_str("~~~\n");
+            }
+            rendered.push(section);
+        }
+        rendered.join("\n")
+    }
+
+    /// Replaces the source of the 1-based cell `number`, keeping its outputs.
+    ///
+    /// The number after the last cell appends a new code cell.
+    pub fn set_cell_source(&mut self, number: usize, source: &str) -> Result<(), ClipboardError> {
+        let count = self.raw_cells().len();
+        let source = lines(source.trim_end_matches('\n'));
+        let cells = self.json["cells"]
+            .as_array_mut()
+            .expect("Notebooks are parsed with cells");
+        match number {
+            0 => Err(ClipboardError::ContentApplicationError(
+                "Cells are numbered from 1".to_string(),
+            )),
+            n if n <= count => {
+                cells[n - 1]["source"] = source;
+                Ok(())
+            }
+            n if n == count + 1 => {
+                cells.push(json!({
+                    "cell_type": "code",
+                    "execution_count": null,
+                    "metadata": {},
+                    "outputs": [],
+                    "source": source,
+                }));
+                Ok(())
+            }
+            n => Err(ClipboardError::ContentApplicationError(format!(
+                "Cell {} does not exist; the notebook has {} cells",
+                n, count
+            ))),
+        }
+    }
+
+    /// The notebook JSON as Jupyter writes it: sorted keys, one-space indent.
+    pub fn to_json(&self) -> Result<String, ClipboardError> {
+        let mut bytes = Vec::new();
+        let mut serializer =
+            serde_json::Serializer::with_formatter(&mut bytes, PrettyFormatter::with_indent(b" "));
+        self.json
+            .serialize(&mut serializer)
+            .map_err(|e| ClipboardError::FileError(e.to_string()))?;
+        let mut json =
+            String::from_utf8(bytes).map_err(|e| ClipboardError::FileError(e.to_string()))?;
+        json.push('\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,155 @@
+use crate::errors::ClipboardError;
+use crate::sink::OutputSink;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use tracing::{debug, warn};
+
+/// GNU screen truncates DCS strings longer than this, so payloads are split.
+pub const SCREEN_CHUNK_SIZE: usize = 768;
+
+/// Size of the DCS passthrough strings large payloads are split into for tmux.
+pub const TMUX_CHUNK_SIZE: usize = 4096;
+
+/// Sequences are written to the terminal in pieces of this many bytes.
+pub const WRITE_CHUNK_SIZE: usize = 4096;
+
+/// Some terminals silently drop OSC 52 payloads larger than this many
+/// base64-encoded bytes, so larger copies log a warning.
+pub const MAX_OSC52_PAYLOAD: usize = 100_000;
+
+/// Environment variable with the largest payload, in base64-encoded bytes, to
+/// send; larger copies are refused.
+pub const MAX_PAYLOAD_ENV: &str = "SNIPPY_OSC52_MAX_BYTES";
+
+/// Terminal multiplexer the OSC 52 sequence has to pass through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    None,
+    Tmux,
+    Screen,
+}
+
+impl Multiplexer {
+    pub fn detect() -> Self {
+        if env::var_os("TMUX").is_some() {
+            Multiplexer::Tmux
+        } else if env::var_os("STY").is_some() {
+            Multiplexer::Screen
+        } else {
+            Multiplexer::None
+        }
+    }
+}
+
+/// Returns true when running over SSH without a local display server.
+pub fn is_remote_session() -> bool {
+    env::var_os("SSH_TTY").is_some()
+        && env::var_os("DISPLAY").is_none()
+        && env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+/// Encodes `content` as an OSC 52 clipboard sequence for `multiplexer`.
+pub fn osc52_sequence(content: &str, multiplexer: Multiplexer) -> String {
+    let osc = format!("\x1b]52;c;{}\x07", STANDARD.encode(content));
+    // Multiplexers pass each DCS string through as is, so the terminal sees the
+    // chunks joined back into one sequence
+    match multiplexer {
+        Multiplexer::None => osc,
+        Multiplexer::Tmux => osc
+            .as_bytes()
+            .chunks(TMUX_CHUNK_SIZE)
+            .map(|chunk| {
+                let chunk = String::from_utf8_lossy(chunk).replace('\x1b', "\x1b\x1b");
+                format!("\x1bPtmux;{}\x1b\\", chunk)
+            })
+            .collect(),
+        Multiplexer::Screen => osc
+            .as_bytes()
+            .chunks(SCREEN_CHUNK_SIZE)
+            .map(|chunk| format!("\x1bP{}\x1b\\", String::from_utf8_lossy(chunk)))
+            .collect(),
+    }
+}
+
+/// Copies to the local clipboard of a remote terminal via OSC 52.
+pub struct Osc52Sink {
+    multiplexer: Multiplexer,
+    /// Largest payload to send, in base64-encoded bytes.
+    max_payload: Option<usize>,
+}
+
+impl Osc52Sink {
+    pub fn new(multiplexer: Multiplexer) -> Self {
+        Osc52Sink {
+            multiplexer,
+            max_payload: None,
+        }
+    }
+
+    /// Refuses payloads larger than `max_payload` base64-encoded bytes.
+    pub fn with_max_payload(mut self, max_payload: Option<usize>) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+}
+
+impl Default for Osc52Sink {
+    fn default() -> Self {
+        let max_payload = env::var(MAX_PAYLOAD_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok());
+        Self::new(Multiplexer::detect()).with_max_payload(max_payload)
+    }
+}
+
+#[async_trait]
+impl OutputSink for Osc52Sink {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError> {
+        let payload = content.len().div_ceil(3) * 4;
+        match self.max_payload {
+            Some(max_payload) if payload > max_payload => {
+                return Err(ClipboardError::ClipboardWriteError(format!(
+                    "{} byte OSC 52 payload exceeds the {} byte limit set by {}; \
+                     copy less or write to a file with -o",
+                    payload, max_payload, MAX_PAYLOAD_ENV
+                )));
+            }
+            _ if payload > MAX_OSC52_PAYLOAD => warn!(
+                "{} byte OSC 52 payload may be dropped by terminals that accept less than {} bytes",
+                payload, MAX_OSC52_PAYLOAD
+            ),
+            _ => {}
+        }
+        let sequence = osc52_sequence(content, self.multiplexer);
+        debug!(
+            "Writing {} byte OSC 52 sequence ({:?})",
+            sequence.len(),
+            self.multiplexer
+        );
+
+        // Prefer the controlling terminal so the sequence survives redirected output
+        let result = match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(mut tty) => write_chunked(&mut tty, &sequence),
+            Err(_) => write_chunked(&mut std::io::stderr().lock(), &sequence),
+        };
+        result.map_err(|e| ClipboardError::ClipboardWriteError(e.to_string()))
+    }
+
+    fn describe(&self) -> String {
+        "clipboard (OSC 52)".to_string()
+    }
+}
+
+/// Writes `sequence` in pieces, flushing each, so large copies are not held
+/// back by terminal buffers.
+fn write_chunked(out: &mut impl Write, sequence: &str) -> std::io::Result<()> {
+    for chunk in sequence.as_bytes().chunks(WRITE_CHUNK_SIZE) {
+        out.write_all(chunk)?;
+        out.flush()?;
+    }
+    Ok(())
+}
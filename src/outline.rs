@@ -0,0 +1,89 @@
+use crate::symbols::{body_start, declaration, item_end, python_header_end, ItemKind, Language};
+
+/// Reduces `content` to its imports, type definitions and function signatures.
+///
+/// Function bodies are replaced by a comment naming the elided lines, so the
+/// outline still points at the original source.
+pub fn outline(content: &str, language: Language) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut kept: Vec<String> = Vec::new();
+    // End lines of the containers (classes, impl blocks, ...) enclosing the current line
+    let mut containers: Vec<usize> = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        containers.retain(|&end| end >= i);
+        let Some(decl) = declaration(lines[i], language, !containers.is_empty()) else {
+            kept.push(lines[i].to_string());
+            i += 1;
+            continue;
+        };
+
+        let end = item_end(&lines, i, language);
+        match decl.kind {
+            ItemKind::Container => {
+                containers.push(end);
+                kept.push(lines[i].to_string());
+                i += 1;
+            }
+            ItemKind::Function => {
+                let header_end = match language {
+                    Language::Python => python_header_end(&lines, i),
+                    _ => body_start(&lines, i, language).unwrap_or(end),
+                }
+                .min(end);
+                kept.extend(lines[i..=header_end].iter().map(|l| l.to_string()));
+
+                // Keep the closing brace of brace-delimited bodies
+                let body_end = if language == Language::Python {
+                    end
+                } else {
+                    end.saturating_sub(1)
+                };
+                if body_end > header_end {
+                    let indent = body_indent(&lines, header_end, end);
+                    kept.push(elision_marker(
+                        language,
+                        &indent,
+                        header_end + 2,
+                        body_end + 1,
+                    ));
+                }
+                if language != Language::Python && end > header_end {
+                    kept.push(lines[end].to_string());
+                }
+                i = end + 1;
+            }
+            ItemKind::Other => {
+                kept.push(lines[i].to_string());
+                i += 1;
+            }
+        }
+    }
+
+    kept.join("\n")
+}
+
+/// Indentation of the first non-blank body line after `header`, or one level
+/// deeper than the header when the body is empty.
+fn body_indent(lines: &[&str], header: usize, end: usize) -> String {
+    let indent = |line: &str| line[..line.len() - line.trim_start().len()].to_string();
+    lines[header + 1..=end.max(header)]
+        .iter()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| indent(l))
+        .unwrap_or_else(|| format!("{}    ", indent(lines[header])))
+}
+
+/// Placeholder for the 1-based lines `first..=last` of an elided body.
+fn elision_marker(language: Language, indent: &str, first: usize, last: usize) -> String {
+    let lines = if first == last {
+        format!("line {}", first)
+    } else {
+        format!("lines {}-{}", first, last)
+    };
+    match language {
+        Language::Python => format!("{}...  # {} elided", indent, lines),
+        _ => format!("{}// ... {} elided", indent, lines),
+    }
+}
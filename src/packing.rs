@@ -0,0 +1,149 @@
+use std::cmp::Reverse;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, trace};
+
+/// A file competing for space in a token budget.
+#[derive(Debug, Clone)]
+pub struct PackCandidate {
+    pub path: PathBuf,
+    pub tokens: usize,
+    /// Size of the file's outline, when one can be made.
+    pub outline_tokens: Option<usize>,
+    /// Whether the file was named explicitly rather than found by a walk.
+    pub explicit: bool,
+    /// Position in a relevance ranking such as a `--query`, best first.
+    pub rank: Option<usize>,
+}
+
+/// What the packer decided to do with a candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackDecision {
+    Included,
+    /// Included as an outline because the full file did not fit.
+    Outlined,
+    Dropped,
+}
+
+/// Number of directory hops between the directories containing `a` and `b`.
+pub fn path_distance(a: &Path, b: &Path) -> usize {
+    let dirs = |p: &Path| -> Vec<String> {
+        p.parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let a_dirs = dirs(a);
+    let b_dirs = dirs(b);
+    let common = a_dirs
+        .iter()
+        .zip(b_dirs.iter())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (a_dirs.len() - common) + (b_dirs.len() - common)
+}
+
+/// Decides which candidates fit into `budget` tokens.
+///
+/// Explicitly named files are taken first, in the order given, then ranked
+/// files best first. The remaining files follow ordered by their distance
+/// from the nearest explicit file (or from the root when nothing was named),
+/// and whatever is left over is filled with the smallest files that still
+/// fit. Files that did not fit fall back to their outline in the same order,
+/// explicit and ranked files taking theirs first.
+pub fn pack(candidates: &[PackCandidate], budget: usize) -> Vec<PackDecision> {
+    let mut decisions = vec![PackDecision::Dropped; candidates.len()];
+    let mut used = 0;
+    let mut leftover = Vec::new();
+    let mut outline_order = Vec::new();
+
+    let anchors: Vec<&Path> = candidates
+        .iter()
+        .filter(|c| c.explicit)
+        .map(|c| c.path.as_path())
+        .collect();
+
+    let mut ranked: Vec<usize> = (0..candidates.len())
+        .filter(|&i| !candidates[i].explicit && candidates[i].rank.is_some())
+        .collect();
+    ranked.sort_by_key(|&i| candidates[i].rank);
+    let prioritized = (0..candidates.len())
+        .filter(|&i| candidates[i].explicit)
+        .chain(ranked);
+    for i in prioritized {
+        let candidate = &candidates[i];
+        if used + candidate.tokens <= budget {
+            used += candidate.tokens;
+            decisions[i] = PackDecision::Included;
+        } else if let Some(outline_tokens) = candidate
+            .outline_tokens
+            .filter(|&tokens| used + tokens <= budget)
+        {
+            trace!("{:?} only fits as an outline", candidate.path);
+            used += outline_tokens;
+            decisions[i] = PackDecision::Outlined;
+        } else {
+            leftover.push(i);
+        }
+    }
+
+    let mut by_distance: Vec<(usize, usize)> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !c.explicit && c.rank.is_none())
+        .map(|(i, c)| {
+            let distance = if anchors.is_empty() {
+                path_distance(&c.path, Path::new(""))
+            } else {
+                anchors
+                    .iter()
+                    .map(|anchor| path_distance(&c.path, anchor))
+                    .min()
+                    .unwrap_or(0)
+            };
+            (distance, i)
+        })
+        .collect();
+    by_distance.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| candidates[a.1].path.cmp(&candidates[b.1].path)));
+
+    outline_order.extend(by_distance.iter().map(|&(_, i)| i));
+    let mut iter = by_distance.into_iter();
+    for (distance, i) in iter.by_ref() {
+        let candidate = &candidates[i];
+        if used + candidate.tokens > budget {
+            trace!("{:?} (distance {}) does not fit", candidate.path, distance);
+            leftover.push(i);
+            break;
+        }
+        used += candidate.tokens;
+        decisions[i] = PackDecision::Included;
+    }
+    leftover.extend(iter.map(|(_, i)| i));
+
+    leftover.sort_by_key(|&i| (candidates[i].tokens, Reverse(candidates[i].explicit)));
+    for i in leftover {
+        if used + candidates[i].tokens <= budget {
+            used += candidates[i].tokens;
+            decisions[i] = PackDecision::Included;
+        }
+    }
+
+    for i in outline_order {
+        if decisions[i] != PackDecision::Dropped {
+            continue;
+        }
+        if let Some(outline_tokens) = candidates[i].outline_tokens {
+            if used + outline_tokens <= budget {
+                used += outline_tokens;
+                decisions[i] = PackDecision::Outlined;
+            }
+        }
+    }
+
+    debug!("Packed {} of {} budget tokens", used, budget);
+    decisions
+}
@@ -0,0 +1,405 @@
+//! Interactive file picker for `snippy copy -i`.
+//!
+//! The workspace is shown as a tree built from a `Trie` of the candidate
+//! files and their token counts. Typing filters the files with a fuzzy
+//! search; space toggles the file or directory under the cursor and enter
+//! copies the selection.
+
+use crate::errors::ClipboardError;
+use crate::trie::{Trie, TrieNode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How much of a row is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Unchecked,
+    Partial,
+    Checked,
+}
+
+/// A line of the picker: a directory or a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub path: PathBuf,
+    /// Name shown for the row; the full path in search results.
+    pub name: String,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+    pub tokens: usize,
+    pub check: Check,
+}
+
+/// What the picker should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerAction {
+    Continue,
+    Confirm,
+    Cancel,
+}
+
+/// The files, selection, search query and cursor of the picker.
+pub struct PickerState {
+    trie: Trie,
+    /// Token count of every file, by path.
+    tokens: HashMap<PathBuf, usize>,
+    selected: HashSet<PathBuf>,
+    expanded: HashSet<PathBuf>,
+    query: String,
+    cursor: usize,
+    /// Token budget shown next to the running total.
+    pub budget: Option<usize>,
+}
+
+impl PickerState {
+    pub fn new(files: Vec<(PathBuf, usize)>) -> Result<Self, ClipboardError> {
+        let mut trie = Trie::new();
+        for (path, tokens) in &files {
+            trie.insert(path, *tokens)?;
+        }
+        Ok(PickerState {
+            trie,
+            tokens: files.into_iter().collect(),
+            selected: HashSet::new(),
+            expanded: HashSet::new(),
+            query: String::new(),
+            cursor: 0,
+            budget: None,
+        })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Selected files, sorted.
+    pub fn selected_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.selected.iter().cloned().collect();
+        files.sort();
+        files
+    }
+
+    pub fn selected_tokens(&self) -> usize {
+        self.selected.iter().map(|path| self.tokens[path]).sum()
+    }
+
+    /// Files at or below `path`.
+    fn files_under(&self, path: &Path) -> Vec<PathBuf> {
+        fn collect(node: &TrieNode, path: &Path, files: &mut Vec<PathBuf>) {
+            if node.token_count.is_some() {
+                files.push(path.to_path_buf());
+            }
+            for (name, child) in &node.children {
+                collect(child, &path.join(name), files);
+            }
+        }
+        let mut files = Vec::new();
+        if let Some(node) = self.trie.get(path) {
+            collect(node, path, &mut files);
+        }
+        files
+    }
+
+    /// Counts the selected and total files of the subtree at `path`.
+    fn count_selected(&self, node: &TrieNode, path: &Path) -> (usize, usize) {
+        let own = node.token_count.is_some();
+        let mut counts = (
+            usize::from(own && self.selected.contains(path)),
+            usize::from(own),
+        );
+        for (name, child) in &node.children {
+            let (selected, total) = self.count_selected(child, &path.join(name));
+            counts = (counts.0 + selected, counts.1 + total);
+        }
+        counts
+    }
+
+    fn check(&self, path: &Path) -> Check {
+        let Some(node) = self.trie.get(path) else {
+            return Check::Unchecked;
+        };
+        match self.count_selected(node, path) {
+            (0, _) => Check::Unchecked,
+            (selected, total) if selected == total => Check::Checked,
+            _ => Check::Partial,
+        }
+    }
+
+    /// The visible rows: the expanded tree, or the files matching the query
+    /// ranked by score.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        if self.query.is_empty() {
+            self.tree_rows(self.trie.get_root(), Path::new(""), 0, &mut rows);
+            return rows;
+        }
+        let mut matches: Vec<(i64, &PathBuf)> = self
+            .tokens
+            .keys()
+            .filter_map(|path| {
+                fuzzy_score(&self.query, &path.to_string_lossy()).map(|score| (score, path))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        for (_, path) in matches {
+            rows.push(Row {
+                path: path.clone(),
+                name: path.to_string_lossy().into_owned(),
+                depth: 0,
+                is_dir: false,
+                expanded: false,
+                tokens: self.tokens[path],
+                check: self.check(path),
+            });
+        }
+        rows
+    }
+
+    fn tree_rows(&self, node: &TrieNode, path: &Path, depth: usize, rows: &mut Vec<Row>) {
+        let mut children: Vec<_> = node.children.iter().collect();
+        children.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, child) in children {
+            let child_path = path.join(name);
+            let is_dir = child.token_count.is_none();
+            let expanded = is_dir && self.expanded.contains(&child_path);
+            rows.push(Row {
+                path: child_path.clone(),
+                name: name.clone(),
+                depth,
+                is_dir,
+                expanded,
+                tokens: child.calculate_total_tokens(),
+                check: self.check(&child_path),
+            });
+            if expanded {
+                self.tree_rows(child, &child_path, depth + 1, rows);
+            }
+        }
+    }
+
+    /// Selects everything under the cursor, or deselects it if it was all selected.
+    pub fn toggle(&mut self) {
+        let Some(row) = self.rows().into_iter().nth(self.cursor) else {
+            return;
+        };
+        let files = self.files_under(&row.path);
+        if row.check == Check::Checked {
+            for file in files {
+                self.selected.remove(&file);
+            }
+        } else {
+            self.selected.extend(files);
+        }
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        let Some(row) = self.rows().into_iter().nth(self.cursor) else {
+            return;
+        };
+        if row.is_dir && expanded {
+            self.expanded.insert(row.path);
+        } else if row.is_dir && row.expanded {
+            self.expanded.remove(&row.path);
+        } else if let Some(parent) = row.path.parent().filter(|_| !expanded && row.depth > 0) {
+            // Collapsing a file or collapsed directory moves to its parent
+            let parent = parent.to_path_buf();
+            self.expanded.remove(&parent);
+            if let Some(index) = self.rows().iter().position(|row| row.path == parent) {
+                self.cursor = index;
+            }
+        }
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        let rows = self.rows().len();
+        if rows == 0 {
+            self.cursor = 0;
+            return;
+        }
+        self.cursor = self.cursor.saturating_add_signed(offset).min(rows - 1);
+    }
+
+    /// Applies a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerAction {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return PickerAction::Cancel;
+        }
+        match key.code {
+            KeyCode::Enter => {
+                if self.selected.is_empty() {
+                    self.toggle();
+                }
+                return if self.selected.is_empty() {
+                    PickerAction::Continue
+                } else {
+                    PickerAction::Confirm
+                };
+            }
+            KeyCode::Esc if self.query.is_empty() => return PickerAction::Cancel,
+            KeyCode::Esc => {
+                self.query.clear();
+                self.cursor = 0;
+            }
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-10),
+            KeyCode::PageDown => self.move_cursor(10),
+            KeyCode::Right => self.set_expanded(true),
+            KeyCode::Left => self.set_expanded(false),
+            KeyCode::Char(' ') | KeyCode::Tab => self.toggle(),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.cursor = 0;
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.cursor = 0;
+            }
+            _ => {}
+        }
+        PickerAction::Continue
+    }
+}
+
+/// Scores `candidate` against `query` when the characters of `query` appear
+/// in it in order, ignoring case.
+///
+/// Consecutive matches and matches at the start of a path segment or word
+/// score higher; gaps cost a little.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let index = (position..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().eq(std::iter::once(q)))?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        let boundary = index == 0 || matches!(candidate[index - 1], '/' | '\\' | '_' | '-' | '.');
+        let camel =
+            index > 0 && candidate[index].is_uppercase() && candidate[index - 1].is_lowercase();
+        if boundary || camel {
+            score += 3;
+        }
+        score -= (index - position).min(3) as i64;
+        previous = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
+
+/// Shows the picker on the terminal and returns the chosen files, or `None`
+/// when it was cancelled.
+pub fn pick_files(mut state: PickerState) -> Result<Option<Vec<PathBuf>>, ClipboardError> {
+    let mut out = std::io::stderr();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run(&mut state, &mut out);
+    let restored = execute!(out, cursor::Show, terminal::LeaveAlternateScreen)
+        .and_then(|_| terminal::disable_raw_mode());
+    let action = result?;
+    restored?;
+    Ok((action == PickerAction::Confirm).then(|| state.selected_files()))
+}
+
+fn run(state: &mut PickerState, out: &mut impl Write) -> Result<PickerAction, ClipboardError> {
+    loop {
+        draw(state, out)?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match state.handle_key(key) {
+                PickerAction::Continue => {}
+                action => return Ok(action),
+            }
+        }
+    }
+}
+
+fn draw(state: &PickerState, out: &mut impl Write) -> Result<(), ClipboardError> {
+    let (width, height) = match terminal::size()? {
+        // Some pseudo-terminals report no size at all
+        (0, _) | (_, 0) => (80, 24),
+        (width, height) => (width as usize, height as usize),
+    };
+    let rows = state.rows();
+    let visible = height.saturating_sub(4).max(1);
+    let first = state.cursor().saturating_sub(visible - 1);
+
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+    let help = "Type to search, ↑↓ move, ←→ collapse/expand, space select, enter copy, esc quit";
+    queue!(out, Print(truncate(help, width)), cursor::MoveToNextLine(1))?;
+    queue!(
+        out,
+        Print(format!("> {}", state.query())),
+        cursor::MoveToNextLine(1)
+    )?;
+
+    for (i, row) in rows.iter().enumerate().skip(first).take(visible) {
+        let check = match row.check {
+            Check::Checked => "[x]",
+            Check::Partial => "[-]",
+            Check::Unchecked => "[ ]",
+        };
+        let arrow = match (row.is_dir, row.expanded) {
+            (true, true) => "▾ ",
+            (true, false) => "▸ ",
+            (false, _) => "  ",
+        };
+        let tokens = format!("{} tokens", row.tokens);
+        let label = format!("{}{} {}{}", "  ".repeat(row.depth), check, arrow, row.name);
+        let label = truncate(&label, width.saturating_sub(tokens.len() + 1));
+        let padding = width.saturating_sub(label.chars().count() + tokens.len());
+        if i == state.cursor() {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(
+            out,
+            Print(format!("{}{}{}", label, " ".repeat(padding), tokens)),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+
+    let selected = state.selected_tokens();
+    let budget = match state.budget {
+        Some(budget) if selected > budget => {
+            format!(" / {} budget, over by {}", budget, selected - budget)
+        }
+        Some(budget) => format!(" / {} budget", budget),
+        None => String::new(),
+    };
+    let footer = format!(
+        "{} files selected, {} tokens{}",
+        state.selected_files().len(),
+        selected,
+        budget
+    );
+    queue!(
+        out,
+        cursor::MoveTo(0, height.saturating_sub(1) as u16),
+        SetAttribute(Attribute::Bold),
+        Print(truncate(&footer, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,127 @@
+use crate::errors::ClipboardError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Prompts built into the binary, by name.
+pub const BUILTIN_PROMPTS: &[(&str, &str)] = &[(
+    "general-coding",
+    include_str!("../prompts/general-coding.md"),
+)];
+
+/// Placeholder replaced by the copied files.
+pub const CONTEXT_VARIABLE: &str = "CONTEXT";
+
+/// Default directory of user prompts, under the user config directory.
+pub fn default_prompts_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("snippy").join("prompts"))
+}
+
+/// Loads the prompt called `name`.
+///
+/// `name` may be a path to a prompt file. Otherwise `<name>.md` in
+/// `prompts_dir` is tried before the built-in prompts.
+pub fn load_prompt(name: &str, prompts_dir: Option<&Path>) -> Result<String, ClipboardError> {
+    let mut candidates = vec![PathBuf::from(name)];
+    if let Some(dir) = prompts_dir {
+        candidates.push(dir.join(name));
+        candidates.push(dir.join(format!("{}.md", name)));
+    }
+    for path in candidates {
+        if path.is_file() {
+            debug!("Loading prompt from {:?}", path);
+            return std::fs::read_to_string(&path).map_err(|e| {
+                ClipboardError::ConfigError(format!("Failed to read prompt {:?}: {}", path, e))
+            });
+        }
+    }
+
+    if let Some((_, prompt)) = BUILTIN_PROMPTS.iter().find(|(builtin, _)| *builtin == name) {
+        return Ok(prompt.to_string());
+    }
+    Err(ClipboardError::ConfigError(format!(
+        "Prompt '{}' not found; available prompts: {}",
+        name,
+        available_prompts(prompts_dir).join(", ")
+    )))
+}
+
+/// Names of the user prompts in `prompts_dir` and the built-in prompts.
+pub fn available_prompts(prompts_dir: Option<&Path>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_PROMPTS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    if let Some(entries) = prompts_dir.and_then(|dir| std::fs::read_dir(dir).ok()) {
+        names.extend(entries.flatten().filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "md" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        }));
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Parses a `key=value` prompt variable.
+pub fn parse_prompt_var(arg: &str) -> Result<(String, String), ClipboardError> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_uppercase(), value.to_string()))
+        }
+        _ => Err(ClipboardError::ConfigError(format!(
+            "Invalid prompt variable '{}', expected key=value",
+            arg
+        ))),
+    }
+}
+
+/// Fills the `{$NAME}` placeholders of `prompt`.
+///
+/// The copied `context` goes into `{$CONTEXT}`, or before the prompt when it
+/// has no such slot. Variable names are case-insensitive. Placeholders
+/// without a value are left as they are.
+pub fn render_prompt(prompt: &str, context: &str, vars: &[(String, String)]) -> String {
+    let mut values: HashMap<String, &str> = vars
+        .iter()
+        .map(|(key, value)| (key.to_uppercase(), value.as_str()))
+        .collect();
+    values.insert(CONTEXT_VARIABLE.to_string(), context);
+
+    let mut rendered = String::with_capacity(prompt.len() + context.len());
+    let mut has_context = false;
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{$") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let name = after.find('}').map(|end| &after[..end]).filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let Some(name) = name else {
+            rendered.push_str("{$");
+            rest = after;
+            continue;
+        };
+        match values.get(&name.to_uppercase()) {
+            Some(value) => {
+                has_context |= name.eq_ignore_ascii_case(CONTEXT_VARIABLE);
+                rendered.push_str(value);
+            }
+            None => {
+                warn!("Prompt variable {} has no value", name);
+                rendered.push_str(&rest[start..start + 3 + name.len()]);
+            }
+        }
+        rest = &after[name.len() + 1..];
+    }
+    rendered.push_str(rest);
+
+    if has_context {
+        rendered
+    } else {
+        format!("{}\n{}", context, rendered)
+    }
+}
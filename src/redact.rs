@@ -0,0 +1,217 @@
+use crate::errors::ClipboardError;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tracing::debug;
+
+/// Minimum Shannon entropy (bits per character) of a quoted string to be redacted.
+const HIGH_ENTROPY_THRESHOLD: f64 = 4.2;
+
+/// Built-in rules as `(name, pattern)`. A `secret` group limits what is replaced.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "private_key",
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+    ),
+    ("aws_key", r"\b(?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16}\b"),
+    (
+        "aws_secret",
+        r#"(?i)aws_?secret_?(?:access_?)?key["']?\s*[:=]\s*["']?(?P<secret>[A-Za-z0-9/+=]{40})\b"#,
+    ),
+    ("gcp_key", r"\bAIza[0-9A-Za-z_\-]{35}\b"),
+    (
+        "github_token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,255}|github_pat_[A-Za-z0-9_]{22,255})\b",
+    ),
+    (
+        "openai_key",
+        r"\bsk-(?:proj-|svcacct-|admin-)?[A-Za-z0-9_\-]{20,}\b",
+    ),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_\-]{10,}\.eyJ[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}",
+    ),
+];
+
+static ENV_VALUE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?m)^[+\- ]?[ \t]*(?:export[ \t]+)?[A-Za-z_][A-Za-z0-9_.]*[ \t]*=[ \t]*(?P<secret>[^\s#'"][^\r\n#]*?|"[^"\r\n]+"|'[^'\r\n]+')[ \t]*(?:#.*)?$"#,
+    )
+    .expect("Invalid regex")
+});
+static QUOTED_STRING_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"["'`](?P<secret>[A-Za-z0-9+/=_\-]{20,})["'`]"#).expect("Invalid regex")
+});
+
+/// Options for the redaction pass.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionOptions {
+    /// Extra rules written as `name=regex`.
+    pub rules: Vec<String>,
+    /// Matches of these patterns are left alone.
+    pub allowlist: Vec<String>,
+}
+
+struct Rule {
+    name: String,
+    regex: Regex,
+    /// Only redact matches at least this random.
+    min_entropy: Option<f64>,
+    /// Only applies to `.env`-style files.
+    env_files_only: bool,
+}
+
+impl Rule {
+    fn new(name: &str, pattern: &str) -> Result<Self, ClipboardError> {
+        Ok(Rule {
+            name: name.to_string(),
+            regex: Regex::new(pattern)?,
+            min_entropy: None,
+            env_files_only: false,
+        })
+    }
+}
+
+/// Replaces secrets with stable placeholders such as `<REDACTED:aws_key_1>`.
+///
+/// The same secret gets the same placeholder everywhere in a copy.
+pub struct Redactor {
+    rules: Vec<Rule>,
+    allowlist: Vec<Regex>,
+    /// Placeholder for each `(rule, secret)` seen so far.
+    placeholders: HashMap<(String, String), String>,
+}
+
+impl Redactor {
+    pub fn new(options: &RedactionOptions) -> Result<Self, ClipboardError> {
+        let mut rules = Vec::new();
+        // User rules come first so they can claim matches before the generic rules
+        for rule in &options.rules {
+            let (name, pattern) = rule.split_once('=').ok_or_else(|| {
+                ClipboardError::ConfigError(format!(
+                    "Invalid redaction rule '{}', expected name=regex",
+                    rule
+                ))
+            })?;
+            rules.push(Rule::new(name.trim(), pattern)?);
+        }
+        for (name, pattern) in BUILTIN_RULES {
+            rules.push(Rule::new(name, pattern)?);
+        }
+        rules.push(Rule {
+            name: "env_value".to_string(),
+            regex: ENV_VALUE_REGEX.clone(),
+            min_entropy: None,
+            env_files_only: true,
+        });
+        rules.push(Rule {
+            name: "high_entropy".to_string(),
+            regex: QUOTED_STRING_REGEX.clone(),
+            min_entropy: Some(HIGH_ENTROPY_THRESHOLD),
+            env_files_only: false,
+        });
+
+        let allowlist = options
+            .allowlist
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Redactor {
+            rules,
+            allowlist,
+            placeholders: HashMap::new(),
+        })
+    }
+
+    /// Redacts `content` of the file at `path`.
+    ///
+    /// Returns the redacted text and the number of matches of each rule.
+    pub fn redact(&mut self, path: &str, content: &str) -> (String, BTreeMap<String, usize>) {
+        let env_file = is_env_file(path);
+        let mut redacted = content.to_string();
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+        for rule in &self.rules {
+            if rule.env_files_only && !env_file {
+                continue;
+            }
+            let replaced = rule.regex.replace_all(&redacted, |caps: &Captures| {
+                let whole = caps.get(0).expect("match");
+                let secret = caps.name("secret").unwrap_or(whole);
+                let value = secret.as_str();
+                let allowed = self.allowlist.iter().any(|allow| allow.is_match(value))
+                    || rule
+                        .min_entropy
+                        .is_some_and(|min| !looks_random(value, min));
+                if allowed {
+                    return whole.as_str().to_string();
+                }
+
+                *counts.entry(rule.name.clone()).or_insert(0) += 1;
+                let next = self
+                    .placeholders
+                    .keys()
+                    .filter(|(name, _)| *name == rule.name)
+                    .count()
+                    + 1;
+                let placeholder = self
+                    .placeholders
+                    .entry((rule.name.clone(), value.to_string()))
+                    .or_insert_with(|| format!("<REDACTED:{}_{}>", rule.name, next))
+                    .clone();
+                let start = secret.start() - whole.start();
+                let end = secret.end() - whole.start();
+                // Multi-line secrets keep their line breaks so line numbers stay put
+                let line_breaks = "\n".repeat(value.matches('\n').count());
+                format!(
+                    "{}{}{}{}",
+                    &whole.as_str()[..start],
+                    placeholder,
+                    line_breaks,
+                    &whole.as_str()[end..]
+                )
+            });
+            redacted = replaced.into_owned();
+        }
+
+        if !counts.is_empty() {
+            debug!("Redacted {:?} in {}", counts, path);
+        }
+        (redacted, counts)
+    }
+}
+
+/// Returns true for `.env`, `.env.local`, `prod.env` and similar files.
+pub fn is_env_file(path: &str) -> bool {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    name == ".env" || name.starts_with(".env.") || name.ends_with(".env")
+}
+
+/// Shannon entropy of `value` in bits per character.
+pub fn shannon_entropy(value: &str) -> f64 {
+    let mut frequencies: HashMap<char, usize> = HashMap::new();
+    for c in value.chars() {
+        *frequencies.entry(c).or_insert(0) += 1;
+    }
+    let length = value.chars().count() as f64;
+    frequencies
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Random-looking strings mix digits with upper and lower case letters.
+fn looks_random(value: &str, min_entropy: f64) -> bool {
+    value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_uppercase())
+        && value.chars().any(|c| c.is_ascii_lowercase())
+        && shannon_entropy(value) >= min_entropy
+}
@@ -0,0 +1,272 @@
+use crate::errors::ClipboardError;
+use crate::git::run_git;
+use crate::selector::parse_selector;
+use crate::utils::glob_base;
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How long to wait for another run to finish with a mirror; older locks are stale.
+const MIRROR_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A git repository to copy from, written as `url#ref:subdir`.
+///
+/// Both parts after `#` are optional: `url#v1.2`, `url#main:src` and `url#:docs`
+/// are all valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    pub url: String,
+    /// Branch, tag or commit to check out; the default branch when unset.
+    pub reference: Option<String>,
+    /// Directory of the repository that file patterns are relative to.
+    pub subdir: Option<String>,
+}
+
+impl GitSource {
+    /// Parses `arg` if it names a git repository.
+    pub fn parse(arg: &str) -> Option<GitSource> {
+        let (url, spec) = match arg.rsplit_once('#') {
+            Some((url, spec)) => (url, Some(spec)),
+            None => (arg, None),
+        };
+        if !is_git_url(url) {
+            return None;
+        }
+
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        let (reference, subdir) = match spec {
+            Some(spec) => match spec.split_once(':') {
+                Some((reference, subdir)) => {
+                    (non_empty(reference), non_empty(subdir.trim_matches('/')))
+                }
+                None => (non_empty(spec), None),
+            },
+            None => (None, None),
+        };
+        Some(GitSource {
+            url: url.to_string(),
+            reference,
+            subdir,
+        })
+    }
+
+    /// Path of `pattern` relative to the repository root.
+    pub fn repo_path(&self, pattern: &str) -> String {
+        match &self.subdir {
+            Some(subdir) if pattern.is_empty() => subdir.clone(),
+            Some(subdir) => format!("{}/{}", subdir, pattern),
+            None => pattern.to_string(),
+        }
+    }
+}
+
+/// Returns true for remote git URLs, `file://` URLs and local bare repositories.
+pub fn is_git_url(url: &str) -> bool {
+    url.starts_with("git@")
+        || url.starts_with("https://")
+        || url.starts_with("git://")
+        || url.starts_with("ssh://")
+        || url.starts_with("file://")
+        || is_bare_repo(Path::new(url))
+}
+
+fn is_bare_repo(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+/// Default location of the mirror cache, under the user cache directory.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("snippy"))
+}
+
+/// Directory of the cached mirror for `url` inside `cache_dir`.
+pub fn mirror_dir(cache_dir: &Path, url: &str) -> PathBuf {
+    let name = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("repo");
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    cache_dir
+        .join("repos")
+        .join(format!("{}-{}.git", name, &hash[..16]))
+}
+
+/// Exclusive lock on a cached mirror, held while it is updated and checked out from.
+struct MirrorLock {
+    path: PathBuf,
+}
+
+impl MirrorLock {
+    /// Waits until no other run holds the lock of `mirror`, then takes it.
+    fn acquire(mirror: &Path) -> Result<MirrorLock, ClipboardError> {
+        let mut path = mirror.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(MirrorLock { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > MIRROR_LOCK_TIMEOUT);
+                    if stale {
+                        warn!("Removing stale lock {:?}", path);
+                        let _ = fs::remove_file(&path);
+                    } else if started.elapsed() > MIRROR_LOCK_TIMEOUT {
+                        return Err(ClipboardError::CloneError(format!(
+                            "Timed out waiting for {:?}",
+                            path
+                        )));
+                    } else {
+                        debug!("Waiting for {:?}", path);
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for MirrorLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Failed to remove lock {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// Clones `url` into the mirror cache, or fetches updates if it is already there.
+fn update_mirror(url: &str, mirror: &Path) -> Result<(), ClipboardError> {
+    if mirror.join("HEAD").is_file() {
+        info!("Updating cached mirror of {}", url);
+        run_git(mirror, &["fetch", "-q", "--prune", "origin"])
+            .map_err(|e| ClipboardError::CloneError(e.to_string()))?;
+        return Ok(());
+    }
+
+    info!("Cloning {} into the cache", url);
+    let parent = mirror.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    // Blobs are fetched on demand, so only the checked out paths are downloaded
+    let mirror_arg = mirror.to_string_lossy();
+    run_git(
+        parent,
+        &[
+            "clone",
+            "-q",
+            "--mirror",
+            "--filter=blob:none",
+            "--",
+            url,
+            &mirror_arg,
+        ],
+    )
+    .map_err(|e| ClipboardError::CloneError(e.to_string()))?;
+    Ok(())
+}
+
+/// Resolves `reference` to a commit, fetching it if no ref points at it.
+fn resolve_commit(mirror: &Path, reference: &str) -> Result<String, ClipboardError> {
+    let spec = format!("{}^{{commit}}", reference);
+    let output = match run_git(mirror, &["rev-parse", "--verify", "-q", &spec]) {
+        Ok(output) => output,
+        Err(_) => {
+            debug!("{} is not in the mirror, fetching it", reference);
+            run_git(
+                mirror,
+                &["fetch", "-q", "--end-of-options", "origin", reference],
+            )
+            .map_err(|e| ClipboardError::CloneError(e.to_string()))?;
+            run_git(
+                mirror,
+                &["rev-parse", "--verify", "-q", "FETCH_HEAD^{commit}"],
+            )?
+        }
+    };
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// Checks out `source` into `target` from the mirror cache in `cache_dir`.
+///
+/// When `patterns` (relative to the repository root) are given, only their
+/// literal base paths are checked out.
+pub fn checkout(
+    source: &GitSource,
+    patterns: &[String],
+    cache_dir: &Path,
+    target: &Path,
+) -> Result<(), ClipboardError> {
+    let reference = source.reference.as_deref().unwrap_or("HEAD");
+    // Git would parse such a reference as an option
+    if reference.starts_with('-') {
+        return Err(ClipboardError::ConfigError(format!(
+            "Invalid git reference {}",
+            reference
+        )));
+    }
+
+    let mirror = mirror_dir(cache_dir, &source.url);
+    // Concurrent runs share the mirror and its worktree list
+    let _lock = MirrorLock::acquire(&mirror)?;
+    update_mirror(&source.url, &mirror)?;
+
+    let commit = resolve_commit(&mirror, reference)?;
+    info!(
+        "Checking out {} ({})",
+        reference,
+        &commit[..commit.len().min(12)]
+    );
+
+    // Worktrees of earlier runs were removed along with their temporary directory
+    if let Err(e) = run_git(&mirror, &["worktree", "prune"]) {
+        warn!("Failed to prune worktrees of {:?}: {}", mirror, e);
+    }
+
+    let sparse_paths: Vec<String> = patterns
+        .iter()
+        .map(|p| glob_base(&parse_selector(p).pattern))
+        .map(|base| base.to_string_lossy().trim_matches('/').to_string())
+        .collect();
+    let target_arg = target.to_string_lossy();
+    if sparse_paths.is_empty() || sparse_paths.iter().any(|p| p.is_empty() || p == ".") {
+        run_git(
+            &mirror,
+            &["worktree", "add", "-q", "--detach", &target_arg, &commit],
+        )?;
+        return Ok(());
+    }
+
+    debug!("Sparse checkout of {:?}", sparse_paths);
+    run_git(
+        &mirror,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "--detach",
+            "--no-checkout",
+            &target_arg,
+            &commit,
+        ],
+    )?;
+    let mut args = vec!["sparse-checkout", "set", "--no-cone"];
+    let anchored: Vec<String> = sparse_paths.iter().map(|p| format!("/{}", p)).collect();
+    args.extend(anchored.iter().map(String::as_str));
+    run_git(target, &args)?;
+    run_git(target, &["reset", "-q", "--hard"])?;
+    Ok(())
+}
@@ -1,62 +1,548 @@
 use crate::errors::ClipboardError;
+use crate::filter::SkipReason;
 use crate::trie::{Trie, TrieNode};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, trace};
 
+/// Token count and annotations for a single file in the stats tree.
+#[derive(Debug, Clone, Default)]
+pub struct FileStats {
+    pub tokens: usize,
+    /// Left out of the copied content; not counted in the totals.
+    pub omitted: bool,
+    /// Size of the file's outline, when one was made.
+    pub outline_tokens: Option<usize>,
+    /// The outline was copied instead of the full file.
+    pub outlined: bool,
+    /// Left out because of its content, before counting tokens.
+    pub skipped: Option<SkipReason>,
+    /// Number of secrets redacted per rule.
+    pub redactions: BTreeMap<String, usize>,
+    /// Tokens removed by compaction; `tokens` is what was left.
+    pub compacted_tokens: usize,
+    pub notes: Vec<String>,
+}
+
 /// Prints the statistics of token counts for files.
 pub fn print_stats(token_counts: &HashMap<PathBuf, usize>) -> Result<(), ClipboardError> {
-    debug!("Printing statistics for token counts");
+    let file_stats = token_counts
+        .iter()
+        .map(|(path, &tokens)| {
+            (
+                path.clone(),
+                FileStats {
+                    tokens,
+                    ..FileStats::default()
+                },
+            )
+        })
+        .collect();
+    print_file_stats(&file_stats)
+}
+
+/// How the statistics are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum StatsFormat {
+    /// A tree of directories and files, logged with the other messages.
+    #[default]
+    Tree,
+    /// A JSON report on stdout.
+    Json,
+}
+
+/// Order of the entries of each directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum StatsSort {
+    #[default]
+    Name,
+    /// Heaviest first.
+    Tokens,
+}
+
+/// Options for the statistics report.
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    pub format: StatsFormat,
+    pub sort: StatsSort,
+    /// Number of heaviest files to list after the tree.
+    pub top: Option<usize>,
+    /// Directories below this depth are collapsed into a single line.
+    pub max_depth: Option<usize>,
+    /// Decorate the tree with emoji; plain ASCII otherwise.
+    pub emoji: bool,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            format: StatsFormat::Tree,
+            sort: StatsSort::Name,
+            top: None,
+            max_depth: None,
+            emoji: true,
+        }
+    }
+}
+
+/// Statistics of a copy, as written by `--stats json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsReport {
+    /// Tokens of the copied content.
+    pub total_tokens: usize,
+    pub copied_files: usize,
+    pub outlined_files: usize,
+    pub omitted_files: usize,
+    pub omitted_tokens: usize,
+    /// Number of skipped files per reason.
+    pub skipped: BTreeMap<String, usize>,
+    /// Number of redacted secrets per rule.
+    pub redactions: BTreeMap<String, usize>,
+    /// Tokens removed from the copied files by compaction.
+    #[serde(default)]
+    pub compacted_tokens: usize,
+    /// The heaviest files, when a top-N list was asked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top: Vec<FileReport>,
+    pub directories: Vec<DirectoryReport>,
+    pub files: Vec<FileReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryReport {
+    pub path: String,
+    /// Copied tokens of the files below the directory.
+    pub tokens: usize,
+    pub percent: f64,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileReport {
+    pub path: String,
+    /// Tokens of the full file.
+    pub tokens: usize,
+    /// Tokens of what was copied: the file, its outline, or nothing.
+    pub copied_tokens: usize,
+    pub percent: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline_tokens: Option<usize>,
+    pub outlined: bool,
+    pub omitted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    pub redacted: usize,
+    /// Tokens removed by compaction.
+    #[serde(default)]
+    pub compacted_tokens: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Prints the statistics tree, marking omitted files and their notes.
+pub fn print_file_stats(file_stats: &HashMap<PathBuf, FileStats>) -> Result<(), ClipboardError> {
+    report_file_stats(file_stats, &StatsOptions::default())
+}
+
+/// Writes the statistics in the format of `options`.
+pub fn report_file_stats(
+    file_stats: &HashMap<PathBuf, FileStats>,
+    options: &StatsOptions,
+) -> Result<(), ClipboardError> {
+    match options.format {
+        StatsFormat::Json => {
+            let report = stats_report(file_stats, options)?;
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(|e| ClipboardError::IoError(e.to_string()))?;
+            println!("{}", json);
+            Ok(())
+        }
+        StatsFormat::Tree => print_stats_tree(file_stats, options),
+    }
+}
+
+/// Builds the trie of `file_stats`, along with the report totals.
+fn build_trie(
+    file_stats: &HashMap<PathBuf, FileStats>,
+) -> Result<(Trie, StatsReport), ClipboardError> {
     let mut trie = Trie::new();
-    let mut total_tokens = 0;
+    let mut report = StatsReport {
+        total_tokens: 0,
+        copied_files: 0,
+        outlined_files: 0,
+        omitted_files: 0,
+        omitted_tokens: 0,
+        skipped: BTreeMap::new(),
+        redactions: BTreeMap::new(),
+        compacted_tokens: 0,
+        top: Vec::new(),
+        directories: Vec::new(),
+        files: Vec::new(),
+    };
 
-    for (path, &token_count) in token_counts {
+    for (path, stats) in file_stats {
         trace!(
             "Inserting path into trie: {:?} with {} tokens",
             path,
-            token_count
+            stats.tokens
         );
-        trie.insert(path, token_count)?;
-        total_tokens += token_count;
+        trie.insert(path, stats.tokens)?;
+        if let Some(node) = trie.get_mut(path) {
+            node.omitted = stats.omitted;
+            node.outline_tokens = stats.outline_tokens;
+            node.outlined = stats.outlined;
+            node.skipped = stats.skipped.as_ref().map(|reason| reason.to_string());
+            node.notes = stats.notes.clone();
+            node.redacted = stats.redactions.values().sum();
+            node.compacted = stats.compacted_tokens;
+            report.total_tokens += node.copied_tokens();
+        }
+        if let Some(reason) = &stats.skipped {
+            *report.skipped.entry(reason.kind().to_string()).or_insert(0) += 1;
+            continue;
+        } else if stats.omitted {
+            report.omitted_files += 1;
+            report.omitted_tokens += stats.tokens;
+            continue;
+        } else if stats.outlined {
+            report.outlined_files += 1;
+        }
+        report.copied_files += 1;
+        report.compacted_tokens += stats.compacted_tokens;
+        for (rule, count) in &stats.redactions {
+            *report.redactions.entry(rule.clone()).or_insert(0) += count;
+        }
     }
-
-    info!("Overall ({} tokens)", total_tokens);
-    print_tree(&trie.get_root(), "", true)?;
-    Ok(())
+    Ok((trie, report))
 }
 
-fn print_tree(node: &TrieNode, prefix: &str, is_last: bool) -> Result<(), ClipboardError> {
-    let connector = if is_last { "┗━━" } else { "┣━━" };
+/// Share of `tokens` in `total`, in percent rounded to two decimals.
+fn percent(tokens: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (tokens as f64 * 10_000.0 / total as f64).round() / 100.0
+    }
+}
 
+/// Entries of `node` in the order of `sort`.
+fn sorted_children(node: &TrieNode, sort: StatsSort) -> Vec<(&String, &TrieNode)> {
     let mut children: Vec<_> = node.children.iter().collect();
-    children.sort_by(|a, b| a.0.cmp(b.0));
+    match sort {
+        StatsSort::Name => children.sort_by(|a, b| a.0.cmp(b.0)),
+        StatsSort::Tokens => children.sort_by(|a, b| {
+            b.1.calculate_total_tokens()
+                .cmp(&a.1.calculate_total_tokens())
+                .then_with(|| a.0.cmp(b.0))
+        }),
+    }
+    children
+}
+
+/// The `count` files that were not skipped with the most copied tokens.
+fn heaviest_files(files: &[FileReport], count: usize) -> Vec<FileReport> {
+    let mut heaviest: Vec<FileReport> = files
+        .iter()
+        .filter(|file| file.skipped.is_none())
+        .cloned()
+        .collect();
+    heaviest.sort_by(|a, b| {
+        b.copied_tokens
+            .cmp(&a.copied_tokens)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    heaviest.truncate(count);
+    heaviest
+}
 
-    for (i, (name, child)) in children.iter().enumerate() {
-        let is_last_child = i == children.len() - 1;
-        let new_prefix = format!("{}{}    ", prefix, if is_last_child { " " } else { "┃" });
+/// Builds the machine-readable report of `file_stats`.
+pub fn stats_report(
+    file_stats: &HashMap<PathBuf, FileStats>,
+    options: &StatsOptions,
+) -> Result<StatsReport, ClipboardError> {
+    build_report(file_stats, options).map(|(_, report)| report)
+}
 
+/// Builds the trie of `file_stats` and the full report.
+fn build_report(
+    file_stats: &HashMap<PathBuf, FileStats>,
+    options: &StatsOptions,
+) -> Result<(Trie, StatsReport), ClipboardError> {
+    let (trie, mut report) = build_trie(file_stats)?;
+    collect_directories(trie.get_root(), "", 1, options, &mut report);
+    let total = report.total_tokens;
+    let mut paths: Vec<&PathBuf> = file_stats.keys().collect();
+    paths.sort();
+    for path in paths {
+        let stats = &file_stats[path];
+        let copied_tokens = match (stats.skipped.is_some() || stats.omitted, stats.outlined) {
+            (true, _) => 0,
+            (false, true) => stats.outline_tokens.unwrap_or(0),
+            (false, false) => stats.tokens,
+        };
+        report.files.push(FileReport {
+            path: path.to_string_lossy().replace('\\', "/"),
+            tokens: stats.tokens,
+            copied_tokens,
+            percent: percent(copied_tokens, total),
+            outline_tokens: stats.outline_tokens,
+            outlined: stats.outlined,
+            omitted: stats.omitted,
+            skipped: stats.skipped.as_ref().map(|reason| reason.to_string()),
+            redacted: stats.redactions.values().sum(),
+            compacted_tokens: stats.compacted_tokens,
+            notes: stats.notes.clone(),
+        });
+    }
+    if options.sort == StatsSort::Tokens {
+        report.files.sort_by(|a, b| {
+            b.copied_tokens
+                .cmp(&a.copied_tokens)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+    if let Some(count) = options.top {
+        report.top = heaviest_files(&report.files, count);
+    }
+    Ok((trie, report))
+}
+
+/// Adds the directories below `node`, up to the maximum depth, to `report`.
+fn collect_directories(
+    node: &TrieNode,
+    path: &str,
+    depth: usize,
+    options: &StatsOptions,
+    report: &mut StatsReport,
+) {
+    if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return;
+    }
+    for (name, child) in sorted_children(node, options.sort) {
         if child.token_count.is_some() {
-            info!(
-                "{}{} {} {} ({} tokens)",
-                prefix,
-                connector,
-                get_file_icon(&Path::new(name))
-                    .map_err(|e| ClipboardError::IoError(e.to_string()))?,
-                name,
-                child.token_count.unwrap()
-            );
+            continue;
+        }
+        let child_path = if path.is_empty() {
+            name.clone()
         } else {
-            let total_tokens = child.calculate_total_tokens();
+            format!("{}/{}", path, name)
+        };
+        let tokens = child.calculate_total_tokens();
+        report.directories.push(DirectoryReport {
+            path: child_path.clone(),
+            tokens,
+            percent: percent(tokens, report.total_tokens),
+            files: child.file_count(),
+        });
+        collect_directories(child, &child_path, depth + 1, options, report);
+    }
+}
+
+fn print_stats_tree(
+    file_stats: &HashMap<PathBuf, FileStats>,
+    options: &StatsOptions,
+) -> Result<(), ClipboardError> {
+    debug!("Printing statistics for token counts");
+    let (trie, report) = build_report(file_stats, options)?;
+
+    info!("Overall ({} tokens)", report.total_tokens);
+    if report.outlined_files > 0 {
+        info!("Outlined {} files", report.outlined_files);
+    }
+    if report.omitted_files > 0 {
+        info!(
+            "Omitted {} files ({} tokens)",
+            report.omitted_files, report.omitted_tokens
+        );
+    }
+    if !report.skipped.is_empty() {
+        let reasons: Vec<String> = report
+            .skipped
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        info!(
+            "Skipped {} files ({})",
+            report.skipped.values().sum::<usize>(),
+            reasons.join(", ")
+        );
+    }
+    if !report.redactions.is_empty() {
+        let rules: Vec<String> = report
+            .redactions
+            .iter()
+            .map(|(rule, count)| format!("{} {}", count, rule))
+            .collect();
+        info!(
+            "Redacted {} secrets ({})",
+            report.redactions.values().sum::<usize>(),
+            rules.join(", ")
+        );
+    }
+    if report.compacted_tokens > 0 {
+        info!(
+            "Compaction saved {} tokens ({:.1}%)",
+            report.compacted_tokens,
+            percent(
+                report.compacted_tokens,
+                report.total_tokens + report.compacted_tokens
+            )
+        );
+    }
+    let printer = TreePrinter {
+        options,
+        total_tokens: report.total_tokens,
+    };
+    printer.print(trie.get_root(), "", 1)?;
+
+    if options.top.is_some() {
+        info!("Heaviest {} files:", report.top.len());
+        for (i, file) in report.top.iter().enumerate() {
             info!(
-                "{}{}📂 {} ({} tokens)",
-                prefix, connector, name, total_tokens
+                "{:>3}. {} ({} tokens, {:.1}%)",
+                i + 1,
+                file.path,
+                file.copied_tokens,
+                file.percent
             );
-            print_tree(child, &new_prefix, is_last_child)?;
         }
     }
     Ok(())
 }
 
+/// Width of the bar charts of directories, in characters.
+const BAR_WIDTH: usize = 10;
+
+struct TreePrinter<'a> {
+    options: &'a StatsOptions,
+    total_tokens: usize,
+}
+
+impl TreePrinter<'_> {
+    fn bar(&self, tokens: usize) -> String {
+        let filled =
+            (percent(tokens, self.total_tokens) / 100.0 * BAR_WIDTH as f64).round() as usize;
+        let (full, empty) = if self.options.emoji {
+            ("█", "░")
+        } else {
+            ("#", ".")
+        };
+        format!(
+            "{}{}",
+            full.repeat(filled),
+            empty.repeat(BAR_WIDTH - filled)
+        )
+    }
+
+    fn icon(&self, name: &str) -> Result<String, ClipboardError> {
+        if !self.options.emoji {
+            return Ok(String::new());
+        }
+        let icon =
+            get_file_icon(Path::new(name)).map_err(|e| ClipboardError::IoError(e.to_string()))?;
+        Ok(format!("{} ", icon))
+    }
+
+    fn print(&self, node: &TrieNode, prefix: &str, depth: usize) -> Result<(), ClipboardError> {
+        let emoji = self.options.emoji;
+        let children = sorted_children(node, self.options.sort);
+        for (i, (name, child)) in children.iter().enumerate() {
+            let is_last_child = i == children.len() - 1;
+            let connector = match (emoji, is_last_child) {
+                (true, true) => "┗━━ ",
+                (true, false) => "┣━━ ",
+                (false, true) => "`-- ",
+                (false, false) => "|-- ",
+            };
+            let continuation = match (emoji, is_last_child) {
+                (_, true) => " ",
+                (true, false) => "┃",
+                (false, false) => "|",
+            };
+            let new_prefix = format!("{}{}    ", prefix, continuation);
+
+            if let Some(reason) = &child.skipped {
+                let marker = if emoji { "⏭️ skipped" } else { "skipped" };
+                info!(
+                    "{}{}{}{} {} [{}]",
+                    prefix,
+                    connector,
+                    self.icon(name)?,
+                    name,
+                    marker,
+                    reason
+                );
+            } else if let Some(token_count) = child.token_count {
+                let sizes = match (child.outline_tokens, child.outlined) {
+                    (Some(outline_tokens), true) => {
+                        format!("{} tokens, outline of {}", outline_tokens, token_count)
+                    }
+                    (Some(outline_tokens), false) => {
+                        format!("{} tokens, outline {}", token_count, outline_tokens)
+                    }
+                    (None, _) => format!("{} tokens", token_count),
+                };
+                let mut suffix = String::new();
+                if child.outlined {
+                    suffix.push_str(if emoji { " 🧩 outlined" } else { " outlined" });
+                }
+                if child.omitted {
+                    suffix.push_str(if emoji { " ✂️ omitted" } else { " omitted" });
+                }
+                if child.redacted > 0 {
+                    let lock = if emoji { " 🔒" } else { "" };
+                    suffix.push_str(&format!("{} {} redacted", lock, child.redacted));
+                }
+                if child.compacted > 0 {
+                    let press = if emoji { " 🗜️" } else { "" };
+                    suffix.push_str(&format!("{} -{} compacted", press, child.compacted));
+                }
+                if !child.notes.is_empty() {
+                    suffix.push_str(&format!(" [{}]", child.notes.join("; ")));
+                }
+                info!(
+                    "{}{}{}{} ({}, {:.1}%){}",
+                    prefix,
+                    connector,
+                    self.icon(name)?,
+                    name,
+                    sizes,
+                    percent(child.copied_tokens(), self.total_tokens),
+                    suffix
+                );
+            } else {
+                let total_tokens = child.calculate_total_tokens();
+                let folder = if emoji { "📂 " } else { "" };
+                let collapsed = self
+                    .options
+                    .max_depth
+                    .is_some_and(|max_depth| depth >= max_depth);
+                let files = if collapsed {
+                    format!(", {} files", child.file_count())
+                } else {
+                    String::new()
+                };
+                info!(
+                    "{}{}{}{} ({} tokens, {:.1}%{}) {}",
+                    prefix,
+                    connector,
+                    folder,
+                    name,
+                    total_tokens,
+                    percent(total_tokens, self.total_tokens),
+                    files,
+                    self.bar(total_tokens)
+                );
+                if !collapsed {
+                    self.print(child, &new_prefix, depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 pub fn get_file_icon(path: &Path) -> Result<&'static str, ClipboardError> {
     debug!("Getting file icon for path: {:?}", path);
     match path.extension().and_then(|e| e.to_str()) {
@@ -0,0 +1,354 @@
+//! Local BM25 search over the files of a workspace, for choosing files by a
+//! natural-language query.
+//!
+//! Files are indexed by the words of their path and content: identifiers are
+//! split on camelCase and snake_case, and comments are indexed like code. The
+//! index is cached in `.snippy/index.json` at the project root, next to a
+//! `.gitignore` that keeps it out of commits, and a file is only read again
+//! when its modification time or size changes. Callers choose what of a file
+//! is indexed, so that filtered files and secrets never reach the cache.
+
+use crate::errors::ClipboardError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, warn};
+
+/// Search index, relative to the project root.
+pub const INDEX_FILE: &str = ".snippy/index.json";
+
+/// Files copied for a query by default.
+pub const DEFAULT_QUERY_TOP: usize = 10;
+
+/// Bumped whenever the way files are indexed changes.
+const INDEX_VERSION: u32 = 2;
+
+/// Files larger than this are only indexed by their path.
+const MAX_INDEXED_BYTES: u64 = 1024 * 1024;
+
+/// Path words count this many times as much as words in the content.
+const PATH_WEIGHT: u32 = 3;
+
+/// BM25 term frequency saturation and length normalization.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "do", "does", "for", "from", "how", "if",
+    "in", "into", "is", "it", "of", "on", "or", "that", "the", "this", "to", "what", "when",
+    "where", "which", "who", "why", "with",
+];
+
+static WORD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z][A-Za-z0-9]*").expect("Invalid regex"));
+
+/// Splits a camelCase or PascalCase word, keeping acronyms such as `HTTP` together.
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (position, c) = chars[i];
+        let previous = chars[i - 1].1;
+        let next_is_lower = chars
+            .get(i + 1)
+            .is_some_and(|(_, next)| next.is_lowercase());
+        if c.is_uppercase()
+            && (previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower))
+        {
+            parts.push(&word[start..position]);
+            start = position;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// Reduces a lowercase word to a rough stem, so that `applies`, `applied` and
+/// `applier` all match `apply`.
+fn stem(word: &str) -> String {
+    let mut stem = word.to_string();
+    for (suffix, replacement) in [
+        ("ies", "y"),
+        ("ied", "y"),
+        ("ier", "y"),
+        ("ing", ""),
+        ("ed", ""),
+        ("s", ""),
+    ] {
+        if stem.len() > suffix.len() + 2 && stem.ends_with(suffix) && !stem.ends_with("ss") {
+            stem.truncate(stem.len() - suffix.len());
+            stem.push_str(replacement);
+            break;
+        }
+    }
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+/// Search terms of `text`: its words split on camelCase and snake_case,
+/// lowercased and stemmed, with compound identifiers also kept whole.
+pub fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut push = |word: &str| {
+        let word = word.to_lowercase();
+        if word.len() >= 2 && !STOP_WORDS.contains(&word.as_str()) {
+            terms.push(stem(&word));
+        }
+    };
+    for word in WORD.find_iter(text) {
+        let parts = split_camel_case(word.as_str());
+        if parts.len() > 1 {
+            push(word.as_str());
+        }
+        for part in parts {
+            push(part);
+        }
+    }
+    terms
+}
+
+/// A file ranked for a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub file: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Modification time in milliseconds since the Unix epoch.
+    modified: u64,
+    size: u64,
+    /// Number of terms, counting path terms by their weight.
+    length: u32,
+    terms: BTreeMap<String, u32>,
+}
+
+/// Term counts of workspace files, keyed by their path from the project root.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    /// Filter and redaction settings the files were indexed with.
+    #[serde(default)]
+    settings: String,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+/// Key of `file` in an index at `root`.
+fn index_key(root: &Path, file: &str) -> String {
+    let path = std::path::absolute(file).unwrap_or_else(|_| PathBuf::from(file));
+    path.strip_prefix(root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+impl SearchIndex {
+    /// Reads the index at `path`, starting over when it is missing, unreadable
+    /// or written by another version.
+    pub fn load(path: &Path) -> Self {
+        let index = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| match serde_json::from_str::<SearchIndex>(&json) {
+                Ok(index) => Some(index),
+                Err(e) => {
+                    warn!("Rebuilding the search index {:?}: {}", path, e);
+                    None
+                }
+            })
+            .filter(|index| index.version == INDEX_VERSION);
+        index.unwrap_or(SearchIndex {
+            version: INDEX_VERSION,
+            settings: String::new(),
+            files: BTreeMap::new(),
+        })
+    }
+
+    /// Starts over when the index was built with other `settings`, which
+    /// describe how files are filtered and redacted before indexing.
+    pub fn for_settings(mut self, settings: &str) -> Self {
+        if self.settings != settings {
+            debug!("Rebuilding the search index for new settings");
+            self.settings = settings.to_string();
+            self.files.clear();
+        }
+        self
+    }
+
+    /// Writes the index to `path`, replacing the previous one atomically.
+    ///
+    /// A `.gitignore` is added to the index directory unless it has one.
+    pub fn save(&self, path: &Path) -> Result<(), ClipboardError> {
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+            fs::create_dir_all(dir)?;
+            let gitignore = dir.join(".gitignore");
+            if !gitignore.exists() {
+                fs::write(&gitignore, format!("{}\n", name.to_string_lossy()))?;
+            }
+        }
+        let json =
+            serde_json::to_string(self).map_err(|e| ClipboardError::FileError(e.to_string()))?;
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, json)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Number of indexed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Indexes the new and modified `files` of the project at `root`, and
+    /// forgets files that no longer exist.
+    ///
+    /// Returns the number of files read and forgotten.
+    pub fn update(&mut self, root: &Path, files: &[String]) -> usize {
+        self.update_with(root, files, |_, bytes| {
+            std::str::from_utf8(bytes).ok().map(str::to_string)
+        })
+    }
+
+    /// Like [`SearchIndex::update`], indexing the text `prepare` makes of each
+    /// file's path from the root and content. Files it returns `None` for are
+    /// remembered without any terms, so they are never ranked.
+    pub fn update_with(
+        &mut self,
+        root: &Path,
+        files: &[String],
+        mut prepare: impl FnMut(&str, &[u8]) -> Option<String>,
+    ) -> usize {
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        let before = self.files.len();
+        self.files.retain(|key, _| root.join(key).is_file());
+        let mut changed = before - self.files.len();
+
+        for file in files {
+            let Ok(metadata) = fs::metadata(file) else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_millis() as u64);
+            let size = metadata.len();
+            let key = index_key(&root, file);
+            if self
+                .files
+                .get(&key)
+                .is_some_and(|indexed| indexed.modified == modified && indexed.size == size)
+            {
+                continue;
+            }
+
+            let bytes = (size <= MAX_INDEXED_BYTES)
+                .then(|| fs::read(file).ok())
+                .flatten();
+            let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+            match bytes.map(|bytes| prepare(&key, &bytes)) {
+                // Rejected files keep an entry without terms
+                Some(None) => debug!("Not indexing {}", key),
+                content => {
+                    for term in terms(&key) {
+                        *counts.entry(term).or_insert(0) += PATH_WEIGHT;
+                    }
+                    match content.flatten() {
+                        Some(content) => {
+                            for term in terms(&content) {
+                                *counts.entry(term).or_insert(0) += 1;
+                            }
+                        }
+                        None => debug!("Indexing {} by its path only", key),
+                    }
+                }
+            }
+            let length = counts.values().sum();
+            self.files.insert(
+                key,
+                IndexedFile {
+                    modified,
+                    size,
+                    length,
+                    terms: counts,
+                },
+            );
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Ranks `files` of the project at `root` against `query` with BM25,
+    /// best first. Files matching none of the query terms are left out.
+    pub fn search(&self, root: &Path, query: &str, files: &[String]) -> Vec<SearchHit> {
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        let documents: Vec<(&String, &IndexedFile)> = files
+            .iter()
+            .filter_map(|file| Some((file, self.files.get(&index_key(&root, file))?)))
+            .collect();
+        if documents.is_empty() {
+            return Vec::new();
+        }
+        let count = documents.len() as f64;
+        let average_length = (documents
+            .iter()
+            .map(|(_, indexed)| indexed.length as f64)
+            .sum::<f64>()
+            / count)
+            .max(1.0);
+
+        let mut seen = HashSet::new();
+        let query_terms: Vec<String> = terms(query)
+            .into_iter()
+            .filter(|term| seen.insert(term.clone()))
+            .collect();
+        let mut scores = vec![0.0; documents.len()];
+        for term in &query_terms {
+            let frequency = documents
+                .iter()
+                .filter(|(_, indexed)| indexed.terms.contains_key(term))
+                .count() as f64;
+            if frequency == 0.0 {
+                continue;
+            }
+            let idf = ((count - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+            for (score, (_, indexed)) in scores.iter_mut().zip(&documents) {
+                let Some(&tf) = indexed.terms.get(term) else {
+                    continue;
+                };
+                let tf = tf as f64;
+                let norm = 1.0 - B + B * indexed.length as f64 / average_length;
+                *score += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = documents
+            .into_iter()
+            .zip(scores)
+            .filter(|(_, score)| *score > 0.0)
+            .map(|((file, _), score)| SearchHit {
+                file: file.clone(),
+                score,
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.file.cmp(&b.file))
+        });
+        hits
+    }
+}
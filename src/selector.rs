@@ -0,0 +1,143 @@
+use crate::symbols::{find_symbol, Language};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use tracing::warn;
+
+static LINE_RANGE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:(\d+)(?:-(\d*))?|-(\d+))$").expect("Invalid regex"));
+
+/// Part of a file named by a copy argument such as `src/watch.rs:120-260`
+/// or `src/watch.rs::ClipboardWatcher::process_with_ai`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Lines `start..=end` (1-based); a missing end means the end of the file.
+    Lines { start: usize, end: Option<usize> },
+    /// The last `n` lines of the file.
+    LastLines(usize),
+    /// A named item such as a function, type or method, with its doc comments.
+    Symbol { language: Language, name: String },
+}
+
+/// A resolved, 1-based inclusive line range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl LineRange {
+    /// Returns the lines of `content` covered by this range.
+    pub fn slice<'a>(&self, content: &'a str) -> Vec<&'a str> {
+        content
+            .lines()
+            .skip(self.start - 1)
+            .take(self.end + 1 - self.start)
+            .collect()
+    }
+}
+
+impl Selection {
+    /// Resolves the selection against `content`, clamping to its length.
+    pub fn resolve(&self, content: &str) -> Option<LineRange> {
+        let total = content.lines().count();
+        let (start, end) = match self {
+            Selection::Lines { start, end } => ((*start).max(1), end.unwrap_or(total).min(total)),
+            Selection::LastLines(n) => ((total + 1).saturating_sub(*n).max(1), total),
+            Selection::Symbol { language, name } => return find_symbol(content, *language, name),
+        };
+        if total == 0 || start > end {
+            return None;
+        }
+        Some(LineRange { start, end })
+    }
+
+    /// Heading label for the part of `file` covered by `range`.
+    pub fn label(&self, file: &str, range: LineRange) -> String {
+        match self {
+            Selection::Symbol { name, .. } => format!("{}::{}:{}", file, name, range),
+            _ => format!("{}:{}", file, range),
+        }
+    }
+}
+
+/// A copy argument split into its path or pattern and an optional selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSelector {
+    pub pattern: String,
+    pub selection: Option<Selection>,
+}
+
+/// Parses `path:120-260`, `path:120-`, `path:120` and `path:-50` arguments,
+/// and `path::Symbol` for languages with symbol support.
+///
+/// Arguments without a recognizable suffix are returned unchanged.
+pub fn parse_selector(arg: &str) -> FileSelector {
+    if let Some(selector) = parse_symbol_selector(arg) {
+        return selector;
+    }
+
+    let unchanged = FileSelector {
+        pattern: arg.to_string(),
+        selection: None,
+    };
+    let Some((pattern, spec)) = arg.rsplit_once(':') else {
+        return unchanged;
+    };
+    let Some(caps) = LINE_RANGE_REGEX.captures(spec) else {
+        return unchanged;
+    };
+    if pattern.is_empty() {
+        return unchanged;
+    }
+
+    let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<usize>().ok());
+    let selection = if let Some(n) = number(3) {
+        Selection::LastLines(n)
+    } else {
+        let start = number(1).unwrap_or(1);
+        let end = match caps.get(2) {
+            Some(m) if m.as_str().is_empty() => None,
+            Some(_) => number(2),
+            None => Some(start),
+        };
+        if end.is_some_and(|end| end < start) {
+            warn!("Ignoring empty line range in {}", arg);
+            return unchanged;
+        }
+        Selection::Lines { start, end }
+    };
+
+    FileSelector {
+        pattern: pattern.to_string(),
+        selection: Some(selection),
+    }
+}
+
+/// Splits `path::Symbol` at the first `::` that follows a supported source file.
+fn parse_symbol_selector(arg: &str) -> Option<FileSelector> {
+    arg.match_indices("::").find_map(|(i, _)| {
+        let (pattern, name) = (&arg[..i], &arg[i + 2..]);
+        let language = Language::from_path(pattern)?;
+        if name.is_empty() {
+            return None;
+        }
+        Some(FileSelector {
+            pattern: pattern.to_string(),
+            selection: Some(Selection::Symbol {
+                language,
+                name: name.to_string(),
+            }),
+        })
+    })
+}
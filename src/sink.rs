@@ -0,0 +1,159 @@
+use crate::errors::ClipboardError;
+use crate::osc52::{is_remote_session, Osc52Sink};
+use crate::utils::write_file_async;
+use arboard::Clipboard;
+use async_trait::async_trait;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Where the copied content is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SinkKind {
+    #[default]
+    Clipboard,
+    Stdout,
+    File,
+    /// The clipboard plus the output file, or stdout when no file is given.
+    Both,
+}
+
+/// Which mechanism the clipboard sink uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ClipboardBackend {
+    /// OSC 52 over SSH without a display server, the system clipboard otherwise.
+    #[default]
+    Auto,
+    System,
+    Osc52,
+}
+
+impl ClipboardBackend {
+    fn sink(self) -> Box<dyn OutputSink> {
+        match self {
+            ClipboardBackend::Auto if is_remote_session() => {
+                debug!("Remote session without a display, using OSC 52");
+                Box::new(Osc52Sink::default())
+            }
+            ClipboardBackend::Auto | ClipboardBackend::System => Box::new(ClipboardSink),
+            ClipboardBackend::Osc52 => Box::new(Osc52Sink::default()),
+        }
+    }
+}
+
+#[async_trait]
+pub trait OutputSink: Send + Sync {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError>;
+
+    /// Human-readable destination used in log messages.
+    fn describe(&self) -> String;
+}
+
+pub struct ClipboardSink;
+
+#[async_trait]
+impl OutputSink for ClipboardSink {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError> {
+        debug!("Initializing clipboard");
+        let mut clipboard =
+            Clipboard::new().map_err(|e| ClipboardError::ClipboardInitError(e.to_string()))?;
+        clipboard
+            .set_text(content)
+            .map_err(|e| ClipboardError::ClipboardWriteError(e.to_string()))
+    }
+
+    fn describe(&self) -> String {
+        "clipboard".to_string()
+    }
+}
+
+pub struct StdoutSink;
+
+#[async_trait]
+impl OutputSink for StdoutSink {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "stdout".to_string()
+    }
+}
+
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        FileSink { path }
+    }
+}
+
+#[async_trait]
+impl OutputSink for FileSink {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError> {
+        write_file_async(&self.path, content).await?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// Writes the same content to several sinks in order.
+pub struct MultiSink {
+    sinks: Vec<Box<dyn OutputSink>>,
+}
+
+impl MultiSink {
+    pub fn new(sinks: Vec<Box<dyn OutputSink>>) -> Self {
+        MultiSink { sinks }
+    }
+}
+
+#[async_trait]
+impl OutputSink for MultiSink {
+    async fn write(&self, content: &str) -> Result<(), ClipboardError> {
+        for sink in &self.sinks {
+            sink.write(content).await?;
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.sinks
+            .iter()
+            .map(|s| s.describe())
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
+}
+
+/// Builds the sink for `kind`, writing files to `output` when given.
+pub fn build_sink(
+    kind: SinkKind,
+    output: Option<PathBuf>,
+    backend: ClipboardBackend,
+) -> Result<Box<dyn OutputSink>, ClipboardError> {
+    match (kind, output) {
+        (SinkKind::Clipboard, _) => Ok(backend.sink()),
+        (SinkKind::Stdout, _) => Ok(Box::new(StdoutSink)),
+        (SinkKind::File, Some(path)) => Ok(Box::new(FileSink::new(path))),
+        (SinkKind::File, None) => Err(ClipboardError::ConfigError(
+            "The file sink requires an output path".to_string(),
+        )),
+        (SinkKind::Both, Some(path)) => Ok(Box::new(MultiSink::new(vec![
+            backend.sink(),
+            Box::new(FileSink::new(path)),
+        ]))),
+        (SinkKind::Both, None) => Ok(Box::new(MultiSink::new(vec![
+            backend.sink(),
+            Box::new(StdoutSink),
+        ]))),
+    }
+}
@@ -0,0 +1,429 @@
+use crate::selector::LineRange;
+use crate::utils::get_file_type;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+static RUST_ITEM_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:default|const|async|unsafe|extern(?:\s+"[^"]*")?)\s+)*(?P<kind>fn|struct|enum|union|trait|type|mod|const|static|macro_rules!)\s+(?:mut\s+)?(?P<name>[A-Za-z_]\w*)"#,
+    )
+    .expect("Invalid regex")
+});
+static RUST_IMPL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:unsafe\s+)?impl\b(?:\s*<.*?>)?\s+(?:(?:\w+::)*(?P<trait>\w+)(?:<.*?>)?\s+for\s+)?(?:&\s*)?(?:\w+::)*(?P<name>\w+)",
+    )
+    .expect("Invalid regex")
+});
+static PYTHON_ITEM_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:async\s+)?(?P<kind>def|class)\s+(?P<name>\w+)").expect("Invalid regex")
+});
+static TS_ITEM_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(?P<kind>function\*?|class|interface|type|enum|const|let|var|namespace|module)\s+(?P<name>[A-Za-z_$][\w$]*)",
+    )
+    .expect("Invalid regex")
+});
+static TS_MEMBER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:(?:public|private|protected|static|readonly|abstract|override|async|get|set)\s+)*\*?(?P<name>[A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\(",
+    )
+    .expect("Invalid regex")
+});
+static GO_FUNC_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^func\s+(?:\(\s*(?:\w+\s+)?\*?(?P<owner>\w+)(?:\[[^\]]*\])?\s*\)\s*)?(?P<name>\w+)",
+    )
+    .expect("Invalid regex")
+});
+static GO_ITEM_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?P<kind>type|var|const)\s+(?P<name>\w+)").expect("Invalid regex"));
+
+/// Keywords that look like method declarations to `TS_MEMBER_REGEX`.
+const TS_CONTROL_KEYWORDS: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "function", "with", "new", "typeof",
+];
+
+/// Languages whose items can be addressed by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    TypeScript,
+    Go,
+}
+
+impl Language {
+    /// Detects the language from the file extension of `path`.
+    pub fn from_path(path: &str) -> Option<Language> {
+        let ext = Path::new(path).extension()?.to_str()?;
+        match get_file_type(ext).ok()? {
+            "rust" => Some(Language::Rust),
+            "python" => Some(Language::Python),
+            "typescript" | "javascript" => Some(Language::TypeScript),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    /// Whether `line` (already trimmed) belongs to the docs or attributes above an item.
+    fn is_preamble(self, line: &str) -> bool {
+        match self {
+            Language::Rust => {
+                line.starts_with("//") || line.starts_with("#[") || is_block_comment(line)
+            }
+            Language::Python => line.starts_with('#') || line.starts_with('@'),
+            Language::TypeScript => {
+                line.starts_with("//") || line.starts_with('@') || is_block_comment(line)
+            }
+            Language::Go => line.starts_with("//") || is_block_comment(line),
+        }
+    }
+}
+
+fn is_block_comment(line: &str) -> bool {
+    line.starts_with("/*") || line.starts_with('*')
+}
+
+/// Broad category of a declared item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ItemKind {
+    /// Functions and methods, whose bodies can be elided.
+    Function,
+    /// Types, impl blocks and modules, which may contain other items.
+    Container,
+    Other,
+}
+
+impl ItemKind {
+    fn from_keyword(keyword: &str) -> ItemKind {
+        match keyword.trim_end_matches('*') {
+            "fn" | "def" | "function" | "func" => ItemKind::Function,
+            "struct" | "enum" | "union" | "trait" | "mod" | "impl" | "type" | "class"
+            | "interface" | "namespace" | "module" => ItemKind::Container,
+            _ => ItemKind::Other,
+        }
+    }
+}
+
+/// A named item declared on a single line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Declaration {
+    pub name: String,
+    pub kind: ItemKind,
+    /// Receiver type of a Go method.
+    pub owner: Option<String>,
+}
+
+/// Recognizes the item declared on `line`; `nested` enables class member syntax.
+pub(crate) fn declaration(line: &str, language: Language, nested: bool) -> Option<Declaration> {
+    let named = |regex: &Regex, keyword: &str| {
+        regex.captures(line).map(|caps| {
+            let keyword = caps.name("kind").map_or(keyword, |m| m.as_str());
+            let mut kind = ItemKind::from_keyword(keyword);
+            // `const handler = async () => { ... }` is a function in all but name
+            if kind == ItemKind::Other && language == Language::TypeScript && line.contains("=>") {
+                kind = ItemKind::Function;
+            }
+            Declaration {
+                name: caps["name"].to_string(),
+                kind,
+                owner: caps.name("owner").map(|m| m.as_str().to_string()),
+            }
+        })
+    };
+    match language {
+        Language::Rust => named(&RUST_ITEM_REGEX, "fn").or_else(|| named(&RUST_IMPL_REGEX, "impl")),
+        Language::Python => named(&PYTHON_ITEM_REGEX, "def"),
+        Language::TypeScript => named(&TS_ITEM_REGEX, "function").or_else(|| {
+            // Class members are only recognized inside a parent item, where calls are rare
+            named(&TS_MEMBER_REGEX, "function")
+                .filter(|d| nested && !TS_CONTROL_KEYWORDS.contains(&d.name.as_str()))
+        }),
+        Language::Go => named(&GO_FUNC_REGEX, "func").or_else(|| named(&GO_ITEM_REGEX, "type")),
+    }
+}
+
+/// Finds the item named by `symbol` in `content`, including its doc comments.
+///
+/// Nested items are separated by `::` or `.`, e.g. `ClipboardWatcher::process_with_ai`
+/// or `Parser.parse`. Go methods are addressed through their receiver type. Rust
+/// impl blocks are named `impl Type` or `impl Trait for Type`.
+pub fn find_symbol(content: &str, language: Language, symbol: &str) -> Option<LineRange> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (start, end) = match symbol.strip_prefix("impl ") {
+        Some(target) if language == Language::Rust => find_impl(&lines, target.trim())?,
+        _ => {
+            let segments: Vec<&str> = symbol.split("::").flat_map(|s| s.split('.')).collect();
+            if segments.iter().any(|s| s.is_empty()) {
+                return None;
+            }
+            find_item(&lines, language, &segments, 0, lines.len(), false)?
+        }
+    };
+    Some(LineRange {
+        start: preamble_start(&lines, language, start) + 1,
+        end: end + 1,
+    })
+}
+
+/// Returns the first and last line index of the first Rust impl block for `target`,
+/// either `Type` for an inherent impl or `Trait for Type`.
+fn find_impl(lines: &[&str], target: &str) -> Option<(usize, usize)> {
+    let (trait_name, type_name) = match target.split_once(" for ") {
+        Some((trait_name, type_name)) => (Some(trait_name.trim()), type_name.trim()),
+        None => (None, target),
+    };
+    // Paths such as `fmt::Display` are matched by their last segment
+    let last = |path: &str| path.rsplit("::").next().unwrap_or(path).to_string();
+    let trait_name = trait_name.map(last);
+    let type_name = last(type_name);
+    (0..lines.len()).find_map(|i| {
+        let caps = RUST_IMPL_REGEX.captures(lines[i])?;
+        let implemented = caps.name("trait").map(|m| m.as_str().to_string());
+        (caps["name"] == type_name && implemented == trait_name)
+            .then(|| (i, item_end(lines, i, Language::Rust)))
+    })
+}
+
+/// Returns the first and last line index of the item named by `segments` within `from..to`.
+fn find_item(
+    lines: &[&str],
+    language: Language,
+    segments: &[&str],
+    from: usize,
+    to: usize,
+    nested: bool,
+) -> Option<(usize, usize)> {
+    for i in from..to {
+        let Some(decl) = declaration(lines[i], language, nested) else {
+            continue;
+        };
+        if segments.len() == 2
+            && decl.owner.as_deref() == Some(segments[0])
+            && decl.name == segments[1]
+        {
+            return Some((i, item_end(lines, i, language)));
+        }
+        if decl.name != segments[0] {
+            continue;
+        }
+
+        let end = item_end(lines, i, language);
+        if segments.len() == 1 {
+            return Some((i, end));
+        }
+        // A name can match several items (a struct and its impl blocks), so keep looking
+        if let Some(found) = find_item(lines, language, &segments[1..], i + 1, end + 1, true) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Walks up from `start` over the doc comments, attributes and decorators of an item.
+pub(crate) fn preamble_start(lines: &[&str], language: Language, start: usize) -> usize {
+    let mut first = start;
+    while first > 0 {
+        let line = lines[first - 1].trim();
+        if line.is_empty() || !language.is_preamble(line) {
+            break;
+        }
+        first -= 1;
+    }
+    first
+}
+
+/// Returns the index of the last line of the item declared at `start`.
+pub(crate) fn item_end(lines: &[&str], start: usize, language: Language) -> usize {
+    match language {
+        Language::Python => indented_block_end(lines, start),
+        _ => delimited_block_end(lines, start, language),
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Index of the line ending the (possibly multi-line) header of a Python block.
+pub(crate) fn python_header_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i32;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let code = line.split('#').next().unwrap_or("");
+        for c in code.chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 && code.contains(':') {
+            return i;
+        }
+    }
+    lines.len().saturating_sub(1)
+}
+
+/// End of a Python block: the last line indented deeper than its header.
+fn indented_block_end(lines: &[&str], start: usize) -> usize {
+    let header_end = python_header_end(lines, start);
+    let indent = indentation(lines[start]);
+    let mut end = header_end;
+    for (i, line) in lines.iter().enumerate().skip(header_end + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) <= indent {
+            break;
+        }
+        end = i;
+    }
+    end
+}
+
+/// End of a brace-delimited item.
+///
+/// Items without a body end at the first `;` outside brackets, or (outside Rust)
+/// at the end of the first line that neither ends nor is followed by an operator.
+fn delimited_block_end(lines: &[&str], start: usize, language: Language) -> usize {
+    let mut depth = 0i32;
+    scan_code(lines, start, language, |i, c| {
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => {
+                depth -= 1;
+                if depth <= 0 && c == '}' {
+                    return Some(i);
+                }
+            }
+            ';' if depth == 0 => return Some(i),
+            '\n' if language != Language::Rust && depth == 0 => {
+                let trimmed = lines[i].trim_end();
+                let continued = trimmed.ends_with(['=', ',', '|', '&', ':', '+', '.'])
+                    || trimmed.ends_with("=>")
+                    || lines
+                        .get(i + 1)
+                        .is_some_and(|next| next.trim_start().starts_with(['|', '&', '.', '?']));
+                if !trimmed.is_empty() && !continued {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        None
+    })
+    .unwrap_or(lines.len().saturating_sub(1))
+}
+
+/// Index of the line where the body of the brace-delimited item at `start` opens.
+///
+/// Returns `None` for items without a body, such as trait method declarations.
+pub(crate) fn body_start(lines: &[&str], start: usize, language: Language) -> Option<usize> {
+    let mut depth = 0i32;
+    scan_code(lines, start, language, |i, c| {
+        match c {
+            '{' if depth == 0 => return Some(Some(i)),
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            ';' if depth == 0 => return Some(None),
+            '\n' if language != Language::Rust && depth == 0 => {
+                let trimmed = lines[i].trim_end();
+                if !trimmed.is_empty() && !trimmed.ends_with([',', '(', ':', '=', '>']) {
+                    return Some(None);
+                }
+            }
+            _ => {}
+        }
+        None
+    })
+    .flatten()
+}
+
+/// Feeds the code characters of `lines[start..]` to `visit`, skipping strings and
+/// comments, with a `'\n'` at the end of each line outside a string or comment.
+///
+/// Stops at the first `Some` returned by `visit`.
+fn scan_code<T>(
+    lines: &[&str],
+    start: usize,
+    language: Language,
+    mut visit: impl FnMut(usize, char) -> Option<T>,
+) -> Option<T> {
+    let mut in_block_comment = false;
+    let mut in_string: Option<char> = None;
+    // Closing delimiter of the Rust raw string being skipped, e.g. `"#`
+    let mut raw_string_end: Option<String> = None;
+
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            let next = chars.get(j + 1).copied();
+            if in_block_comment {
+                if c == '*' && next == Some('/') {
+                    in_block_comment = false;
+                    j += 1;
+                }
+            } else if let Some(end) = &raw_string_end {
+                if chars[j..].iter().take(end.len()).copied().eq(end.chars()) {
+                    j += end.len() - 1;
+                    raw_string_end = None;
+                }
+            } else if let Some(quote) = in_string {
+                if c == '\\' {
+                    j += 1;
+                } else if c == quote {
+                    in_string = None;
+                }
+            } else {
+                match c {
+                    '/' if next == Some('/') => break,
+                    '/' if next == Some('*') => {
+                        in_block_comment = true;
+                        j += 1;
+                    }
+                    'r' if language == Language::Rust
+                        && (j == 0 || !(chars[j - 1].is_alphanumeric() || chars[j - 1] == '_'))
+                        && matches!(next, Some('"' | '#')) =>
+                    {
+                        let hashes = chars[j + 1..].iter().take_while(|&&c| c == '#').count();
+                        if chars.get(j + 1 + hashes) == Some(&'"') {
+                            raw_string_end = Some(format!("\"{}", "#".repeat(hashes)));
+                            j += 1 + hashes;
+                        }
+                    }
+                    '"' | '`' => in_string = Some(c),
+                    '\'' if language == Language::Rust => {
+                        // Skip char literals but not lifetimes
+                        if next == Some('\\') {
+                            let rest = &chars[(j + 3).min(chars.len())..];
+                            j += rest.iter().position(|&c| c == '\'').unwrap_or(0) + 3;
+                        } else if chars.get(j + 2) == Some(&'\'') {
+                            j += 2;
+                        }
+                    }
+                    '\'' => in_string = Some(c),
+                    _ => {
+                        if let Some(found) = visit(i, c) {
+                            return Some(found);
+                        }
+                    }
+                }
+            }
+            j += 1;
+        }
+        if in_string == Some('\'') {
+            in_string = None;
+        }
+
+        if in_string.is_none() && raw_string_end.is_none() && !in_block_comment {
+            if let Some(found) = visit(i, '\n') {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
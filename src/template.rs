@@ -0,0 +1,262 @@
+use crate::errors::ClipboardError;
+use crate::xml::XmlAttribute;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use tiktoken_rs::CoreBPE;
+
+/// Names of the built-in templates.
+pub const PRESETS: &[&str] = &[
+    "markdown",
+    "markdown-first-line",
+    "plain",
+    "xml",
+    "documents",
+];
+
+const NUMBERED_LINE: &str = "{{number}}{{prefix}}{{line}}";
+
+/// Wrappers around the copied files, with `{{variable}}` placeholders.
+///
+/// The file template gets `path`, `file`, `language`, `extension`, `content`,
+/// `lines`, `bytes`, `tokens`, `hash`, `mtime`, `index` and `filename_comment`. The header and
+/// footer get `files` and `tokens`. Unknown placeholders are left as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    /// Text before the files; the configured first line when unset.
+    pub header: Option<String>,
+    pub file: String,
+    /// Template for diffs; the file template is used when unset.
+    pub diff: Option<String>,
+    pub footer: String,
+    /// Template for each line when line numbers are on, with `number`, `prefix` and `line`.
+    pub line: String,
+    /// Escape values for XML and wrap content in CDATA sections.
+    pub xml: bool,
+}
+
+impl OutputTemplate {
+    /// Built-in template `name`, with code fences unless `markdown` is false.
+    pub fn preset(name: &str, markdown: bool) -> Result<Self, ClipboardError> {
+        let fenced = |heading: &str, fence: &str, body: &str| {
+            if markdown {
+                format!("{}```{}\n{}```\n", heading, fence, body)
+            } else {
+                format!("{}{}", heading, body)
+            }
+        };
+        let template = match name {
+            "markdown" => OutputTemplate {
+                file: fenced("### `{{path}}`\n", "{{extension}}", "{{content}}"),
+                diff: Some(fenced("### `{{path}}` (diff)\n", "diff", "{{content}}")),
+                ..Self::markdown_defaults()
+            },
+            "markdown-first-line" => OutputTemplate {
+                file: fenced("", "{{extension}}", "{{filename_comment}}{{content}}"),
+                diff: Some(fenced("", "diff", "{{content}}")),
+                ..Self::markdown_defaults()
+            },
+            "plain" => OutputTemplate {
+                file: fenced("", "{{extension}}", "{{content}}"),
+                diff: Some(fenced("", "diff", "{{content}}")),
+                ..Self::markdown_defaults()
+            },
+            "xml" => OutputTemplate {
+                header: Some("<files>\n".to_string()),
+                file: xml_file_template(&[]),
+                diff: Some("<diff path=\"{{path}}\">\n{{content}}</diff>\n".to_string()),
+                footer: "</files>\n".to_string(),
+                line: "<line number=\"{{number}}\">{{line}}</line>".to_string(),
+                xml: true,
+            },
+            "documents" => OutputTemplate {
+                header: Some("<documents>\n".to_string()),
+                file: "<document index=\"{{index}}\">\n<source>{{path}}</source>\n<document_content>\n{{content}}</document_content>\n</document>\n"
+                    .to_string(),
+                diff: None,
+                footer: "</documents>\n".to_string(),
+                line: NUMBERED_LINE.to_string(),
+                xml: true,
+            },
+            _ => {
+                return Err(ClipboardError::ConfigError(format!(
+                    "Unknown template '{}', expected one of {} or a template file",
+                    name,
+                    PRESETS.join(", ")
+                )))
+            }
+        };
+        Ok(template)
+    }
+
+    fn markdown_defaults() -> Self {
+        OutputTemplate {
+            header: None,
+            file: String::new(),
+            diff: None,
+            footer: String::new(),
+            line: NUMBERED_LINE.to_string(),
+            xml: false,
+        }
+    }
+
+    /// Adds metadata `attributes` to the `<file>` elements of the XML template.
+    pub fn with_xml_attributes(mut self, attributes: &[XmlAttribute]) -> Self {
+        if self.xml && self.file == xml_file_template(&[]) {
+            self.file = xml_file_template(attributes);
+        }
+        self
+    }
+
+    /// Template for the legacy `--filename-format` and `--xml` options.
+    pub fn from_format(filename_format: &str, xml: bool, markdown: bool) -> Self {
+        let name = match filename_format {
+            _ if xml => "xml",
+            "MarkdownHeading" => "markdown",
+            "MarkdownFirstCodeLine" => "markdown-first-line",
+            _ => "plain",
+        };
+        Self::preset(name, markdown).expect("Built-in template")
+    }
+
+    /// Loads the preset called `spec`, or the template file at that path.
+    pub fn load(spec: &str, markdown: bool) -> Result<Self, ClipboardError> {
+        if PRESETS.contains(&spec) || !Path::new(spec).is_file() {
+            return Self::preset(spec, markdown);
+        }
+        let text = std::fs::read_to_string(spec).map_err(|e| {
+            ClipboardError::ConfigError(format!("Failed to read template {}: {}", spec, e))
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Parses a template file.
+    ///
+    /// Sections start with a `--- header ---`, `--- file ---`, `--- diff ---`,
+    /// `--- footer ---` or `--- line ---` line. Text before the first section
+    /// is the file template. The line template is a single line.
+    pub fn parse(text: &str) -> Result<Self, ClipboardError> {
+        let mut template = Self::markdown_defaults();
+        let mut section = "file".to_string();
+        let mut body = String::new();
+        let mut finish = |section: &str, body: &mut String| -> Result<(), ClipboardError> {
+            let text = std::mem::take(body);
+            match section {
+                "header" => template.header = Some(text),
+                "file" => template.file = text,
+                "diff" => template.diff = Some(text),
+                "footer" => template.footer = text,
+                "line" => template.line = text.trim_end_matches('\n').to_string(),
+                _ => {
+                    return Err(ClipboardError::ConfigError(format!(
+                        "Unknown template section '{}'",
+                        section
+                    )))
+                }
+            }
+            Ok(())
+        };
+
+        for line in text.split_inclusive('\n') {
+            let marker = line.trim();
+            if let Some(name) = marker
+                .strip_prefix("--- ")
+                .and_then(|rest| rest.strip_suffix(" ---"))
+            {
+                if section != "file" || !body.trim().is_empty() {
+                    finish(&section, &mut body)?;
+                }
+                section = name.trim().to_string();
+                continue;
+            }
+            body.push_str(line);
+        }
+        finish(&section, &mut body)?;
+
+        if template.file.trim().is_empty() {
+            return Err(ClipboardError::ConfigError(
+                "Template has no file section".to_string(),
+            ));
+        }
+        Ok(template)
+    }
+
+    /// Returns true if any part of the template uses `{{name}}`.
+    pub fn uses(&self, name: &str) -> bool {
+        let placeholder = format!("{{{{{}}}}}", name);
+        [
+            self.header.as_deref(),
+            Some(self.file.as_str()),
+            self.diff.as_deref(),
+            Some(self.footer.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|part| part.contains(&placeholder))
+    }
+}
+
+fn xml_file_template(attributes: &[XmlAttribute]) -> String {
+    let attributes: String = attributes
+        .iter()
+        .map(|attribute| {
+            let (name, variable) = attribute.attribute();
+            format!(" {}=\"{{{{{}}}}}\"", name, variable)
+        })
+        .collect();
+    format!(
+        "<file path=\"{{{{path}}}}\" type=\"{{{{language}}}}\"{}>\n{{{{content}}}}</file>\n",
+        attributes
+    )
+}
+
+/// Replaces `{{name}}` placeholders in `template` with their values.
+///
+/// Substituted values are not scanned again, so file contents that contain
+/// placeholders are copied as they are.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = after[..end].trim();
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| (*value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Counts tokens for the `{{tokens}}` template variable.
+#[derive(Clone)]
+pub struct TokenCounter(Arc<CoreBPE>);
+
+impl TokenCounter {
+    pub fn new(bpe: CoreBPE) -> Self {
+        TokenCounter(Arc::new(bpe))
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.0.encode_ordinary(text).len()
+    }
+}
+
+impl fmt::Debug for TokenCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenCounter")
+    }
+}
@@ -7,6 +7,25 @@ use tracing::{debug, trace};
 pub struct TrieNode {
     pub children: HashMap<String, TrieNode>,
     pub token_count: Option<usize>,
+    /// Set when the file was left out of the copied content.
+    pub omitted: bool,
+    /// Size of the file's outline, when one was made.
+    pub outline_tokens: Option<usize>,
+    /// Set when the outline was copied instead of the full file.
+    pub outlined: bool,
+    /// Why the file was skipped, if it was.
+    pub skipped: Option<String>,
+    /// Number of secrets redacted from the file.
+    pub redacted: usize,
+    /// Tokens removed from the file by compaction.
+    pub compacted: usize,
+    pub notes: Vec<String>,
+}
+
+impl Default for TrieNode {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TrieNode {
@@ -15,13 +34,43 @@ impl TrieNode {
         TrieNode {
             children: HashMap::new(),
             token_count: None,
+            omitted: false,
+            outline_tokens: None,
+            outlined: false,
+            skipped: None,
+            redacted: 0,
+            compacted: 0,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Number of tokens this node contributes to the copied content.
+    pub fn copied_tokens(&self) -> usize {
+        if self.skipped.is_some() {
+            return 0;
+        }
+        match (self.omitted, self.outlined) {
+            (true, _) => 0,
+            (false, true) => self.outline_tokens.unwrap_or(0),
+            (false, false) => self.token_count.unwrap_or(0),
         }
     }
 
-    /// Calculates the total number of tokens in the subtree.
+    /// Number of files in the subtree.
+    pub fn file_count(&self) -> usize {
+        let own = usize::from(self.token_count.is_some());
+        own + self
+            .children
+            .values()
+            .map(|child| child.file_count())
+            .sum::<usize>()
+    }
+
+    /// Calculates the total number of copied tokens in the subtree.
     pub fn calculate_total_tokens(&self) -> usize {
         trace!("Calculating total tokens for TrieNode");
-        self.token_count.unwrap_or(0)
+        let own_tokens = self.copied_tokens();
+        own_tokens
             + self
                 .children
                 .values()
@@ -35,6 +84,12 @@ pub struct Trie {
     root: TrieNode,
 }
 
+impl Default for Trie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Trie {
     pub fn new() -> Self {
         debug!("Creating new Trie");
@@ -55,7 +110,7 @@ impl Trie {
             current_node = current_node
                 .children
                 .entry(component_str)
-                .or_insert_with(TrieNode::new);
+                .or_default();
         }
         if current_node.token_count.is_some() {
             trace!("Overwriting existing token count for path: {:?}", path);
@@ -64,6 +119,28 @@ impl Trie {
         Ok(())
     }
 
+    /// Returns the node for `path`, if it has been inserted.
+    pub fn get(&self, path: &Path) -> Option<&TrieNode> {
+        let mut current_node = &self.root;
+        for component in path.iter() {
+            current_node = current_node
+                .children
+                .get(component.to_string_lossy().as_ref())?;
+        }
+        Some(current_node)
+    }
+
+    /// Returns the node for `path`, if it has been inserted.
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut TrieNode> {
+        let mut current_node = &mut self.root;
+        for component in path.iter() {
+            current_node = current_node
+                .children
+                .get_mut(component.to_string_lossy().as_ref())?;
+        }
+        Some(current_node)
+    }
+
     /// Returns the root node of the trie.
     pub fn get_root(&self) -> &TrieNode {
         trace!("Getting root of the Trie");
@@ -0,0 +1,177 @@
+//! References to a symbol across the workspace, copied with their surrounding
+//! lines or enclosing functions.
+//!
+//! References are found by name, not resolved: a qualified symbol such as
+//! `LLMClient::call` matches `.call` and `::call` where `LLMClient` is in
+//! scope, that is on the same line, earlier in the enclosing function, or on
+//! the declaration of an enclosing `impl LLMClient` or `class LLMClient`.
+
+use crate::selector::LineRange;
+use crate::symbols::{declaration, item_end, preamble_start, ItemKind, Language};
+use regex::Regex;
+
+/// Lines copied above and below each reference by default.
+pub const DEFAULT_USAGE_CONTEXT: usize = 3;
+
+/// Which symbols to look for and how much around each reference to copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageOptions {
+    /// Symbols whose references are copied, e.g. `LLMClient::call` or `Parser.parse`.
+    pub symbols: Vec<String>,
+    /// Lines copied above and below each reference.
+    pub context: usize,
+    /// Copy the functions enclosing the references instead of the lines around them.
+    pub functions: bool,
+}
+
+impl Default for UsageOptions {
+    fn default() -> Self {
+        UsageOptions {
+            symbols: Vec::new(),
+            context: DEFAULT_USAGE_CONTEXT,
+            functions: false,
+        }
+    }
+}
+
+impl UsageOptions {
+    pub fn is_enabled(&self) -> bool {
+        !self.symbols.is_empty()
+    }
+}
+
+/// References found in one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usages {
+    /// Number of referencing lines per symbol, in the order of the options.
+    pub counts: Vec<(String, usize)>,
+    /// Merged line ranges to copy.
+    pub ranges: Vec<LineRange>,
+}
+
+/// Whether `line` (already trimmed) is a comment in `language`.
+fn is_comment(line: &str, language: Language) -> bool {
+    match language {
+        Language::Python => line.starts_with('#'),
+        _ => line.starts_with("//") || line.starts_with("/*") || line.starts_with('*'),
+    }
+}
+
+/// Whether `qualifier` is mentioned near the reference on line `index`: on the
+/// line itself, earlier in the enclosing function, or on the declaration of
+/// an enclosing type or impl block.
+fn qualifier_in_scope(lines: &[&str], language: Language, index: usize, qualifier: &Regex) -> bool {
+    if qualifier.is_match(lines[index]) {
+        return true;
+    }
+    let function = (0..index).rev().find(|&i| {
+        declaration(lines[i], language, false)
+            .is_some_and(|declared| declared.kind == ItemKind::Function)
+            && item_end(lines, i, language) >= index
+    });
+    if function.is_some_and(|start| {
+        lines[start..index]
+            .iter()
+            .any(|line| qualifier.is_match(line))
+    }) {
+        return true;
+    }
+    (0..index).rev().any(|i| {
+        qualifier.is_match(lines[i])
+            && declaration(lines[i], language, false)
+                .is_some_and(|declared| declared.kind == ItemKind::Container)
+            && item_end(lines, i, language) >= index
+    })
+}
+
+/// 0-based indices of the lines of `lines` referencing `symbol`.
+///
+/// Declarations of the symbol and comment lines are not references. A
+/// qualified symbol is only referenced as a member, with its qualifier in scope.
+pub fn find_references(lines: &[&str], language: Language, symbol: &str) -> Vec<usize> {
+    let segments: Vec<&str> = symbol
+        .split("::")
+        .flat_map(|s| s.split('.'))
+        .filter(|s| !s.is_empty())
+        .collect();
+    let Some((name, qualifiers)) = segments.split_last() else {
+        return Vec::new();
+    };
+    let word =
+        |word: &str| Regex::new(&format!(r"\b{}\b", regex::escape(word))).expect("Invalid regex");
+    let qualifier = qualifiers.last().map(|qualifier| word(qualifier));
+    let name_regex = match qualifier {
+        Some(_) => {
+            Regex::new(&format!(r"(?:\.|::)\s*{}\b", regex::escape(name))).expect("Invalid regex")
+        }
+        None => word(name),
+    };
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| name_regex.is_match(line) && !is_comment(line.trim(), language))
+        .filter(|(_, line)| {
+            declaration(line, language, false).is_none_or(|declared| declared.name != *name)
+        })
+        .filter(|&(i, _)| {
+            qualifier
+                .as_ref()
+                .is_none_or(|qualifier| qualifier_in_scope(lines, language, i, qualifier))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// The innermost function containing line `index`, with its doc comments.
+fn enclosing_function(lines: &[&str], language: Language, index: usize) -> Option<LineRange> {
+    (0..=index).rev().find_map(|i| {
+        let declared = declaration(lines[i], language, false)?;
+        if declared.kind != ItemKind::Function {
+            return None;
+        }
+        let end = item_end(lines, i, language);
+        (end >= index).then(|| LineRange {
+            start: preamble_start(lines, language, i) + 1,
+            end: end + 1,
+        })
+    })
+}
+
+/// Merges overlapping and adjacent ranges.
+pub fn merge_ranges(mut ranges: Vec<LineRange>) -> Vec<LineRange> {
+    ranges.sort_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<LineRange> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + 1 => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Finds the references to the symbols of `options` in `content`.
+///
+/// References outside any function keep their context lines when copying
+/// enclosing functions.
+pub fn find_usages(content: &str, language: Language, options: &UsageOptions) -> Usages {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut usages = Usages::default();
+    let mut ranges = Vec::new();
+    for symbol in &options.symbols {
+        let references = find_references(&lines, language, symbol);
+        for &index in &references {
+            let function = options
+                .functions
+                .then(|| enclosing_function(&lines, language, index))
+                .flatten();
+            ranges.push(function.unwrap_or(LineRange {
+                start: index.saturating_sub(options.context) + 1,
+                end: (index + options.context + 1).min(lines.len()),
+            }));
+        }
+        usages.counts.push((symbol.clone(), references.len()));
+    }
+    usages.ranges = merge_ranges(ranges);
+    usages
+}
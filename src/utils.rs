@@ -1,4 +1,17 @@
 use crate::errors::ClipboardError;
+use crate::git::tracked_files;
+use crate::ignore::WalkOptions;
+use crate::json::{to_line, FileRecord, Record};
+use crate::outline::outline;
+use crate::selector::LineRange;
+use crate::symbols::Language;
+use crate::template::{render, OutputTemplate, TokenCounter};
+use crate::xml::{cdata, escape};
+use chrono::{DateTime, SecondsFormat, Utc};
+use glob::{MatchOptions, Pattern};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use tokio::fs as async_fs;
@@ -18,167 +31,441 @@ pub fn normalize_path(path_str: &str) -> String {
     normalized_path.to_string_lossy().replace("\\", "/")
 }
 
-/// Check if a path contains any of the skipped directories.
-fn is_path_skipped(path: &Path, skip_dirs: &[&str]) -> bool {
-    for component in path.components() {
-        if let std::path::Component::Normal(os_str) = component {
-            if let Some(dir_name) = os_str.to_str() {
-                if skip_dirs.contains(&dir_name) {
-                    return true;
-                }
+/// Split a glob pattern into the literal directory it starts from and the rest.
+pub(crate) fn glob_base(pattern: &str) -> PathBuf {
+    let mut base = PathBuf::new();
+    for component in Path::new(pattern).components() {
+        let part = component.as_os_str().to_string_lossy();
+        if part.contains(['*', '?', '[']) {
+            break;
+        }
+        base.push(component);
+    }
+    base
+}
+
+/// Walk `root` with the given options, keeping files accepted by `keep`.
+fn walk_files(
+    root: &Path,
+    options: &WalkOptions,
+    keep: impl Fn(&str) -> bool,
+) -> Result<Vec<String>, ClipboardError> {
+    let walk_root = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let tracked = if options.tracked_only {
+        Some(tracked_files(walk_root)?)
+    } else {
+        None
+    };
+
+    let mut files = Vec::new();
+    for entry in options.walker(walk_root).build() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Error walking {:?}: {}", walk_root, e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if let Some(tracked) = &tracked {
+            let relative = entry.path().strip_prefix(walk_root).unwrap_or(entry.path());
+            if !tracked.contains(relative) {
+                continue;
             }
         }
+        let path = if root.as_os_str().is_empty() {
+            entry.path().strip_prefix(".").unwrap_or(entry.path())
+        } else {
+            entry.path()
+        };
+        let path = path.to_string_lossy().replace("\\", "/");
+        if keep(&path) {
+            files.push(path);
+        }
     }
-    false
+    Ok(files)
 }
 
-/// Expand file patterns while skipping specified directories.
+/// Expand file patterns using the default walk options.
 pub fn expand_patterns(patterns: &[String]) -> Result<Vec<String>, ClipboardError> {
-    let mut files = Vec::new();
+    expand_patterns_with_options(patterns, &WalkOptions::default())
+}
 
-    // Define directories to skip
-    // TODO: Make this configurable
-    let skip_dirs = vec![
-        ".git",
-        "node_modules",
-        "target",
-        "dist",
-        "build",
-        "__pycache__",
-        "venv",
-        "obj",
-        "bin",
-    ];
+/// Expand file patterns, honoring ignore files and skipped directories.
+///
+/// Files named explicitly are always kept; directories and globs are walked
+/// and filtered by the ignore rules in `options`.
+pub fn expand_patterns_with_options(
+    patterns: &[String],
+    options: &WalkOptions,
+) -> Result<Vec<String>, ClipboardError> {
+    let mut files = Vec::new();
 
     for pattern in patterns {
         let normalized_pattern = normalize_path(pattern);
         let path = Path::new(&normalized_pattern);
 
-        if path.is_dir() {
-            for entry in walkdir::WalkDir::new(path)
-                .into_iter()
-                .filter_entry(|e| {
-                    let file_name = e.file_name().to_string_lossy();
-                    if e.file_type().is_dir() && skip_dirs.contains(&file_name.as_ref()) {
-                        return false;
-                    }
-                    true
-                })
-                .filter_map(|e| e.ok())
-            {
-                if entry.file_type().is_file() {
-                    files.push(entry.path().to_string_lossy().to_string());
-                }
-            }
+        if path.is_file() {
+            files.push(normalized_pattern);
+        } else if path.is_dir() {
+            files.extend(walk_files(path, options, |_| true)?);
         } else {
-            for entry in glob::glob(&normalized_pattern)
-                .map_err(|e| ClipboardError::IoError(e.to_string()))?
-            {
-                match entry {
-                    Ok(path) => {
-                        if path.is_file() {
-                            if !is_path_skipped(&path, &skip_dirs) {
-                                files.push(path.to_string_lossy().to_string());
-                            }
-                        }
-                    }
-                    Err(e) => warn!("Error processing pattern {}: {:?}", pattern, e),
-                }
+            let glob_pattern = Pattern::new(&normalized_pattern)
+                .map_err(|e| ClipboardError::IoError(e.to_string()))?;
+            let match_options = MatchOptions {
+                require_literal_separator: true,
+                ..MatchOptions::new()
+            };
+            let base = glob_base(&normalized_pattern);
+            if !base.as_os_str().is_empty() && !base.is_dir() {
+                warn!("No files match pattern {}", pattern);
+                continue;
             }
+            files.extend(walk_files(&base, options, |p| {
+                glob_pattern.matches_with(p, match_options)
+            })?);
         }
     }
 
+    let mut seen = HashSet::new();
+    files.retain(|f| seen.insert(f.clone()));
     Ok(files)
 }
 
+/// Whether `path` is named by `pattern`: the file itself, a file under the
+/// directory, or a match of the glob.
+///
+/// Unlike expanding the pattern, this also matches files that no longer exist.
+pub fn pattern_matches(pattern: &str, path: &str) -> Result<bool, ClipboardError> {
+    let normalized_pattern = normalize_path(pattern);
+    if Path::new(path).starts_with(&normalized_pattern) {
+        return Ok(true);
+    }
+    let glob_pattern = Pattern::new(&normalized_pattern)
+        .map_err(|e| ClipboardError::IoError(e.to_string()))?;
+    let match_options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    Ok(glob_pattern.matches_with(path, match_options))
+}
+
 pub async fn read_file_content(file_path: &str) -> Result<String, ClipboardError> {
     async_fs::read_to_string(file_path)
         .await
         .map_err(|err| ClipboardError::IoError(err.to_string()))
 }
 
-pub fn format_content(
-    content: &str,
-    file: &str,
-    no_markdown: bool,
-    line_number: Option<usize>,
-    prefix: &str,
-    filename_format: String,
-    xml: bool,
-) -> Result<String, ClipboardError> {
-    if xml {
-        return format_xml_content(content, file, line_number);
-    }
+pub async fn read_file_bytes(file_path: &str) -> Result<Vec<u8>, ClipboardError> {
+    async_fs::read(file_path)
+        .await
+        .map_err(|err| ClipboardError::IoError(err.to_string()))
+}
 
-    let mut formatted_content = String::new();
-    let file = normalize_path(file);
-    let ext = file.split('.').last().unwrap_or("");
+/// Formatting options shared by every file in a copy.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub no_markdown: bool,
+    pub line_number: Option<usize>,
+    pub prefix: String,
+    pub filename_format: String,
+    pub xml: bool,
+    /// Emit one JSON record per file instead of rendering the template.
+    pub json: bool,
+    /// Reduce supported source files to an outline of their signatures.
+    pub outline: bool,
+    /// Replaces `filename_format` and `xml` when set.
+    pub template: Option<OutputTemplate>,
+    /// Fills in the `{{tokens}}` template variable.
+    pub token_counter: Option<TokenCounter>,
+    /// File on disk being formatted, for the `{{mtime}}` template variable.
+    pub source_path: Option<PathBuf>,
+}
 
-    match filename_format.as_str() {
-        "None" => {}
-        "MarkdownFirstCodeLine" => {
-            if !no_markdown {
-                formatted_content.push_str(&format!("```{}\n", ext));
-            }
-            formatted_content.push_str(&get_filename_comment(ext, &file));
-        }
-        "MarkdownHeading" => {
-            formatted_content.push_str(&format!("### `{}`\n", file));
-            if !no_markdown {
-                formatted_content.push_str(&format!("```{}\n", ext));
-            }
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            no_markdown: false,
+            line_number: None,
+            prefix: "|".to_string(),
+            filename_format: "MarkdownHeading".to_string(),
+            xml: false,
+            json: false,
+            outline: false,
+            template: None,
+            token_counter: None,
+            source_path: None,
         }
-        _ => {
-            if !no_markdown {
-                formatted_content.push_str(&format!("```{}\n", ext));
-            }
+    }
+}
+
+impl FormatOptions {
+    /// The template to format files with.
+    pub fn template(&self) -> Cow<'_, OutputTemplate> {
+        match &self.template {
+            Some(template) => Cow::Borrowed(template),
+            None => Cow::Owned(OutputTemplate::from_format(
+                &self.filename_format,
+                self.xml,
+                !self.no_markdown,
+            )),
         }
     }
 
-    formatted_content.push_str(&get_line_numbered_content(content, line_number, prefix));
+    /// Renders `template` for one file or diff with `content` already formatted.
+    ///
+    /// `vars` are escaped for XML templates; `content` is inserted as it is.
+    fn render_file(
+        &self,
+        template: &str,
+        xml: bool,
+        vars: &[(&str, &str)],
+        raw: &str,
+        content: &str,
+        index: usize,
+    ) -> String {
+        let uses = |name: &str| template.contains(&format!("{{{{{}}}}}", name));
+        let tokens = match &self.token_counter {
+            Some(counter) if uses("tokens") => counter.count(raw).to_string(),
+            _ => String::new(),
+        };
+        let hash = if uses("hash") {
+            format!("{:x}", Sha256::digest(raw.as_bytes()))
+        } else {
+            String::new()
+        };
+        let mtime = if uses("mtime") {
+            let file = vars.iter().find(|(name, _)| *name == "file");
+            let path = match (&self.source_path, file) {
+                (Some(path), _) => path.clone(),
+                (None, Some((_, file))) => PathBuf::from(file),
+                (None, None) => PathBuf::new(),
+            };
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| {
+                    DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Secs, true)
+                })
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let lines = raw.lines().count().to_string();
+        let bytes = raw.len().to_string();
+        let index = index.to_string();
+        let mut all_vars = vec![
+            ("lines", lines.as_str()),
+            ("bytes", bytes.as_str()),
+            ("tokens", tokens.as_str()),
+            ("hash", hash.as_str()),
+            ("mtime", mtime.as_str()),
+            ("index", index.as_str()),
+        ];
+        all_vars.extend_from_slice(vars);
+        let escaped: Vec<(&str, String)> = all_vars
+            .iter()
+            .map(|&(name, value)| {
+                let value = if xml { escape(value) } else { value.to_string() };
+                (name, value)
+            })
+            .collect();
+        let mut all_vars: Vec<(&str, &str)> = escaped
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        all_vars.push(("content", content));
+        render(template, &all_vars)
+    }
 
-    if !no_markdown {
-        formatted_content.push_str("```\n");
+    /// Serializes `content` of `file` as a JSON file record line.
+    fn json_record(
+        &self,
+        path: &str,
+        language: &str,
+        content: &str,
+        start_line: Option<usize>,
+    ) -> String {
+        let record = FileRecord {
+            path: path.to_string(),
+            language: language.to_string(),
+            content: content.to_string(),
+            tokens: self.token_counter.as_ref().map(|counter| counter.count(content)),
+            lines: content.lines().count(),
+            sha256: format!("{:x}", Sha256::digest(content.as_bytes())),
+            start_line,
+        };
+        format!("{}\n", to_line(&Record::File(record)))
     }
+}
 
-    Ok(formatted_content)
+/// Format a region of `file` whose first line is `start_line`.
+///
+/// `label` replaces the path in the heading or XML `path` attribute, e.g. to
+/// record the selected line range.
+pub fn format_snippet(
+    content: &str,
+    file: &str,
+    start_line: usize,
+    label: Option<&str>,
+    options: &FormatOptions,
+) -> Result<String, ClipboardError> {
+    format_snippet_at(content, file, start_line, label, 1, options)
 }
 
-fn format_xml_content(
+/// Like `format_snippet`, with `index` as the `{{index}}` template variable.
+pub fn format_snippet_at(
     content: &str,
     file: &str,
-    line_number: Option<usize>,
+    start_line: usize,
+    label: Option<&str>,
+    index: usize,
+    options: &FormatOptions,
 ) -> Result<String, ClipboardError> {
-    let path = normalize_path(file);
-    let ext = file.split('.').last().unwrap_or("unknown");
-    let file_type = get_file_type(ext)?;
-
-    let mut formatted_content = String::new();
-    formatted_content.push_str(&format!("<file path=\"{path}\" type=\"{file_type}\">\n"));
-
-    if let Some(digits) = line_number {
-        // When line numbers are provided, wrap each line in a <line> tag
-        for (i, line) in content.lines().enumerate() {
-            formatted_content.push_str(&format!(
-                "<line number=\"{:0width$}\">",
-                i + 1,
-                width = digits
-            ));
-            formatted_content.push_str(&line);
-            formatted_content.push_str("</line>\n");
+    let file = normalize_path(file);
+    let label = label.map(normalize_path).unwrap_or_else(|| file.clone());
+
+    // Outlines skip lines, so their elision markers stand in for line numbers
+    let outlined;
+    let (content, line_number) = match Language::from_path(&file) {
+        Some(language) if options.outline => {
+            outlined = outline(content, language);
+            (outlined.as_str(), None)
         }
-    } else {
-        // When line numbers are not provided, output content directly
-        formatted_content.push_str(&content);
-        formatted_content.push('\n');
+        _ => (content, options.line_number),
+    };
+    render_snippet(content, &file, &label, start_line, index, options, |template| {
+        // XML keeps the content intact in CDATA unless its lines are numbered
+        match line_number {
+            None if template.xml => format!("{}\n", cdata(content)),
+            _ => get_line_numbered_content(
+                content,
+                line_number,
+                &options.prefix,
+                start_line,
+                &template.line,
+                template.xml,
+            ),
+        }
+    })
+}
+
+/// Line put between the regions of a file formatted by `format_regions_at`.
+pub const REGION_SEPARATOR: &str = "...";
+
+/// Formats the `regions` of `content` as one snippet, numbering their lines
+/// from where they are in the file and separating them with `...` lines.
+pub fn format_regions_at(
+    content: &str,
+    file: &str,
+    regions: &[LineRange],
+    index: usize,
+    options: &FormatOptions,
+) -> Result<String, ClipboardError> {
+    let file = normalize_path(file);
+    let ranges: Vec<String> = regions.iter().map(|region| region.to_string()).collect();
+    let label = format!("{}:{}", file, ranges.join(","));
+    let excerpts: Vec<String> = regions
+        .iter()
+        .map(|region| region.slice(content).join("\n"))
+        .collect();
+    let excerpt = excerpts.join(&format!("\n{}\n", REGION_SEPARATOR));
+    let start_line = regions.first().map_or(1, |region| region.start);
+    render_snippet(&excerpt, &file, &label, start_line, index, options, |template| {
+        if options.line_number.is_none() && template.xml {
+            return format!("{}\n", cdata(&excerpt));
+        }
+        let numbered: Vec<String> = regions
+            .iter()
+            .zip(&excerpts)
+            .map(|(region, excerpt)| {
+                get_line_numbered_content(
+                    excerpt,
+                    options.line_number,
+                    &options.prefix,
+                    region.start,
+                    &template.line,
+                    template.xml,
+                )
+            })
+            .collect();
+        numbered.join(&format!("{}\n", REGION_SEPARATOR))
+    })
+}
+
+/// Renders a snippet of `file` with the file template of `options`, its lines
+/// formatted by `format_lines`.
+fn render_snippet(
+    content: &str,
+    file: &str,
+    label: &str,
+    start_line: usize,
+    index: usize,
+    options: &FormatOptions,
+    format_lines: impl FnOnce(&OutputTemplate) -> String,
+) -> Result<String, ClipboardError> {
+    let ext = file.split('.').next_back().unwrap_or("");
+    if options.json {
+        let start_line = (start_line != 1 || label != file).then_some(start_line);
+        return Ok(options.json_record(file, get_file_type(ext)?, content, start_line));
     }
 
-    formatted_content.push_str("</file>\n");
+    let template = options.template();
+    let formatted = format_lines(&template);
+    let filename_comment = get_filename_comment(ext, label);
+    Ok(options.render_file(
+        &template.file,
+        template.xml,
+        &[
+            ("path", label),
+            ("file", file),
+            ("language", get_file_type(ext)?),
+            ("extension", ext),
+            ("filename_comment", &filename_comment),
+        ],
+        content,
+        &formatted,
+        index,
+    ))
+}
 
-    Ok(formatted_content)
+/// Format a unified diff for `file` with the diff template of `options`.
+pub fn format_diff_at(diff: &str, file: &str, index: usize, options: &FormatOptions) -> String {
+    let file = normalize_path(file);
+    if options.json {
+        return options.json_record(&file, "diff", diff, None);
+    }
+    let template = options.template();
+    let content = if template.xml {
+        format!("{}\n", cdata(diff))
+    } else if diff.ends_with('\n') {
+        diff.to_string()
+    } else {
+        format!("{}\n", diff)
+    };
+    let filename_comment = get_filename_comment("diff", &file);
+    options.render_file(
+        template.diff.as_ref().unwrap_or(&template.file),
+        template.xml,
+        &[
+            ("path", &file),
+            ("file", &file),
+            ("language", "diff"),
+            ("extension", "diff"),
+            ("filename_comment", &filename_comment),
+        ],
+        diff,
+        &content,
+        index,
+    )
 }
 
-fn get_file_type(ext: &str) -> Result<&'static str, ClipboardError> {
+pub fn get_file_type(ext: &str) -> Result<&'static str, ClipboardError> {
     match ext {
         // Programming languages
         "rs" => Ok("rust"),
@@ -226,6 +513,7 @@ fn get_file_type(ext: &str) -> Result<&'static str, ClipboardError> {
         "tsv" => Ok("tsv"),
         "md" => Ok("markdown"),
         "rst" => Ok("reStructuredText"),
+        "ipynb" => Ok("jupyter"),
 
         // Markup languages
         "tex" => Ok("latex"),
@@ -243,25 +531,123 @@ fn get_file_type(ext: &str) -> Result<&'static str, ClipboardError> {
     }
 }
 
+/// Comment delimiters of a file type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentSyntax {
+    /// Markers of comments running to the end of the line.
+    pub line: &'static [&'static str],
+    /// Opening and closing delimiters of block comments.
+    pub block: Option<(&'static str, &'static str)>,
+    /// Prefixes marking a comment as documentation.
+    pub doc: &'static [&'static str],
+}
+
+const C_COMMENTS: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    doc: &["///", "//!", "/**", "/*!"],
+};
+const HASH_COMMENTS: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: None,
+    doc: &[],
+};
+
+/// Comment syntax of files with extension `ext`, if the file type has comments.
+pub fn comment_syntax(ext: &str) -> Option<CommentSyntax> {
+    let syntax = match get_file_type(ext).ok()? {
+        "rust" | "javascript" | "typescript" | "java" | "c" | "cpp" | "header" | "csharp"
+        | "go" | "swift" | "kotlin" | "scala" | "dart" | "scss" | "sass" | "less" => C_COMMENTS,
+        "php" => CommentSyntax {
+            line: &["//", "#"],
+            ..C_COMMENTS
+        },
+        "fsharp" => CommentSyntax {
+            block: Some(("(*", "*)")),
+            doc: &["///"],
+            ..C_COMMENTS
+        },
+        "python" | "ruby" | "shell" | "bash" | "zsh" | "toml" | "yaml" | "r" | "conf"
+        | "dockerfile" => HASH_COMMENTS,
+        "powershell" => CommentSyntax {
+            block: Some(("<#", "#>")),
+            ..HASH_COMMENTS
+        },
+        "ini" => CommentSyntax {
+            line: &[";", "#"],
+            ..HASH_COMMENTS
+        },
+        "lua" => CommentSyntax {
+            line: &["--"],
+            block: Some(("--[[", "]]")),
+            doc: &["---"],
+        },
+        "sql" => CommentSyntax {
+            line: &["--"],
+            block: Some(("/*", "*/")),
+            doc: &[],
+        },
+        "html" | "xml" | "xhtml" => CommentSyntax {
+            line: &[],
+            block: Some(("<!--", "-->")),
+            doc: &[],
+        },
+        "css" => CommentSyntax {
+            line: &[],
+            block: Some(("/*", "*/")),
+            doc: &[],
+        },
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// The comment naming `filename` at the top of a snippet.
+///
+/// Only `//`, `#`, `/* */` and `<!-- -->` comments are written, since those
+/// are the ones the extractor reads back.
 fn get_filename_comment(ext: &str, filename: &str) -> String {
-    match ext {
-        "rs" | "js" | "ts" | "tsx" | "java" | "c" | "cpp" | "h" | "cs" | "fs" | "json" => {
-            format!("// filename: {}\n", filename)
+    match comment_syntax(ext) {
+        Some(CommentSyntax { line: [marker, ..], .. }) if matches!(*marker, "//" | "#") => {
+            format!("{} filename: {}\n", marker, filename)
         }
-        "py" | "toml" | "sh" | "yml" | "yaml" => format!("# filename: {}\n", filename),
-        "html" | "xml" => format!("<!-- filename: {} -->\n", filename),
-        "css" => format!("/* filename: {} */\n", filename),
+        Some(CommentSyntax {
+            line: [],
+            block: Some((open, close)),
+            ..
+        }) => format!("{} filename: {} {}\n", open, filename, close),
         _ => format!("// filename: {}\n", filename),
     }
 }
 
-fn get_line_numbered_content(content: &str, line_number: Option<usize>, prefix: &str) -> String {
+/// Renders each line of `content` with `line_template` when line numbers are on.
+///
+/// Lines are escaped for XML templates.
+fn get_line_numbered_content(
+    content: &str,
+    line_number: Option<usize>,
+    prefix: &str,
+    start_line: usize,
+    line_template: &str,
+    xml: bool,
+) -> String {
     let mut numbered_content = String::new();
     for (i, line) in content.lines().enumerate() {
-        if let Some(digits) = line_number {
-            numbered_content.push_str(&format!("{:0width$}{}", i + 1, prefix, width = digits));
+        match line_number {
+            Some(digits) => {
+                let number = format!("{:0width$}", start_line + i, width = digits);
+                let line = if xml {
+                    Cow::Owned(escape(line))
+                } else {
+                    Cow::Borrowed(line)
+                };
+                numbered_content.push_str(&render(
+                    line_template,
+                    &[("number", &number), ("prefix", prefix), ("line", &line)],
+                ));
+            }
+            None => numbered_content.push_str(line),
         }
-        numbered_content.push_str(line);
         numbered_content.push('\n');
     }
     numbered_content
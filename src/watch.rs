@@ -1,8 +1,12 @@
-use crate::applier::{Applier, DiffApplier, FullContentApplier, SearchReplaceApplier};
+use crate::applier::{
+    Applier, DiffApplier, FullContentApplier, NotebookCellApplier, SearchReplaceApplier,
+};
 use crate::errors::ClipboardError;
 use crate::extractor::Extractor;
-use crate::ignore::{DEFAULT_IGNORE_PATTERNS, IgnorePatterns};
-use crate::llm::{LLMClient, TokenUsage, MODEL_PRICING};
+use crate::ignore::{DEFAULT_IGNORE_PATTERNS, IgnorePatterns, WalkOptions};
+use crate::llm::{LLMClient, TokenUsage};
+use crate::models::ModelRegistry;
+use crate::notebook::parse_cell_address;
 use crate::applier::utils::print_diff;
 use arboard::Clipboard;
 use std::path::PathBuf;
@@ -12,7 +16,6 @@ use tracing::{debug, error, info, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::collections::VecDeque;
-use walkdir::WalkDir;
 use std::time::Instant;
 use std::collections::HashMap;
 use futures::future::join_all;
@@ -51,6 +54,8 @@ pub struct WatcherConfig {
     pub store_enabled: bool,
     pub metadata: HashMap<String, String>,
     pub one_shot: bool,
+    /// Models to price the AI usage with.
+    pub registry: ModelRegistry,
 }
 
 impl Default for WatcherConfig {
@@ -70,6 +75,7 @@ impl Default for WatcherConfig {
                 m
             },
             one_shot: false,
+            registry: ModelRegistry::global().clone(),
         }
     }
 }
@@ -90,12 +96,14 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                 config.model.clone(),
                 config.store_enabled,
                 config.metadata.clone()
-            ),
+            )
+            .with_registry(config.registry.clone()),
             config: config.clone(), 
             extractor,
             modified_files: VecDeque::with_capacity(MAX_HISTORY_SIZE),
             total_token_usage: TokenUsage::default(),
-            ignore_patterns: IgnorePatterns::new(Some(config.ignore_patterns)),
+            ignore_patterns: IgnorePatterns::new(Some(config.ignore_patterns))
+                .with_ignore_files(&config.watch_path),
         }
     }
 
@@ -118,20 +126,18 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
 
     fn get_directory_tree(&self) -> Result<String, ClipboardError> {
         let mut tree = String::new();
-        for entry in WalkDir::new(&self.config.watch_path)
-            .min_depth(1)
-            .max_depth(3)
-            .into_iter()
-            .filter_entry(|e| {
-                !e.file_name().to_str().map_or(false, |s| s.starts_with('.')) && 
-                !e.path().strip_prefix(&self.config.watch_path)
-                    .ok()
-                    .and_then(|p| p.to_str())
-                    .map_or(false, |p| self.should_ignore(p))
-            }) {
+        let mut walker = WalkOptions::default().walker(&self.config.watch_path);
+        walker.hidden(true).max_depth(Some(3));
+        for entry in walker.build() {
                 match entry {
                     Ok(entry) => {
+                        if entry.depth() == 0 {
+                            continue;
+                        }
                         let path = entry.path().strip_prefix(&self.config.watch_path).unwrap_or(entry.path());
+                        if path.to_str().is_some_and(|p| self.should_ignore(p)) {
+                            continue;
+                        }
                         let depth = entry.depth();
                         let prefix = "  ".repeat(depth);
                         tree.push_str(&format!("{}{}\n", prefix, path.display()));
@@ -186,7 +192,7 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
         info!("Analyzing content with AI");
         match self.llm_client.call_with_json_response::<AIResponse>(&check_prompt).await {
             Ok((parsed_response, usage, analysis_time)) => {
-                info!("Analysis token usage: {} (response time: {:?})", usage.format_details(&self.config.model), analysis_time);
+                info!("Analysis token usage: {} (response time: {:?})", usage.format_details(&self.config.registry, &self.config.model), analysis_time);
                 self.update_token_usage(usage);
 
                 if !parsed_response.contains_code {
@@ -222,12 +228,14 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                 let predictions_enabled = self.config.predictions_enabled;
                 let store_enabled = self.config.store_enabled;
                 let metadata = self.config.metadata.clone();
+                let registry = self.config.registry.clone();
 
                 for (i, file_path) in files.into_iter().enumerate() {
                     let content = content.clone();
                     let watch_path = watch_path.clone();
                     let model = model.clone();
                     let metadata = metadata.clone();
+                    let registry = registry.clone();
 
                     futures.push(tokio::spawn(async move {
                         let file_start_time = Instant::now();
@@ -298,11 +306,12 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                             None
                         };
 
-                        let llm_client = LLMClient::new(model.clone(), store_enabled, metadata);
+                        let llm_client = LLMClient::new(model.clone(), store_enabled, metadata)
+                            .with_registry(registry.clone());
                         let response = llm_client.call(&update_prompt, prediction).await?;
 
                         info!("Generation token usage: {} (response time: {:?})", 
-                            response.usage.format_details(&model), 
+                            response.usage.format_details(&registry, &model), 
                             response.response_time
                         );
 
@@ -378,7 +387,7 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                         stats.total_time,
                         stats.llm_response_time,
                         stats.io_time,
-                        stats.token_usage.format_details(&self.config.model)
+                        stats.token_usage.format_details(&self.config.registry, &self.config.model)
                     );
                 }
 
@@ -394,12 +403,11 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
 
     pub async fn run(&mut self) -> Result<(), ClipboardError> {
         // Check for OpenAI API key if AI is enabled
-        if self.config.ai_enabled {
-            if std::env::var("OPENAI_API_KEY").is_err() {
+        if self.config.ai_enabled
+            && std::env::var("OPENAI_API_KEY").is_err() {
                 error!("OpenAI API key not found in environment. Please set OPENAI_API_KEY environment variable.");
                 return Err(ClipboardError::ConfigError("OPENAI_API_KEY environment variable not set".to_string()));
             }
-        }
 
         let mut clipboard = Clipboard::new()
             .map_err(|e| ClipboardError::ClipboardInitError(e.to_string()))?;
@@ -425,14 +433,14 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
         info!("AI processing is {}", if self.config.ai_enabled { "enabled" } else { "disabled" });
         
         if self.config.ai_enabled {
+            let pricing = self.config.registry
+                .get(&self.config.model)
+                .and_then(|info| info.pricing);
             info!("Using OpenAI model: {} (input=${:.3}/1M, cached=${:.3}/1M, output=${:.3}/1M)", 
                 self.config.model,
-                MODEL_PRICING.get(self.config.model.as_str())
-                    .map_or(0.0, |p| p.input_price),
-                MODEL_PRICING.get(self.config.model.as_str())
-                    .map_or(0.0, |p| p.cached_price),
-                MODEL_PRICING.get(self.config.model.as_str())
-                    .map_or(0.0, |p| p.output_price)
+                pricing.map_or(0.0, |p| p.input_price),
+                pricing.map_or(0.0, |p| p.cached_price),
+                pricing.map_or(0.0, |p| p.output_price)
             );
             info!("Predictions are {}", if self.config.predictions_enabled { "enabled" } else { "disabled" });
             if self.config.store_enabled {
@@ -452,7 +460,7 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
             info!("One-shot processing completed in {:?}", start_time.elapsed());
             if self.config.ai_enabled {
                 info!("Total token usage: {}", 
-                    self.total_token_usage.format_details(&self.config.model)
+                    self.total_token_usage.format_details(&self.config.registry, &self.config.model)
                 );
             }
             return Ok(());
@@ -504,7 +512,7 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                     info!("Received Ctrl+C, terminating clipboard watcher.");
                     info!("Total runtime: {:?}", start_time.elapsed());
                     info!("Total token usage: {}", 
-                        self.total_token_usage.format_details(&self.config.model)
+                        self.total_token_usage.format_details(&self.config.registry, &self.config.model)
                     );
                     break;
                 }
@@ -523,6 +531,11 @@ impl<E: Extractor + Send + Sync> ClipboardWatcher<E> {
                 for block in blocks {
                     debug!("Applying block: {:?}", block);
                     let applier: Box<dyn Applier> = match block.block_type {
+                        crate::extractor::BlockType::FullContent
+                            if parse_cell_address(&block.filename).is_some() =>
+                        {
+                            Box::new(NotebookCellApplier::new(&self.config.watch_path))
+                        }
                         crate::extractor::BlockType::FullContent => {
                             Box::new(FullContentApplier::new(&self.config.watch_path))
                         }
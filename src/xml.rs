@@ -0,0 +1,108 @@
+//! Escaping for snippy's XML output.
+//!
+//! Files are written as
+//!
+//! ```xml
+//! <files>
+//! <file path="src/a.rs" type="rust" lines="3">
+//! <![CDATA[fn a() {
+//!     1
+//! }
+//! ]]>
+//! </file>
+//! </files>
+//! ```
+//!
+//! Attribute values are entity-escaped and content is wrapped in a CDATA
+//! section, so the text inside the section is the file byte for byte. A
+//! `]]>` in the content ends one section and starts another. With line
+//! numbers, each line is a `<line number="..">` element with escaped text
+//! instead. Diffs use `<diff path="..">` elements.
+
+use serde::{Deserialize, Serialize};
+
+/// Optional metadata attributes of `<file>` elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum XmlAttribute {
+    /// Number of lines.
+    Lines,
+    /// Size of the content in bytes.
+    Bytes,
+    /// Token count for the selected model.
+    Tokens,
+    /// SHA-256 of the content.
+    Hash,
+    /// Last modification time of the file, in RFC 3339 format.
+    Mtime,
+}
+
+impl XmlAttribute {
+    /// Attribute name and the template variable that fills it.
+    pub fn attribute(&self) -> (&'static str, &'static str) {
+        match self {
+            XmlAttribute::Lines => ("lines", "lines"),
+            XmlAttribute::Bytes => ("bytes", "bytes"),
+            XmlAttribute::Tokens => ("tokens", "tokens"),
+            XmlAttribute::Hash => ("sha256", "hash"),
+            XmlAttribute::Mtime => ("mtime", "mtime"),
+        }
+    }
+}
+
+/// Escapes `text` for use in attribute values and element text.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses `escape`, along with numeric character references.
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let entity = after.find(';').map(|end| &after[1..end]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        });
+        match (decoded, entity) {
+            (Some(c), Some(entity)) => {
+                unescaped.push(c);
+                rest = &after[entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &after[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Wraps `text` in CDATA sections, splitting it around any `]]>`.
+pub fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
@@ -91,9 +91,8 @@ async fn test_large_diff_apply_performance() {
 
     // Generate the diff content with correct hunk headers
     let mut diff_content = String::new();
-    let mut current_line = 1; // Start from line 1
-
-    for i in 0..count {
+    // Hunk line numbers start from line 1
+    for (current_line, i) in (1..).zip(0..count) {
         let old_line = format!("fn main() {{ println!(\"Hello, {}!\"); }}\n", i);
         let new_line = format!("fn main() {{ println!(\"Hello, updated {}!\"); }}\n", i);
 
@@ -102,7 +101,6 @@ async fn test_large_diff_apply_performance() {
         diff_content.push_str(&hunk_header);
         diff_content.push_str(&format!("-{}", old_line));
         diff_content.push_str(&format!("+{}", new_line));
-        current_line += 1;
     }
 
     let block = ParsedBlock {
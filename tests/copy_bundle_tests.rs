@@ -0,0 +1,163 @@
+use snippy::bundle::{project_root, root_relative_pattern, Bundle, BundleStore, BUNDLES_DIR};
+use snippy::copy::{copy_files_from, ClipboardCopierConfig};
+use snippy::copy_files_to_clipboard;
+use snippy::json::OutputFormat;
+use snippy::sink::SinkKind;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn test_bundle_store_round_trip() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+    assert_eq!(project_root(&dir.path().join("src/nested")), dir.path());
+
+    let store = BundleStore::for_project(&dir.path().join("src"));
+    assert!(store.list().unwrap().is_empty());
+
+    let config = ClipboardCopierConfig {
+        line_number: Some(3),
+        ignore_patterns: Some(vec!["**/*_test.rs".to_string()]),
+        format: OutputFormat::Jsonl,
+        ..Default::default()
+    };
+    let bundle = Bundle::from_config(vec!["src/watch.rs".to_string()], &config);
+    let path = store.save("watch", &bundle).unwrap();
+    assert_eq!(path, dir.path().join(BUNDLES_DIR).join("watch.json"));
+    store.save("applier", &Bundle::default()).unwrap();
+
+    assert_eq!(store.list().unwrap(), vec!["applier", "watch"]);
+    assert_eq!(store.load("watch").unwrap(), bundle);
+    assert!(store.save("../escape", &bundle).is_err());
+
+    store.remove("watch").unwrap();
+    assert_eq!(store.list().unwrap(), vec!["applier"]);
+    let err = store.load("watch").unwrap_err();
+    assert!(
+        err.to_string().contains("applier"),
+        "Unexpected error: {}",
+        err
+    );
+}
+
+#[test]
+fn test_bundle_files_fill_in_defaults() {
+    let dir = tempdir().unwrap();
+    let store = BundleStore::new(dir.path().to_path_buf());
+    fs::write(
+        dir.path().join("old.json"),
+        r#"{"patterns": ["src"], "outline": true}"#,
+    )
+    .unwrap();
+
+    let bundle = store.load("old").unwrap();
+    assert_eq!(bundle.patterns, vec!["src"]);
+    let config = bundle.apply(ClipboardCopierConfig {
+        no_stats: true,
+        ..Default::default()
+    });
+    assert!(config.outline);
+    assert!(config.no_stats);
+    assert_eq!(config.model, "gpt-4o");
+}
+
+#[test]
+fn test_root_relative_pattern() {
+    let root = Path::new("/repo");
+    let cwd = Path::new("/repo/src");
+    assert_eq!(root_relative_pattern("*.rs", cwd, root), "src/*.rs");
+    assert_eq!(
+        root_relative_pattern("./a.rs:1-5", cwd, root),
+        "src/a.rs:1-5"
+    );
+    assert_eq!(root_relative_pattern(".", cwd, root), "src");
+    assert_eq!(root_relative_pattern("*.rs", root, root), "*.rs");
+    assert_eq!(root_relative_pattern("/abs/a.rs", cwd, root), "/abs/a.rs");
+    assert_eq!(
+        root_relative_pattern("https://github.com/owner/repo.git", cwd, root),
+        "https://github.com/owner/repo.git"
+    );
+}
+
+#[tokio::test]
+async fn test_copy_bundle() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("b.rs"), "fn b() {}\n").unwrap();
+    let output_dir = tempdir().unwrap();
+    let output = output_dir.path().join("bundle.md");
+
+    let bundle = Bundle {
+        line_number: Some(2),
+        ..Bundle::from_config(
+            vec![root.join("a.rs").to_string_lossy().into_owned()],
+            &ClipboardCopierConfig::default(),
+        )
+    };
+    let config = bundle.apply(ClipboardCopierConfig {
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    });
+    copy_files_to_clipboard(config, bundle.patterns.clone())
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("01|fn a() {}"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        !written.contains("fn b()"),
+        "Unexpected output: {}",
+        written
+    );
+}
+
+#[tokio::test]
+async fn test_copy_bundle_relative_to_root() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("b.rs"), "fn b() {}\n").unwrap();
+    let output_dir = tempdir().unwrap();
+    let output = output_dir.path().join("bundle.md");
+
+    let bundle = Bundle {
+        redact_rules: vec![r"name=fn (?P<secret>a)".to_string()],
+        ..Bundle::from_config(vec!["src".to_string()], &ClipboardCopierConfig::default())
+    };
+    assert_eq!(
+        bundle.resolve_patterns(root),
+        vec![root.join("src").to_string_lossy().into_owned()]
+    );
+    let config = bundle.apply(ClipboardCopierConfig {
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    });
+    copy_files_from(root, config, bundle.resolve_patterns(root))
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("src/a.rs") && !written.contains(&*root.to_string_lossy()),
+        "Unexpected output: {}",
+        written
+    );
+    // Stored redaction rules are applied
+    assert!(
+        written.contains("fn <REDACTED:name_1>") && !written.contains("fn b()"),
+        "Unexpected output: {}",
+        written
+    );
+}
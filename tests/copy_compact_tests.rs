@@ -0,0 +1,247 @@
+use snippy::compact::{CompactOptions, Compactor};
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::reporting::{stats_report, FileStats, StatsOptions};
+use snippy::sink::SinkKind;
+use snippy::utils::comment_syntax;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+const RUST_SOURCE: &str = r##"//! Crate docs.
+
+/// Adds numbers.
+pub fn add(a: i32, b: i32) -> i32 {
+    // sum them
+    let url = "http://example.com"; /* inline */ let slash = '/';
+    let raw = r#"// not a comment"#;
+
+
+    a + b // trailing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds() {
+        assert_eq!(add(1, 2), 3);
+    }
+}
+"##;
+
+#[test]
+fn test_compact_rust() {
+    let mut compactor = Compactor::new(CompactOptions::all());
+    assert_eq!(
+        compactor.compact("src/lib.rs", RUST_SOURCE),
+        r##"pub fn add(a: i32, b: i32) -> i32 {
+    let url = "http://example.com"; let slash = '/';
+    let raw = r#"// not a comment"#;
+
+    a + b
+}
+"##
+    );
+
+    let mut compactor = Compactor::new(CompactOptions {
+        keep_doc_comments: true,
+        strip_tests: false,
+        ..CompactOptions::all()
+    });
+    let compacted = compactor.compact("src/lib.rs", RUST_SOURCE);
+    assert!(compacted.starts_with("//! Crate docs.\n\n/// Adds numbers.\n"));
+    assert!(compacted.contains("#[cfg(test)]\nmod tests {"));
+    assert!(!compacted.contains("// sum them"));
+}
+
+#[test]
+fn test_compact_python_and_typescript() {
+    let python = r##"#!/usr/bin/env python
+"""Module docs."""
+import os  # os
+
+
+def f(x):
+    """Docstring."""
+    s = "# not a comment"
+    return x
+
+
+@pytest.mark.slow
+def test_f():
+    assert f(1) == 1
+
+
+class TestF:
+    def test_g(self):
+        pass
+"##;
+    let mut compactor = Compactor::new(CompactOptions::all());
+    assert_eq!(
+        compactor.compact("test_app.py", python),
+        "#!/usr/bin/env python\nimport os\n\ndef f(x):\n    s = \"# not a comment\"\n    return x\n"
+    );
+
+    // Only names following the pytest conventions are tests, and only in test
+    // modules; unittest test cases are tests anywhere
+    let lookalikes = r#"class Testament:
+    pass
+
+
+def testimonial():
+    pass
+
+
+def test_h():
+    pass
+
+
+class CheckF(unittest.TestCase):
+    def test_i(self):
+        pass
+"#;
+    let mut compactor = Compactor::new(CompactOptions {
+        strip_tests: true,
+        ..CompactOptions::default()
+    });
+    let compacted = compactor.compact("test_app.py", lookalikes);
+    assert!(
+        compacted.contains("class Testament:") && compacted.contains("def testimonial():"),
+        "Unexpected output: {}",
+        compacted
+    );
+    assert!(
+        !compacted.contains("test_h") && !compacted.contains("CheckF"),
+        "Unexpected output: {}",
+        compacted
+    );
+    let compacted = compactor.compact("app.py", lookalikes);
+    assert!(
+        compacted.contains("def test_h():") && !compacted.contains("CheckF"),
+        "Unexpected output: {}",
+        compacted
+    );
+
+    let typescript = r#"/**
+ * Greets.
+ */
+export function greet(name: string): string {
+  // say hi
+  return `hi // ${name}`; // done
+}
+
+describe("greet", () => {
+  it("greets", () => {
+    expect(greet("a")).toBe("hi // a");
+  });
+});
+"#;
+    let mut compactor = Compactor::new(CompactOptions {
+        keep_doc_comments: true,
+        ..CompactOptions::all()
+    });
+    assert_eq!(
+        compactor.compact("greet.ts", typescript),
+        "/**\n * Greets.\n */\nexport function greet(name: string): string {\n  return `hi // ${name}`;\n}\n"
+    );
+}
+
+#[test]
+fn test_compact_license_headers_and_blank_lines() {
+    let mut compactor = Compactor::new(CompactOptions {
+        strip_license_headers: true,
+        ..CompactOptions::default()
+    });
+    let first = "// Copyright 2023 Acme Inc.\n// Licensed under the MIT license.\n\nfn a() {}\n";
+    let second = "// Copyright 2024 Acme Inc.\n// Licensed under the MIT license.\n\nfn b() {}\n";
+    let other = "# Copyright 2024 Other Corp.\n\nimport os\n";
+    assert_eq!(compactor.compact("a.rs", first), first);
+    assert_eq!(compactor.compact("b.rs", second), "fn b() {}\n");
+    assert_eq!(compactor.compact("c.py", other), other);
+    // Files without comment syntax are left alone
+    assert_eq!(compactor.compact("README.md", second), second);
+
+    let mut compactor = Compactor::new(CompactOptions {
+        collapse_blank_lines: true,
+        ..CompactOptions::default()
+    });
+    assert_eq!(
+        compactor.compact("notes.md", "\n\n# Notes\n\n  \n\nText\n\n"),
+        "# Notes\n\nText\n"
+    );
+}
+
+#[test]
+fn test_comment_syntax() {
+    assert_eq!(comment_syntax("rs").unwrap().line, &["//"]);
+    assert_eq!(comment_syntax("yml").unwrap().line, &["#"]);
+    assert_eq!(comment_syntax("lua").unwrap().block, Some(("--[[", "]]")));
+    assert_eq!(comment_syntax("html").unwrap().block, Some(("<!--", "-->")));
+    assert_eq!(comment_syntax("json"), None);
+    assert_eq!(comment_syntax("md"), None);
+}
+
+#[test]
+fn test_stats_report_compaction_savings() {
+    let stats = |tokens, compacted_tokens| FileStats {
+        tokens,
+        compacted_tokens,
+        ..FileStats::default()
+    };
+    let file_stats = HashMap::from([
+        (PathBuf::from("src/a.rs"), stats(300, 100)),
+        (PathBuf::from("README.md"), stats(100, 0)),
+    ]);
+    let report = stats_report(&file_stats, &StatsOptions::default()).unwrap();
+    assert_eq!(report.total_tokens, 400);
+    assert_eq!(report.compacted_tokens, 100);
+    assert_eq!(report.files[1].path, "src/a.rs");
+    assert_eq!(report.files[1].compacted_tokens, 100);
+}
+
+#[tokio::test]
+async fn test_copy_compacts_whole_files_only() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let source = "fn a() {\n    // one\n    1\n}\n// two\nfn b() {}\n";
+    fs::write(root.join("a.rs"), source).unwrap();
+    let output = root.join("out.md");
+
+    let copy = |pattern: String| {
+        let config = ClipboardCopierConfig {
+            compact: CompactOptions {
+                strip_comments: true,
+                ..CompactOptions::default()
+            },
+            no_markdown: true,
+            sink: SinkKind::File,
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        copy_files_to_clipboard(config, vec![pattern])
+    };
+    let file = root.join("a.rs").to_string_lossy().into_owned();
+    copy(file.clone())
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("fn a() {\n    1\n}\nfn b() {}"),
+        "Unexpected output: {}",
+        written
+    );
+
+    // Selected line ranges keep the file as it is
+    copy(format!("{}:4-6", file))
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("}\n// two\nfn b() {}"),
+        "Unexpected output: {}",
+        written
+    );
+}
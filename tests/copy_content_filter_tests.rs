@@ -0,0 +1,153 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::filter::{is_binary, is_generated, is_minified, ContentFilter, SkipReason};
+use snippy::sink::SinkKind;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_content_sniffing() {
+    assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0"));
+    assert!(!is_binary(b"fn main() {}\n"));
+    assert!(is_binary(b"\x7fELF\x02\x01\x01\x03\x04\x05\x06\x07"));
+    // Latin-1 text is not valid UTF-8 but is still text
+    assert!(!is_binary(b"caf\xe9 cr\xe8me br\xfbl\xe9e\n"));
+
+    let bundle = "var a=1,b=2;function c(){return a+b}".repeat(100);
+    assert!(is_minified(&bundle));
+    let source = "fn main() {\n    println!(\"hi\");\n}\n".repeat(100);
+    assert!(!is_minified(&source));
+
+    assert!(is_generated(
+        "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n"
+    ));
+    assert!(is_generated("# @generated by tool\nx = 1\n"));
+    assert!(!is_generated("fn main() {}\n"));
+}
+
+#[test]
+fn test_content_filter_reasons() {
+    let mut filter = ContentFilter::new(true, Some(64));
+
+    assert_eq!(
+        filter.check("a.rs", b"fn a() {}\n", false),
+        Ok("fn a() {}\n".into())
+    );
+    assert_eq!(
+        filter.check("b.rs", b"fn a() {}\n", false),
+        Err(SkipReason::Duplicate("a.rs".to_string()))
+    );
+    assert_eq!(
+        filter.check("img.jpg", b"\xff\xd8\xff\xe0\0\x10JFIF\0", true),
+        Err(SkipReason::Binary)
+    );
+    assert_eq!(
+        filter.check("gen.go", b"// DO NOT EDIT\npackage x\n", false),
+        Err(SkipReason::Generated)
+    );
+
+    let large = "x".repeat(100);
+    assert_eq!(
+        filter.check("large.txt", large.as_bytes(), false),
+        Err(SkipReason::TooLarge {
+            bytes: 100,
+            limit: 64
+        })
+    );
+    // Explicitly named files are only checked for binary and duplicate content
+    assert_eq!(
+        filter.check("large.txt", large.as_bytes(), true),
+        Ok(large.as_str().into())
+    );
+    // Text in legacy encodings is decoded lossily
+    assert_eq!(
+        filter.check("latin1.txt", b"caf\xe9\n", false),
+        Ok("caf\u{fffd}\n".into())
+    );
+
+    let mut disabled = ContentFilter::new(false, None);
+    assert!(disabled.check("gen.go", b"// DO NOT EDIT\n", false).is_ok());
+    assert!(disabled
+        .check("copy.go", b"// DO NOT EDIT\n", false)
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_copy_skips_filtered_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("lib.rs"), "pub fn answer() -> u32 { 42 }\n").unwrap();
+    fs::write(root.join("app.min.js"), "var a=1;".repeat(500)).unwrap();
+    fs::write(root.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+    fs::write(root.join("big.rs"), "pub fn big() {}\n".repeat(200)).unwrap();
+    let output = dir.path().join("context.md");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        max_file_tokens: Some(100),
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("pub fn answer()"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        !written.contains("var a=1"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("PNG"), "Unexpected output: {}", written);
+    // Binary files are stubbed out with a single line
+    assert!(
+        written.contains("logo.png") && written.contains("(binary file, 10 bytes, not copied)"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        !written.contains("pub fn big()"),
+        "Unexpected output: {}",
+        written
+    );
+}
+
+#[tokio::test]
+async fn test_copy_skips_oversize_files_before_reading() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("small.txt"), "small\n").unwrap();
+    fs::write(root.join("large.txt"), "large\n".repeat(100)).unwrap();
+    let output = dir.path().join("context.md");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        max_file_bytes: Some(100),
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains("small"), "Unexpected output: {}", written);
+    assert!(!written.contains("large"), "Unexpected output: {}", written);
+
+    let filter = ContentFilter::new(true, Some(100));
+    assert_eq!(
+        filter.check_size(101, false),
+        Err(SkipReason::TooLarge {
+            bytes: 101,
+            limit: 100
+        })
+    );
+    assert_eq!(filter.check_size(101, true), Ok(()));
+}
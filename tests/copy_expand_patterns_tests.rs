@@ -0,0 +1,129 @@
+use snippy::ignore::{IgnorePatterns, WalkOptions};
+use snippy::utils::{expand_patterns, expand_patterns_with_options};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn write(root: &Path, relative: &str, content: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn relative_names(root: &Path, files: Vec<String>) -> Vec<String> {
+    let mut names: Vec<String> = files
+        .iter()
+        .map(|f| {
+            Path::new(f)
+                .strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_expand_patterns_honors_nested_gitignore_and_negation() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(root, ".gitignore", "*.log\ngenerated/\n");
+    write(root, "src/main.rs", "fn main() {}");
+    write(root, "src/debug.log", "noise");
+    write(root, "src/generated/out.rs", "// generated");
+    write(root, "src/nested/.gitignore", "*.rs\n!keep.rs\n");
+    write(root, "src/nested/drop.rs", "");
+    write(root, "src/nested/keep.rs", "");
+
+    let files = expand_patterns(&[root.to_string_lossy().into_owned()]).unwrap();
+
+    assert_eq!(
+        relative_names(root, files),
+        vec![".gitignore", "src/main.rs", "src/nested/.gitignore", "src/nested/keep.rs"]
+    );
+}
+
+#[test]
+fn test_ignore_patterns_read_nested_ignore_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(root, ".gitignore", "*.log\n");
+    write(root, "src/nested/.gitignore", "*.rs\n!keep.rs\n");
+    write(root, "src/nested/deeper/.gitignore", "!*.log\n");
+
+    let ignore = IgnorePatterns::new(Some(Vec::new())).with_ignore_files(root);
+    assert!(ignore.should_ignore("debug.log"));
+    assert!(ignore.should_ignore("src/nested/drop.rs"));
+    assert!(!ignore.should_ignore("src/nested/keep.rs"));
+    assert!(!ignore.should_ignore("src/main.rs"));
+    assert!(!ignore.should_ignore("src/nested/deeper/trace.log"));
+}
+
+#[test]
+fn test_expand_patterns_honors_snippyignore_and_anchoring() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(root, ".snippyignore", "/docs\nfixtures/\n");
+    write(root, "docs/guide.md", "");
+    write(root, "src/docs/api.md", "");
+    write(root, "tests/fixtures/big.json", "");
+    write(root, "tests/it.rs", "");
+
+    let pattern = format!("{}/**/*.*", root.to_string_lossy());
+    let files = expand_patterns(&[pattern]).unwrap();
+
+    assert_eq!(
+        relative_names(root, files),
+        vec![".snippyignore", "src/docs/api.md", "tests/it.rs"]
+    );
+}
+
+#[test]
+fn test_expand_patterns_keeps_explicit_files_and_can_skip_ignore_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(root, ".ignore", "secret.txt\n");
+    write(root, "secret.txt", "");
+
+    let explicit = vec![root.join("secret.txt").to_string_lossy().into_owned()];
+    assert_eq!(expand_patterns(&explicit).unwrap(), explicit);
+
+    let options = WalkOptions {
+        respect_ignore_files: false,
+        ..WalkOptions::default()
+    };
+    let files =
+        expand_patterns_with_options(&[root.to_string_lossy().into_owned()], &options).unwrap();
+    assert_eq!(relative_names(root, files), vec![".ignore", "secret.txt"]);
+}
+
+#[test]
+fn test_expand_patterns_tracked_only() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(root, "tracked.rs", "");
+    write(root, "untracked.rs", "");
+
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .status()
+            .expect("Failed to run git");
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["init", "-q", "."]);
+    git(&["add", "tracked.rs"]);
+
+    let options = WalkOptions {
+        tracked_only: true,
+        ..WalkOptions::default()
+    };
+    let files =
+        expand_patterns_with_options(&[root.to_string_lossy().into_owned()], &options).unwrap();
+
+    assert_eq!(relative_names(root, files), vec!["tracked.rs"]);
+}
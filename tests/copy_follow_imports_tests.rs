@@ -0,0 +1,199 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::imports::{follow_imports, local_imports, ImportedFile};
+use snippy::sink::SinkKind;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write(root: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+fn path(root: &Path, file: &str) -> String {
+    root.join(file).to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_rust_imports() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        &[
+            ("Cargo.toml", "[package]\nname = \"my-app\"\n"),
+            ("src/config.rs", "use super::net::Client;\n"),
+            ("src/net/mod.rs", "pub mod client;\n"),
+            (
+                "src/net/client.rs",
+                "use self::retry::Policy;\nmod retry;\n",
+            ),
+            ("src/net/client/retry.rs", "pub struct Policy;\n"),
+            ("src/util.rs", ""),
+        ],
+    );
+    let main = "mod config;\nuse crate::net::{\n    client::{Client as C, self},\n    *,\n};\nuse my_app::util::helper;\nuse std::fs;\n";
+    assert_eq!(
+        local_imports(&path(root, "src/main.rs"), main),
+        vec![
+            path(root, "src/config.rs"),
+            path(root, "src/net/client.rs"),
+            path(root, "src/net/mod.rs"),
+            path(root, "src/util.rs"),
+        ]
+    );
+    assert_eq!(
+        local_imports(
+            &path(root, "src/net/client.rs"),
+            "use self::retry::Policy;\nmod retry;\n"
+        ),
+        vec![path(root, "src/net/client/retry.rs")]
+    );
+    assert_eq!(
+        local_imports(&path(root, "src/config.rs"), "use super::net::Client;\n"),
+        vec![path(root, "src/net/mod.rs")]
+    );
+}
+
+#[test]
+fn test_python_imports() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        &[
+            ("app/__init__.py", ""),
+            ("app/models.py", ""),
+            ("app/services/__init__.py", ""),
+            ("app/services/billing.py", ""),
+            ("app/services/mail.py", ""),
+            ("helpers.py", ""),
+        ],
+    );
+    let source = "import os, app.models as m\nfrom . import models\nfrom .services import (\n    billing,\n    mail as m2,\n)\nfrom app.services import Invoice\nimport helpers\n";
+    assert_eq!(
+        local_imports(&path(root, "app/views.py"), source),
+        vec![
+            path(root, "app/models.py"),
+            path(root, "app/services/billing.py"),
+            path(root, "app/services/mail.py"),
+            path(root, "app/services/__init__.py"),
+            path(root, "helpers.py"),
+        ]
+    );
+    assert_eq!(
+        local_imports(
+            &path(root, "app/services/billing.py"),
+            "from ..models import User\n"
+        ),
+        vec![path(root, "app/models.py")]
+    );
+}
+
+#[test]
+fn test_javascript_and_go_imports() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        &[
+            ("web/api.ts", ""),
+            ("web/util/index.js", ""),
+            ("web/types.ts", ""),
+            ("web/legacy.cjs", ""),
+            ("go.mod", "module example.com/shop\n\ngo 1.22\n"),
+            ("internal/store/store.go", ""),
+            ("internal/store/cache.go", ""),
+            ("internal/store/store_test.go", ""),
+        ],
+    );
+    let source = "import { get } from './api';\nimport * as util from \"./util\";\nimport type { T } from './types.js';\nconst legacy = require('./legacy.cjs');\nimport React from 'react';\n";
+    assert_eq!(
+        local_imports(&path(root, "web/app.tsx"), source),
+        vec![
+            path(root, "web/api.ts"),
+            path(root, "web/util/index.js"),
+            path(root, "web/types.ts"),
+            path(root, "web/legacy.cjs"),
+        ]
+    );
+
+    let source =
+        "package main\n\nimport (\n\t\"fmt\"\n\tst \"example.com/shop/internal/store\"\n)\n";
+    assert_eq!(
+        local_imports(&path(root, "cmd/shop/main.go"), source),
+        vec![
+            path(root, "internal/store/cache.go"),
+            path(root, "internal/store/store.go"),
+        ]
+    );
+}
+
+#[test]
+fn test_follow_imports_depth() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        &[
+            ("a.py", "from b import x\n"),
+            ("b.py", "import c\nimport a\n"),
+            ("c.py", "import d\n"),
+            ("d.py", ""),
+        ],
+    );
+    let files = vec![path(root, "a.py")];
+    assert_eq!(
+        follow_imports(&files, 1),
+        vec![ImportedFile {
+            path: path(root, "b.py"),
+            importer: path(root, "a.py"),
+        }]
+    );
+    let imported: Vec<(String, String)> = follow_imports(&files, 3)
+        .into_iter()
+        .map(|file| (file.path, file.importer))
+        .collect();
+    assert_eq!(
+        imported,
+        vec![
+            (path(root, "b.py"), path(root, "a.py")),
+            (path(root, "c.py"), path(root, "b.py")),
+            (path(root, "d.py"), path(root, "c.py")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_copy_follows_imports() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        &[
+            ("index.ts", "import { helper } from './helper';\n"),
+            ("helper.ts", "export const helper = 1;\n"),
+        ],
+    );
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        follow_imports: Some(1),
+        no_markdown: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![path(root, "index.ts")])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("export const helper = 1;"),
+        "Unexpected output: {}",
+        written
+    );
+}
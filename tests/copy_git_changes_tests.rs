@@ -0,0 +1,182 @@
+use snippy::copy::{copy_files_from, ClipboardCopierConfig, DiffMode};
+use snippy::git::{changed_files, file_diff, ChangeScope};
+use snippy::sink::SinkKind;
+use snippy::utils::{format_diff_at, FormatOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::tempdir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=snippy", "-c", "user.email=snippy@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn sorted(mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+    files.sort();
+    files
+}
+
+fn setup_repo(dir: &Path) {
+    git(dir, &["init", "-q", "-b", "main", "."]);
+    fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(dir.join("b.rs"), "fn b() {}\n").unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Initial commit"]);
+}
+
+#[test]
+fn test_changed_files_for_working_tree_and_staged_scopes() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    setup_repo(root);
+
+    fs::write(root.join("a.rs"), "fn a() { todo!() }\n").unwrap();
+    fs::write(root.join("b.rs"), "fn b() { todo!() }\n").unwrap();
+    fs::write(root.join("new.rs"), "fn new() {}\n").unwrap();
+    git(root, &["add", "b.rs"]);
+
+    assert_eq!(
+        sorted(changed_files(root, &ChangeScope::WorkingTree).unwrap()),
+        vec![PathBuf::from("a.rs"), PathBuf::from("b.rs"), PathBuf::from("new.rs")]
+    );
+    assert_eq!(
+        changed_files(root, &ChangeScope::Staged).unwrap(),
+        vec![PathBuf::from("b.rs")]
+    );
+}
+
+#[test]
+fn test_changed_files_since_ref_and_merge_base() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    setup_repo(root);
+
+    git(root, &["checkout", "-q", "-b", "feature"]);
+    fs::write(root.join("a.rs"), "fn a() { 1 }\n").unwrap();
+    git(root, &["commit", "-q", "-am", "Change a"]);
+    git(root, &["checkout", "-q", "main"]);
+    fs::write(root.join("b.rs"), "fn b() { 2 }\n").unwrap();
+    git(root, &["commit", "-q", "-am", "Change b"]);
+    git(root, &["checkout", "-q", "feature"]);
+
+    let since = ChangeScope::Since {
+        reference: "main".to_string(),
+        merge_base: false,
+    };
+    assert_eq!(
+        sorted(changed_files(root, &since).unwrap()),
+        vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")]
+    );
+
+    let merge_base = ChangeScope::Since {
+        reference: "main".to_string(),
+        merge_base: true,
+    }
+    .resolve(root)
+    .unwrap();
+    assert_eq!(
+        changed_files(root, &merge_base).unwrap(),
+        vec![PathBuf::from("a.rs")]
+    );
+
+    let diff = file_diff(root, &merge_base, Path::new("a.rs")).unwrap();
+    assert!(diff.contains("-fn a() {}"), "Unexpected diff: {}", diff);
+    assert!(diff.contains("+fn a() { 1 }"), "Unexpected diff: {}", diff);
+}
+
+#[test]
+fn test_changed_files_without_commits() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    git(root, &["init", "-q", "-b", "main", "."]);
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("b.rs"), "fn b() {}\n").unwrap();
+    git(root, &["add", "a.rs"]);
+
+    assert_eq!(
+        sorted(changed_files(root, &ChangeScope::WorkingTree).unwrap()),
+        vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")]
+    );
+    let diff = file_diff(root, &ChangeScope::WorkingTree, Path::new("a.rs")).unwrap();
+    assert!(diff.contains("+fn a() {}"), "Unexpected diff: {}", diff);
+}
+
+#[test]
+fn test_since_rejects_option_like_refs() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    setup_repo(root);
+
+    for merge_base in [false, true] {
+        let since = ChangeScope::Since {
+            reference: "--output=out.txt".to_string(),
+            merge_base,
+        };
+        assert!(changed_files(root, &since).is_err());
+    }
+    assert!(!root.join("out.txt").exists());
+}
+
+#[tokio::test]
+async fn test_copy_changed_keeps_deleted_files_matching_patterns() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    git(root, &["init", "-q", "-b", "main", "."]);
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/gone.rs"), "fn gone() {}\n").unwrap();
+    fs::write(root.join("src/kept.rs"), "fn kept() {}\n").unwrap();
+    fs::write(root.join("other.rs"), "fn other() {}\n").unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-q", "-m", "Initial commit"]);
+    fs::remove_file(root.join("src/gone.rs")).unwrap();
+    fs::remove_file(root.join("other.rs")).unwrap();
+
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        change_scope: Some(ChangeScope::WorkingTree),
+        diff_mode: DiffMode::WithContent,
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    let pattern = root.join("src").to_string_lossy().into_owned();
+    copy_files_from(root, config, vec![pattern])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("-fn gone() {}") && !written.contains("other.rs"),
+        "Unexpected output: {}",
+        written
+    );
+}
+
+#[test]
+fn test_format_diff_uses_markdown_and_xml_wrappers() {
+    let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-old\n+new\n";
+
+    let markdown = FormatOptions {
+        filename_format: "MarkdownHeading".to_string(),
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format_diff_at(diff, "./src/a.rs", 1, &markdown),
+        format!("### `src/a.rs` (diff)\n```diff\n{}```\n", diff)
+    );
+    let xml = FormatOptions {
+        xml: true,
+        ..markdown
+    };
+    assert_eq!(
+        format_diff_at(diff, "src/a.rs", 1, &xml),
+        format!("<diff path=\"src/a.rs\">\n<![CDATA[{}]]>\n</diff>\n", diff)
+    );
+}
@@ -1,7 +1,8 @@
 use snippy::copy::ClipboardCopierConfig;
 use snippy::copy_files_to_clipboard;
 use snippy::errors::ClipboardError;
-use tempfile::{tempdir, TempDir};
+use snippy::sink::SinkKind;
+use tempfile::tempdir;
 use tokio::fs;
 use tokio::process::Command;
 
@@ -15,7 +16,7 @@ async fn test_copy_files_from_git_repository() -> Result<(), ClipboardError> {
     let git_init_status = Command::new("git")
         .arg("init")
         .arg(".")
-        .current_dir(&repo_path)
+        .current_dir(repo_path)
         .status()
         .await
         .expect("Failed to initialize git repository");
@@ -43,7 +44,7 @@ async fn test_copy_files_from_git_repository() -> Result<(), ClipboardError> {
     let git_add_status = Command::new("git")
         .arg("add")
         .arg(".")
-        .current_dir(&repo_path)
+        .current_dir(repo_path)
         .status()
         .await
         .expect("Failed to add files to git");
@@ -59,7 +60,7 @@ async fn test_copy_files_from_git_repository() -> Result<(), ClipboardError> {
         .arg("commit")
         .arg("-m")
         .arg("Initial commit")
-        .current_dir(&repo_path)
+        .current_dir(repo_path)
         .status()
         .await
         .expect("Failed to commit files to git");
@@ -82,6 +83,8 @@ async fn test_copy_files_from_git_repository() -> Result<(), ClipboardError> {
         filename_format: String::from("MarkdownHeading"),
         first_line: String::from("# Code from Git Repository\n"),
         xml: false,
+        sink: SinkKind::Stdout,
+        ..Default::default()
     };
 
     let files = vec![git_url, String::from("file1.rs"), String::from("file2.py")];
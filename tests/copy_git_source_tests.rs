@@ -0,0 +1,166 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::errors::ClipboardError;
+use snippy::remote::{checkout, mirror_dir, GitSource};
+use snippy::sink::SinkKind;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=snippy",
+            "-c",
+            "user.email=snippy@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Creates a work repository with a tagged commit and a bare clone of it.
+fn setup_bare_repo(root: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let work = root.join("work");
+    fs::create_dir_all(work.join("src")).unwrap();
+    fs::create_dir_all(work.join("docs")).unwrap();
+    git(&work, &["init", "-q", "-b", "main", "."]);
+    fs::write(work.join("src/lib.rs"), "pub fn v1() {}\n").unwrap();
+    fs::write(work.join("docs/guide.md"), "# Guide\n").unwrap();
+    git(&work, &["add", "."]);
+    git(&work, &["commit", "-q", "-m", "v1"]);
+    git(&work, &["tag", "v1"]);
+
+    let bare = root.join("upstream.git");
+    git(root, &["clone", "-q", "--bare", "work", "upstream.git"]);
+    (work, bare)
+}
+
+#[test]
+fn test_parse_git_source() {
+    assert_eq!(
+        GitSource::parse("https://github.com/owner/repo.git#v1.2:src/"),
+        Some(GitSource {
+            url: "https://github.com/owner/repo.git".to_string(),
+            reference: Some("v1.2".to_string()),
+            subdir: Some("src".to_string()),
+        })
+    );
+    assert_eq!(
+        GitSource::parse("git@github.com:owner/repo.git#main"),
+        Some(GitSource {
+            url: "git@github.com:owner/repo.git".to_string(),
+            reference: Some("main".to_string()),
+            subdir: None,
+        })
+    );
+    assert_eq!(
+        GitSource::parse("file:///srv/repo.git#:docs"),
+        Some(GitSource {
+            url: "file:///srv/repo.git".to_string(),
+            reference: None,
+            subdir: Some("docs".to_string()),
+        })
+    );
+    assert_eq!(GitSource::parse("src/lib.rs"), None);
+    assert_eq!(GitSource::parse("src/#weird:name"), None);
+}
+
+#[test]
+fn test_sparse_checkout_reuses_and_updates_mirror() {
+    let dir = tempdir().unwrap();
+    let (work, bare) = setup_bare_repo(dir.path());
+    let url = format!("file://{}", bare.display());
+    let cache = dir.path().join("cache");
+
+    let source = GitSource::parse(&format!("{}#v1", url)).unwrap();
+    let first = dir.path().join("first");
+    checkout(&source, &["src/*.rs".to_string()], &cache, &first).unwrap();
+    assert_eq!(
+        fs::read_to_string(first.join("src/lib.rs")).unwrap(),
+        "pub fn v1() {}\n"
+    );
+    assert!(
+        !first.join("docs").exists(),
+        "docs should not be checked out"
+    );
+    assert!(mirror_dir(&cache, &url).join("HEAD").is_file());
+
+    // A later run fetches new commits into the existing mirror
+    fs::write(work.join("src/lib.rs"), "pub fn v2() {}\n").unwrap();
+    git(&work, &["commit", "-q", "-am", "v2"]);
+    git(&work, &["push", "-q", bare.to_str().unwrap(), "main"]);
+
+    let source = GitSource::parse(&format!("{}#main", url)).unwrap();
+    let second = dir.path().join("second");
+    checkout(&source, &[String::new()], &cache, &second).unwrap();
+    assert_eq!(
+        fs::read_to_string(second.join("src/lib.rs")).unwrap(),
+        "pub fn v2() {}\n"
+    );
+    assert!(second.join("docs/guide.md").is_file());
+}
+
+#[test]
+fn test_checkout_rejects_option_like_refs_and_locks_the_mirror() {
+    let dir = tempdir().unwrap();
+    let (_, bare) = setup_bare_repo(dir.path());
+    let url = format!("file://{}", bare.display());
+    let cache = dir.path().join("cache");
+
+    let source = GitSource::parse(&format!("{}#--upload-pack=touch", url)).unwrap();
+    assert!(matches!(
+        checkout(&source, &[], &cache, &dir.path().join("bad")),
+        Err(ClipboardError::ConfigError(_))
+    ));
+
+    // Concurrent runs wait for each other instead of racing on the shared mirror
+    let source = GitSource::parse(&format!("{}#v1", url)).unwrap();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let (source, cache) = (source.clone(), cache.clone());
+            let target = dir.path().join(format!("checkout-{}", i));
+            std::thread::spawn(move || checkout(&source, &[], &cache, &target).map(|_| target))
+        })
+        .collect();
+    for handle in handles {
+        let target = handle.join().unwrap().unwrap();
+        assert!(target.join("src/lib.rs").is_file());
+    }
+    let leftovers: Vec<_> = fs::read_dir(cache.join("repos"))
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().to_string_lossy().ends_with(".lock"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_from_bare_repo_subdirectory() {
+    let dir = tempdir().unwrap();
+    let (_, bare) = setup_bare_repo(dir.path());
+    let output = dir.path().join("context.md");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        git_cache_dir: Some(dir.path().join("cache")),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![format!("{}#v1:src", bare.display())])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("### `src/lib.rs`\n"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("Guide"), "Unexpected output: {}", written);
+}
@@ -0,0 +1,101 @@
+use regex::Regex;
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::errors::ClipboardError;
+use snippy::grep::{context_regions, matching_lines};
+use snippy::selector::LineRange;
+use snippy::sink::SinkKind;
+use snippy::utils::{format_regions_at, FormatOptions};
+use std::fs;
+use tempfile::tempdir;
+
+fn numbered_source(lines: usize) -> String {
+    (1..=lines).map(|i| format!("line {}\n", i)).collect()
+}
+
+#[test]
+fn test_matching_lines_and_regions() {
+    let content = "fn a() {}\nErr(ClipboardError::IoError(e))\n\n\nx\nClipboardError::FileError\n";
+    let regex = Regex::new("ClipboardError::").unwrap();
+    assert_eq!(matching_lines(content, &regex), vec![2, 6]);
+
+    let range = |start, end| LineRange { start, end };
+    assert_eq!(
+        context_regions(&[2, 6], 1, 6),
+        vec![range(1, 3), range(5, 6)]
+    );
+    // Windows that touch are merged
+    assert_eq!(context_regions(&[2, 6], 2, 6), vec![range(1, 6)]);
+    assert_eq!(
+        context_regions(&[1, 50, 100], 3, 100),
+        vec![range(1, 4), range(47, 53), range(97, 100)]
+    );
+}
+
+#[test]
+fn test_format_regions() {
+    let content = numbered_source(20);
+    let regions = [
+        LineRange { start: 2, end: 3 },
+        LineRange { start: 10, end: 10 },
+    ];
+    let options = FormatOptions {
+        line_number: Some(2),
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format_regions_at(&content, "notes.txt", &regions, 1, &options).unwrap(),
+        "### `notes.txt:2-3,10`\n```txt\n02|line 2\n03|line 3\n...\n10|line 10\n```\n"
+    );
+
+    let options = FormatOptions {
+        json: true,
+        ..FormatOptions::default()
+    };
+    let record: serde_json::Value = serde_json::from_str(
+        format_regions_at(&content, "notes.txt", &regions, 1, &options)
+            .unwrap()
+            .trim(),
+    )
+    .unwrap();
+    assert_eq!(record["content"], "line 2\nline 3\n...\nline 10");
+    assert_eq!(record["start_line"], 2);
+}
+
+#[tokio::test]
+async fn test_copy_grep() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.txt"), numbered_source(30)).unwrap();
+    fs::write(root.join("b.txt"), "nothing here\n").unwrap();
+    let output = root.join("out.md");
+
+    let copy = |grep: &str| {
+        let config = ClipboardCopierConfig {
+            grep: Some(grep.to_string()),
+            grep_context: 1,
+            no_markdown: true,
+            sink: SinkKind::File,
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+    };
+    copy(r"^line (5|25)$")
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("a.txt:4-6,24-26")
+            && written.contains("0004|line 4\n0005|line 5\n0006|line 6\n...\n0024|line 24\n"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("line 10\n"));
+    assert!(!written.contains("nothing here"));
+
+    assert!(matches!(
+        copy("(unclosed").await,
+        Err(ClipboardError::RegexError(_))
+    ));
+}
@@ -0,0 +1,136 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::json::{HeaderRecord, OutputFormat, Record};
+use snippy::sink::SinkKind;
+use snippy::utils::{format_snippet, FormatOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+async fn copy_json(root: &Path, format: OutputFormat, files: Vec<String>) -> String {
+    let output_dir = tempdir().unwrap();
+    let output = output_dir.path().join("context.json");
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        format,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    let files = files
+        .into_iter()
+        .map(|f| root.join(f).to_string_lossy().into_owned())
+        .collect();
+    copy_files_to_clipboard(config, files)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    fs::read_to_string(&output).unwrap()
+}
+
+fn header(record: &Record) -> &HeaderRecord {
+    match record {
+        Record::Header(header) => header,
+        record => panic!("Expected a header record, got {:?}", record),
+    }
+}
+
+#[test]
+fn test_format_snippet_as_json_record() {
+    let options = FormatOptions {
+        json: true,
+        line_number: Some(3),
+        ..FormatOptions::default()
+    };
+    let line = format_snippet("fn a() {\n    \"x\"\n}\n", "src/a.rs", 1, None, &options).unwrap();
+    assert!(line.ends_with("}\n") && line.lines().count() == 1);
+    let Record::File(record) = serde_json::from_str(&line).unwrap() else {
+        panic!("Expected a file record: {}", line);
+    };
+    assert_eq!(record.path, "src/a.rs");
+    assert_eq!(record.language, "rust");
+    // Line numbers are left out of the raw content
+    assert_eq!(record.content, "fn a() {\n    \"x\"\n}\n");
+    assert_eq!(record.lines, 3);
+    assert_eq!(record.tokens, None);
+    assert_eq!(record.sha256.len(), 64);
+    assert_eq!(record.start_line, None);
+}
+
+#[tokio::test]
+async fn test_copy_jsonl_records() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("b.py"), "def b():\n    pass\n").unwrap();
+    fs::write(root.join("c.bin"), [0u8, 159, 146, 150]).unwrap();
+
+    let written = copy_json(root, OutputFormat::Jsonl, vec![String::new()]).await;
+    let records: Vec<Record> = written
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 3, "Unexpected output: {}", written);
+
+    let header = header(&records[0]);
+    assert_eq!(header.model, "gpt-4o");
+    assert_eq!(header.files, 2);
+    assert!(header.total_tokens > 0);
+    assert_eq!(header.skipped.len(), 1);
+    assert!(header.skipped[0].path.ends_with("c.bin"));
+    assert_eq!(header.skipped[0].reason, "binary");
+
+    let mut files: Vec<_> = records[1..]
+        .iter()
+        .map(|record| match record {
+            Record::File(file) => file.clone(),
+            record => panic!("Expected a file record, got {:?}", record),
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    assert!(files[0].path.ends_with("a.rs"));
+    assert_eq!(files[0].language, "rust");
+    assert_eq!(files[0].content, "fn a() {}\n");
+    assert_eq!(files[0].tokens, Some(4));
+    assert_eq!(files[0].lines, 1);
+    assert_eq!(
+        files[0].sha256,
+        "509a0a5b5ce4e59f5039e30a39324342d7a161296bb8eba761983faaeebf6efd"
+    );
+    assert_eq!(files[1].language, "python");
+}
+
+#[tokio::test]
+async fn test_copy_json_array_with_selection() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "// one\nfn a() {}\n// three\n").unwrap();
+
+    let written = copy_json(root, OutputFormat::Json, vec!["a.rs:2-3".to_string()]).await;
+    assert!(written.starts_with("[\n{\"type\":\"header\""));
+    let records: Vec<Record> = serde_json::from_str(&written).unwrap();
+    assert_eq!(header(&records[0]).files, 1);
+    let Record::File(file) = &records[1] else {
+        panic!("Expected a file record: {}", written);
+    };
+    assert_eq!(file.content, "fn a() {}\n// three");
+    assert_eq!(file.start_line, Some(2));
+}
+
+#[tokio::test]
+async fn test_json_rejects_prompts() {
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        format: OutputFormat::Json,
+        prompt: Some("general-coding".to_string()),
+        sink: SinkKind::Stdout,
+        ..Default::default()
+    };
+    let err = copy_files_to_clipboard(config, vec!["Cargo.toml".to_string()])
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("JSON output"),
+        "Unexpected error: {}",
+        err
+    );
+}
@@ -0,0 +1,141 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::llm::{ModelPricing, TokenUsage};
+use snippy::models::{Encoding, ModelInfo, ModelRegistry, TokenizerSpec, UNKNOWN_MODEL_MARGIN};
+use snippy::sink::SinkKind;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_builtin_models() {
+    let registry = ModelRegistry::builtin();
+    let gpt = registry.get("gpt-4o").unwrap();
+    assert_eq!(
+        gpt.tokenizer,
+        TokenizerSpec::Exact {
+            encoding: Encoding::O200kBase
+        }
+    );
+    assert_eq!(gpt.context_window, Some(128_000));
+    assert_eq!(gpt.input_cost(1_000_000), Some(2.50));
+
+    let claude = registry.get("claude-3-5-sonnet-latest").unwrap();
+    assert!(claude.tokenizer.margin() > 0.0);
+    assert_eq!(claude.context_window, Some(200_000));
+    assert!(registry.names().any(|name| name.starts_with("gemini")));
+
+    // Usage costs come from the registry
+    let usage = TokenUsage {
+        prompt_tokens: 1_000_000,
+        completion_tokens: 0,
+        total_tokens: 1_000_000,
+        ..TokenUsage::default()
+    };
+    assert_eq!(usage.get_cost(&registry, "gpt-4o-mini"), Some(0.15));
+}
+
+#[test]
+fn test_resolve_unknown_models() {
+    let registry = ModelRegistry::builtin();
+    // tiktoken still knows models the registry doesn't
+    assert_eq!(
+        registry.resolve("text-davinci-003").tokenizer,
+        TokenizerSpec::Exact {
+            encoding: Encoding::P50kBase
+        }
+    );
+
+    let unknown = registry.resolve("mystery-model");
+    assert_eq!(unknown.tokenizer.margin(), UNKNOWN_MODEL_MARGIN);
+    assert_eq!(unknown.context_window, None);
+    assert_eq!(unknown.fit_warning(usize::MAX), None);
+    assert_eq!(unknown.input_cost(1000), None);
+}
+
+#[test]
+fn test_fit_warning() {
+    let model = ModelInfo {
+        tokenizer: TokenizerSpec::Exact {
+            encoding: Encoding::Cl100kBase,
+        },
+        context_window: Some(1000),
+        max_output_tokens: Some(200),
+        pricing: None,
+    };
+    assert_eq!(model.fit_warning(700), None);
+    assert_eq!(
+        model.fit_warning(900).unwrap(),
+        "900 tokens leave only 100 of the 1000-token context window for the response"
+    );
+    assert_eq!(
+        model.fit_warning(1001).unwrap(),
+        "1001 tokens do not fit the 1000-token context window"
+    );
+}
+
+#[test]
+fn test_load_models_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("models.json");
+    fs::write(
+        &path,
+        r#"{
+            "models": {
+                "local-coder": {
+                    "tokenizer": { "kind": "approximate", "encoding": "cl100k_base", "margin": 0.15 },
+                    "context_window": 32768
+                },
+                "gpt-4o": {
+                    "tokenizer": { "kind": "exact", "encoding": "o200k_base" },
+                    "context_window": 64000,
+                    "pricing": { "input_price": 1.0, "cached_price": 0.5, "output_price": 4.0 }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let registry = ModelRegistry::load(Some(&path)).unwrap();
+    let local = registry.get("local-coder").unwrap();
+    assert_eq!(
+        local.tokenizer,
+        TokenizerSpec::Approximate {
+            encoding: Encoding::Cl100kBase,
+            margin: 0.15
+        }
+    );
+    assert_eq!(local.max_output_tokens, None);
+    let gpt = registry.get("gpt-4o").unwrap();
+    assert_eq!(gpt.context_window, Some(64000));
+    assert_eq!(gpt.pricing, Some(ModelPricing::new(1.0, 0.5, 4.0)));
+    assert!(registry.get("gpt-4o-mini").is_some());
+
+    fs::write(&path, r#"{"models": {"bad": {}}}"#).unwrap();
+    assert!(ModelRegistry::load(Some(&path)).is_err());
+    assert!(ModelRegistry::load(Some(&dir.path().join("missing.json"))).is_err());
+}
+
+#[tokio::test]
+async fn test_copy_with_model_outside_tiktoken() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    let output = root.join("out.md");
+
+    for model in ["claude-3-5-sonnet-latest", "mystery-model"] {
+        let config = ClipboardCopierConfig {
+            model: model.to_string(),
+            max_tokens: Some(1000),
+            sink: SinkKind::File,
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        copy_files_to_clipboard(
+            config,
+            vec![root.join("a.rs").to_string_lossy().into_owned()],
+        )
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy with {}: {:?}", model, e));
+        assert!(fs::read_to_string(&output).unwrap().contains("fn a() {}"));
+    }
+}
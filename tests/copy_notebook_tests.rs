@@ -0,0 +1,208 @@
+use snippy::applier::{Applier, NotebookCellApplier};
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::extractor::markdown::MarkdownExtractor;
+use snippy::extractor::{BlockType, Extractor, ParsedBlock};
+use snippy::notebook::{is_notebook, parse_cell_address, CellKind, Notebook, NotebookOptions};
+use snippy::sink::SinkKind;
+use std::fs;
+use tempfile::tempdir;
+
+const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["# Analysis\n", "```python\n", "x = 1\n", "```"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [
+    {"name": "stdout", "output_type": "stream", "text": ["1\n", "2\n", "3\n", "4\n"]},
+    {"ename": "ValueError", "evalue": "bad", "output_type": "error", "traceback": []}
+   ],
+   "source": "for i in range(1, 5):\n    print(i)"
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 2,
+   "metadata": {},
+   "outputs": [
+    {"data": {"image/png": "iVBORw0KGgo="}, "metadata": {}, "output_type": "display_data"}
+   ],
+   "source": ["plot()"]
+  }
+ ],
+ "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+#[test]
+fn test_render_notebook_cells() {
+    let notebook = Notebook::parse(NOTEBOOK).unwrap();
+    let cells = notebook.cells();
+    assert_eq!(cells.len(), 3);
+    assert_eq!(cells[0].kind, CellKind::Markdown);
+    assert_eq!(cells[1].outputs, vec!["1\n2\n3\n4\n", "ValueError: bad"]);
+    assert_eq!(cells[2].outputs, vec!["[image/png output]"]);
+
+    let options = NotebookOptions {
+        max_output_lines: Some(2),
+        ..NotebookOptions::default()
+    };
+    assert_eq!(
+        notebook.render(&options),
+        "## Cell 1 (markdown)\n# Analysis\n~~~python\nx = 1\n~~~\n\n\
+         ## Cell 2 (code)\n~~~python\nfor i in range(1, 5):\n    print(i)\n~~~\n\
+         Output:\n~~~\n1\n2\n... (2 more lines)\nValueError: bad\n~~~\n\n\
+         ## Cell 3 (code)\n~~~python\nplot()\n~~~\nOutput:\n~~~\n[image/png output]\n~~~\n"
+    );
+
+    let options = NotebookOptions {
+        outputs: false,
+        ..NotebookOptions::default()
+    };
+    let rendered = notebook.render(&options);
+    assert!(!rendered.contains("Output:"));
+    assert!(rendered.contains("## Cell 3 (code)\n~~~python\nplot()\n~~~\n"));
+
+    assert!(Notebook::parse("{}").is_err());
+    assert!(Notebook::parse("not json").is_err());
+}
+
+#[test]
+fn test_parse_cell_address() {
+    let expected = Some(("notebooks/analysis.ipynb".to_string(), 4));
+    assert_eq!(
+        parse_cell_address("notebooks/analysis.ipynb cell 4"),
+        expected
+    );
+    assert_eq!(
+        parse_cell_address("notebooks/analysis.ipynb:cell 4"),
+        expected
+    );
+    assert_eq!(
+        parse_cell_address("notebooks/analysis.ipynb#cell4"),
+        expected
+    );
+    assert_eq!(
+        parse_cell_address(" notebooks/analysis.ipynb Cell 4 "),
+        expected
+    );
+    assert_eq!(parse_cell_address("analysis.ipynb cell 0"), None);
+    assert_eq!(parse_cell_address("analysis.ipynb"), None);
+    assert_eq!(parse_cell_address("src/cell 4.rs"), None);
+    assert!(is_notebook("a/B.IPYNB"));
+    assert!(!is_notebook("a/b.json"));
+}
+
+#[test]
+fn test_set_cell_source() {
+    let mut notebook = Notebook::parse(NOTEBOOK).unwrap();
+    notebook
+        .set_cell_source(2, "print('hi')\nprint('there')\n")
+        .unwrap();
+    notebook.set_cell_source(4, "new()").unwrap();
+    assert!(notebook.set_cell_source(6, "x").is_err());
+    assert!(notebook.set_cell_source(0, "x").is_err());
+
+    let json = notebook.to_json().unwrap();
+    assert!(json.ends_with("}\n"));
+    assert!(json.starts_with("{\n \"cells\": [\n  {\n"));
+    let cells = Notebook::parse(&json).unwrap().cells();
+    assert_eq!(cells.len(), 4);
+    assert_eq!(cells[1].source, "print('hi')\nprint('there')");
+    assert_eq!(cells[1].outputs.len(), 2);
+    assert_eq!(cells[3].kind, CellKind::Code);
+    assert_eq!(cells[3].source, "new()");
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value["cells"][1]["source"],
+        serde_json::json!(["print('hi')\n", "print('there')"])
+    );
+    assert_eq!(value["metadata"]["kernelspec"]["name"], "python3");
+}
+
+#[tokio::test]
+async fn test_apply_notebook_cell() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("analysis.ipynb");
+    fs::write(&path, NOTEBOOK).unwrap();
+
+    let content = r#"
+### `analysis.ipynb` cell 3
+
+```python
+plot(title="Totals")
+```
+"#;
+    let blocks = MarkdownExtractor::new()
+        .extract(content)
+        .unwrap_or_else(|e| panic!("Failed to extract content: {:?}", e));
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].filename, "analysis.ipynb cell 3");
+    assert_eq!(blocks[0].block_type, BlockType::FullContent);
+
+    let applier = NotebookCellApplier::new(dir.path());
+    applier
+        .apply(&blocks[0])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to apply content: {:?}", e));
+    let cells = Notebook::parse(&fs::read_to_string(&path).unwrap())
+        .unwrap()
+        .cells();
+    assert_eq!(cells[2].source, "plot(title=\"Totals\")");
+    assert_eq!(cells[2].outputs, vec!["[image/png output]"]);
+    assert_eq!(cells[0].source, "# Analysis\n```python\nx = 1\n```");
+
+    let missing = ParsedBlock {
+        filename: "analysis.ipynb cell 9".to_string(),
+        content: "x".to_string(),
+        block_type: BlockType::FullContent,
+    };
+    assert!(applier.apply(&missing).await.is_err());
+}
+
+#[tokio::test]
+async fn test_copy_renders_notebooks() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("analysis.ipynb"), NOTEBOOK).unwrap();
+    let output = root.join("out.md");
+    let file = root.join("analysis.ipynb").to_string_lossy().into_owned();
+
+    let copy = |notebook: NotebookOptions| {
+        let config = ClipboardCopierConfig {
+            notebook,
+            no_markdown: true,
+            sink: SinkKind::File,
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        copy_files_to_clipboard(config, vec![file.clone()])
+    };
+    copy(NotebookOptions::default())
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("## Cell 2 (code)\n~~~python\nfor i in range(1, 5):"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("\"cell_type\""));
+
+    copy(NotebookOptions {
+        render: false,
+        ..NotebookOptions::default()
+    })
+    .await
+    .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains("\"cell_type\": \"markdown\""));
+}
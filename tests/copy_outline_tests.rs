@@ -0,0 +1,156 @@
+use snippy::outline::outline;
+use snippy::symbols::Language;
+use snippy::utils::{format_snippet, FormatOptions};
+
+#[test]
+fn test_outline_rust_keeps_signatures_and_types() {
+    let source = r#"use std::fmt;
+
+/// A point.
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(
+        x: i32,
+    ) -> Self {
+        let s = "}";
+        Point { x }
+    }
+
+    fn x(&self) -> i32 { self.x }
+}
+
+pub trait Shape {
+    fn area(&self) -> f64;
+}
+"#;
+
+    assert_eq!(
+        outline(source, Language::Rust),
+        r#"use std::fmt;
+
+/// A point.
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(
+        x: i32,
+    ) -> Self {
+        // ... lines 13-14 elided
+    }
+
+    fn x(&self) -> i32 { self.x }
+}
+
+pub trait Shape {
+    fn area(&self) -> f64;
+}"#
+    );
+}
+
+#[test]
+fn test_outline_python_uses_ellipsis_bodies() {
+    let source = r#"import os
+
+class User:
+    """A user."""
+
+    def save(self,
+             force=False):
+        if force:
+            os.sync()
+        return None
+
+def main():
+    User().save()
+"#;
+
+    assert_eq!(
+        outline(source, Language::Python),
+        r#"import os
+
+class User:
+    """A user."""
+
+    def save(self,
+             force=False):
+        ...  # lines 8-10 elided
+
+def main():
+    ...  # line 13 elided"#
+    );
+}
+
+#[test]
+fn test_outline_typescript_and_go() {
+    let ts = r#"import { a } from "./a";
+
+export class Parser {
+  parse(text: string): Node {
+    return a(text);
+  }
+}
+
+export const handler = async (req: Request) => {
+  return req;
+};
+"#;
+    assert_eq!(
+        outline(ts, Language::TypeScript),
+        r#"import { a } from "./a";
+
+export class Parser {
+  parse(text: string): Node {
+    // ... line 5 elided
+  }
+}
+
+export const handler = async (req: Request) => {
+  // ... line 10 elided
+};"#
+    );
+
+    let go = r#"package main
+
+type Server struct {
+	addr string
+}
+
+func (s *Server) Start() error {
+	return nil
+}
+"#;
+    assert_eq!(
+        outline(go, Language::Go),
+        "package main\n\ntype Server struct {\n\taddr string\n}\n\nfunc (s *Server) Start() error {\n\t// ... line 8 elided\n}"
+    );
+}
+
+#[test]
+fn test_format_snippet_outline_drops_line_numbers() {
+    let options = FormatOptions {
+        no_markdown: false,
+        line_number: Some(2),
+        prefix: "|".to_string(),
+        filename_format: "MarkdownHeading".to_string(),
+        xml: false,
+        outline: true,
+        ..FormatOptions::default()
+    };
+
+    assert_eq!(
+        format_snippet("fn a() {\n    1\n}", "src/a.rs", 1, None, &options).unwrap(),
+        "### `src/a.rs`\n```rs\nfn a() {\n    // ... line 2 elided\n}\n```\n"
+    );
+    // Files without outline support are copied in full
+    assert_eq!(
+        format_snippet("# Title", "notes.md", 1, None, &options).unwrap(),
+        "### `notes.md`\n```md\n01|# Title\n```\n"
+    );
+}
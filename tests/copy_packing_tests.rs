@@ -0,0 +1,137 @@
+use snippy::packing::{pack, path_distance, PackCandidate, PackDecision};
+use std::path::{Path, PathBuf};
+
+fn candidate(path: &str, tokens: usize, explicit: bool) -> PackCandidate {
+    PackCandidate {
+        path: PathBuf::from(path),
+        tokens,
+        outline_tokens: None,
+        explicit,
+        rank: None,
+    }
+}
+
+fn outlinable(path: &str, tokens: usize, outline_tokens: usize, explicit: bool) -> PackCandidate {
+    PackCandidate {
+        outline_tokens: Some(outline_tokens),
+        ..candidate(path, tokens, explicit)
+    }
+}
+
+#[test]
+fn test_path_distance() {
+    assert_eq!(
+        path_distance(Path::new("src/a.rs"), Path::new("src/b.rs")),
+        0
+    );
+    assert_eq!(
+        path_distance(Path::new("src/x/a.rs"), Path::new("src/b.rs")),
+        1
+    );
+    assert_eq!(
+        path_distance(Path::new("tests/a.rs"), Path::new("src/x/b.rs")),
+        3
+    );
+    assert_eq!(path_distance(Path::new("a.rs"), Path::new("")), 0);
+}
+
+#[test]
+fn test_pack_prefers_explicit_then_nearby_files() {
+    let candidates = vec![
+        candidate("docs/guide.md", 30, false),
+        candidate("src/applier/mod.rs", 30, false),
+        candidate("src/copy.rs", 40, true),
+        candidate("src/utils.rs", 30, false),
+    ];
+
+    let decisions = pack(&candidates, 90);
+
+    assert_eq!(
+        decisions,
+        vec![
+            PackDecision::Dropped,
+            PackDecision::Dropped,
+            PackDecision::Included,
+            PackDecision::Included,
+        ]
+    );
+}
+
+#[test]
+fn test_pack_fills_leftover_space_with_smallest_files() {
+    let candidates = vec![
+        candidate("src/main.rs", 50, true),
+        candidate("src/big.rs", 80, false),
+        candidate("src/deep/tiny.rs", 10, false),
+        candidate("README.md", 30, false),
+    ];
+
+    let decisions = pack(&candidates, 95);
+
+    assert_eq!(
+        decisions,
+        vec![
+            PackDecision::Included,
+            PackDecision::Dropped,
+            PackDecision::Included,
+            PackDecision::Included,
+        ]
+    );
+}
+
+#[test]
+fn test_pack_without_explicit_files_prefers_shallow_paths() {
+    let candidates = vec![
+        candidate("a/b/c/deep.rs", 10, false),
+        candidate("top.rs", 10, false),
+    ];
+
+    assert_eq!(
+        pack(&candidates, 10),
+        vec![PackDecision::Dropped, PackDecision::Included]
+    );
+}
+
+#[test]
+fn test_pack_takes_ranked_files_best_first() {
+    let ranked = |path: &str, tokens: usize, rank: usize| PackCandidate {
+        rank: Some(rank),
+        ..candidate(path, tokens, false)
+    };
+    let candidates = vec![
+        ranked("a.rs", 30, 2),
+        candidate("b.rs", 10, false),
+        ranked("deep/c/d.rs", 50, 0),
+        ranked("e.rs", 30, 1),
+    ];
+
+    assert_eq!(
+        pack(&candidates, 90),
+        vec![
+            PackDecision::Dropped,
+            PackDecision::Included,
+            PackDecision::Included,
+            PackDecision::Included,
+        ]
+    );
+}
+
+#[test]
+fn test_pack_falls_back_to_outlines() {
+    let candidates = vec![
+        outlinable("src/copy.rs", 120, 20, true),
+        candidate("src/utils.rs", 50, false),
+        outlinable("src/watch.rs", 90, 25, false),
+        outlinable("src/x/llm.rs", 80, 40, false),
+    ];
+
+    assert_eq!(
+        pack(&candidates, 100),
+        vec![
+            PackDecision::Outlined,
+            PackDecision::Included,
+            PackDecision::Outlined,
+            PackDecision::Dropped,
+        ]
+    );
+}
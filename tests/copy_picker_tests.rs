@@ -0,0 +1,107 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use snippy::picker::{fuzzy_score, Check, PickerAction, PickerState};
+use std::path::PathBuf;
+
+fn state() -> PickerState {
+    PickerState::new(vec![
+        (PathBuf::from("README.md"), 40),
+        (PathBuf::from("src/copy.rs"), 500),
+        (PathBuf::from("src/watch.rs"), 300),
+        (PathBuf::from("src/applier/mod.rs"), 100),
+    ])
+    .unwrap()
+}
+
+fn press(state: &mut PickerState, code: KeyCode) -> PickerAction {
+    state.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn names(state: &PickerState) -> Vec<String> {
+    state.rows().into_iter().map(|row| row.name).collect()
+}
+
+#[test]
+fn test_picker_tree_and_selection() {
+    let mut state = state();
+    assert_eq!(names(&state), vec!["README.md", "src"]);
+    assert_eq!(state.rows()[1].tokens, 900);
+
+    press(&mut state, KeyCode::Down);
+    press(&mut state, KeyCode::Right);
+    assert_eq!(
+        names(&state),
+        vec!["README.md", "src", "applier", "copy.rs", "watch.rs"]
+    );
+    assert_eq!(state.rows()[3].depth, 1);
+
+    // Selecting a file leaves its directory partially selected
+    press(&mut state, KeyCode::Down);
+    press(&mut state, KeyCode::Down);
+    press(&mut state, KeyCode::Char(' '));
+    assert_eq!(state.rows()[1].check, Check::Partial);
+    assert_eq!(state.selected_tokens(), 500);
+
+    // Collapsing from a file moves to its directory
+    press(&mut state, KeyCode::Left);
+    assert_eq!(state.cursor(), 1);
+    assert_eq!(names(&state), vec!["README.md", "src"]);
+
+    press(&mut state, KeyCode::Char(' '));
+    assert_eq!(state.rows()[1].check, Check::Checked);
+    assert_eq!(state.selected_tokens(), 900);
+    press(&mut state, KeyCode::Tab);
+    assert_eq!(state.rows()[1].check, Check::Unchecked);
+    assert!(state.selected_files().is_empty());
+}
+
+#[test]
+fn test_picker_search() {
+    let mut state = state();
+    for c in "wat".chars() {
+        press(&mut state, KeyCode::Char(c));
+    }
+    assert_eq!(state.query(), "wat");
+    assert_eq!(names(&state), vec!["src/watch.rs"]);
+
+    for _ in 0..3 {
+        press(&mut state, KeyCode::Backspace);
+    }
+    press(&mut state, KeyCode::Char('r'));
+    press(&mut state, KeyCode::Char('d'));
+    assert_eq!(names(&state), vec!["README.md", "src/applier/mod.rs"]);
+
+    // Esc clears the query before it cancels
+    assert_eq!(press(&mut state, KeyCode::Esc), PickerAction::Continue);
+    assert_eq!(state.query(), "");
+    assert_eq!(press(&mut state, KeyCode::Esc), PickerAction::Cancel);
+}
+
+#[test]
+fn test_picker_confirm_and_cancel() {
+    let mut state = state();
+    // Enter with nothing selected picks the row under the cursor
+    assert_eq!(press(&mut state, KeyCode::Enter), PickerAction::Confirm);
+    assert_eq!(state.selected_files(), vec![PathBuf::from("README.md")]);
+
+    let mut state = self::state();
+    assert_eq!(
+        state.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+        PickerAction::Cancel
+    );
+    assert_eq!(state.query(), "");
+}
+
+#[test]
+fn test_fuzzy_score() {
+    assert_eq!(fuzzy_score("xyz", "src/copy.rs"), None);
+    assert!(fuzzy_score("", "src/copy.rs").is_some());
+    assert!(fuzzy_score("COPY", "src/copy.rs").is_some());
+
+    // Consecutive and boundary matches rank higher
+    let copy = fuzzy_score("copy", "src/copy.rs").unwrap();
+    let scattered = fuzzy_score("copy", "src/config/proxy.rs").unwrap();
+    assert!(copy > scattered);
+    let camel = fuzzy_score("fm", "src/FileMap.rs").unwrap();
+    let inner = fuzzy_score("fm", "src/farm.rs").unwrap();
+    assert!(camel > inner);
+}
@@ -0,0 +1,119 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::prompt::{available_prompts, load_prompt, parse_prompt_var, render_prompt};
+use snippy::sink::SinkKind;
+use std::fs;
+use tempfile::tempdir;
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_render_prompt_fills_slots() {
+    let rendered = render_prompt(
+        "Context:\n{$CONTEXT}\nTask: {$TASK} in {$lang}. {$MISSING} {$not a var} {$",
+        "fn a() {}",
+        &vars(&[("TASK", "add {$CONTEXT}"), ("LANG", "Rust")]),
+    );
+    assert_eq!(
+        rendered,
+        "Context:\nfn a() {}\nTask: add {$CONTEXT} in Rust. {$MISSING} {$not a var} {$"
+    );
+
+    // Prompts without a context slot follow the context
+    assert_eq!(
+        render_prompt("Review this.", "fn a() {}\n", &[]),
+        "fn a() {}\n\nReview this."
+    );
+}
+
+#[test]
+fn test_parse_prompt_var() {
+    assert_eq!(
+        parse_prompt_var("lang=rust=2021").unwrap(),
+        ("LANG".to_string(), "rust=2021".to_string())
+    );
+    assert!(parse_prompt_var("lang").is_err());
+    assert!(parse_prompt_var("=rust").is_err());
+}
+
+#[test]
+fn test_load_prompt_prefers_user_prompts() {
+    let builtin = load_prompt("general-coding", None).unwrap();
+    assert!(builtin.contains("{$CODING_TASK}"));
+    assert!(builtin.contains("{$CONTEXT}"));
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("general-coding.md"), "Mine: {$TASK}").unwrap();
+    fs::write(dir.path().join("review.md"), "Review {$CONTEXT}").unwrap();
+    fs::write(dir.path().join("notes.txt"), "Not a prompt").unwrap();
+    assert_eq!(
+        load_prompt("general-coding", Some(dir.path())).unwrap(),
+        "Mine: {$TASK}"
+    );
+    assert_eq!(
+        load_prompt("review", Some(dir.path())).unwrap(),
+        "Review {$CONTEXT}"
+    );
+    assert_eq!(
+        available_prompts(Some(dir.path())),
+        vec!["general-coding", "review"]
+    );
+
+    let err = load_prompt("missing", Some(dir.path())).unwrap_err();
+    assert!(
+        err.to_string().contains("general-coding, review"),
+        "Unexpected error: {}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_copy_with_prompt() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("client.rs"), "struct LLMClient;\n").unwrap();
+    let output = root.join("prompt.md");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        prompt: Some("general-coding".to_string()),
+        task: Some("add retries to LLMClient".to_string()),
+        prompts_dir: Some(root.join("prompts")),
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(
+        config,
+        vec![root.join("client.rs").to_string_lossy().into_owned()],
+    )
+    .await
+    .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.starts_with("You are a highly knowledgeable coding assistant"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.contains("<context>\n# Relevant Code\n"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.contains("struct LLMClient;\n```\n\n</context>"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.contains("<coding_task>\nadd retries to LLMClient\n</coding_task>"),
+        "Unexpected output: {}",
+        written
+    );
+}
@@ -0,0 +1,200 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::search::{terms, SearchIndex, INDEX_FILE};
+use snippy::sink::SinkKind;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write(root: &Path, file: &str, content: &str) -> String {
+    let path = root.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_terms() {
+    assert_eq!(
+        terms("SearchReplaceApplier"),
+        vec!["searchreplaceapply", "search", "replac", "apply"]
+    );
+    assert_eq!(
+        terms("how are blocks applied to parse_http_headers?"),
+        vec!["block", "apply", "pars", "http", "header"]
+    );
+    // Acronyms stay together
+    assert_eq!(terms("HTTPServer"), vec!["httpserver", "http", "server"]);
+}
+
+#[test]
+fn test_search_ranks_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let files = vec![
+        write(
+            root,
+            "src/applier.rs",
+            "/// Applies search/replace blocks.\nfn apply_block(block: &Block) {}\n",
+        ),
+        write(root, "src/parser.rs", "fn parse(text: &str) -> Block {}\n"),
+        write(root, "README.md", "Nothing to see here.\n"),
+    ];
+    let mut index = SearchIndex::default();
+    assert_eq!(index.update(root, &files), 3);
+
+    let hits = index.search(root, "how are search/replace blocks applied", &files);
+    let ranked: Vec<&str> = hits.iter().map(|hit| hit.file.as_str()).collect();
+    assert_eq!(ranked, vec![files[0].as_str(), files[1].as_str()]);
+    assert!(hits[0].score > hits[1].score);
+    // Only the given files are ranked
+    let hits = index.search(root, "block", &files[1..]);
+    assert_eq!(hits.len(), 1);
+    assert!(index.search(root, "unrelated", &files).is_empty());
+}
+
+#[test]
+fn test_index_updates_incrementally() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let files = vec![
+        write(root, "a.rs", "fn alpha() {}\n"),
+        write(root, "b.rs", "fn beta() {}\n"),
+    ];
+    let index_path = root.join(INDEX_FILE);
+    let mut index = SearchIndex::load(&index_path);
+    assert!(index.is_empty());
+    assert_eq!(index.update(root, &files), 2);
+    index.save(&index_path).unwrap();
+
+    let mut index = SearchIndex::load(&index_path);
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.update(root, &files), 0);
+
+    // A changed size is noticed even within the same modification time
+    write(root, "b.rs", "fn beta() { gamma() }\n");
+    assert_eq!(index.update(root, &files), 1);
+    assert_eq!(index.search(root, "gamma", &files).len(), 1);
+
+    fs::remove_file(&files[0]).unwrap();
+    assert_eq!(index.update(root, &files[1..]), 1);
+    assert_eq!(index.len(), 1);
+
+    fs::write(&index_path, "not json").unwrap();
+    assert!(SearchIndex::load(&index_path).is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_query() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    write(
+        root,
+        "src/applier.rs",
+        "/// Applies search/replace blocks.\nfn apply_block() {}\n",
+    );
+    write(root, "src/blocks.rs", "struct Block;\n");
+    write(root, "src/other.rs", "fn other() {}\n");
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        query: Some("applying blocks".to_string()),
+        query_top: 1,
+        no_markdown: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("fn apply_block()"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("struct Block"));
+    assert!(!written.contains("fn other()"));
+    assert!(root.join(INDEX_FILE).is_file());
+}
+
+#[tokio::test]
+async fn test_copy_query_keeps_file_filters() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    write(root, "src/parser.rs", "fn parse_tokens() {}\n");
+    write(
+        root,
+        "src/big_parser.rs",
+        &"fn parse_tokens_again() {}\n".repeat(100),
+    );
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        query: Some("parse tokens".to_string()),
+        max_file_tokens: Some(50),
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    // Ranked files are not explicit, so the token limit still applies
+    assert!(
+        written.contains("fn parse_tokens()") && !written.contains("parse_tokens_again"),
+        "Unexpected output: {}",
+        written
+    );
+}
+
+#[tokio::test]
+async fn test_query_index_leaves_out_secrets_and_filtered_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    // Fake credentials are assembled at runtime so they do not trip secret scanners
+    let key = format!("AKIA{}", "QWERTYUIOPASDFGH");
+    write(
+        root,
+        "src/deploy.py",
+        &format!("def deploy_upload():\n    key = \"{}\"\n", key),
+    );
+    write(
+        root,
+        "src/gen.py",
+        "# Code generated by tool. DO NOT EDIT.\ndef deploy_upload(): pass\n",
+    );
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        query: Some("deploy upload".to_string()),
+        no_redact: true,
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("deploy.py") && !written.contains("gen.py"),
+        "Unexpected output: {}",
+        written
+    );
+    let index = fs::read_to_string(root.join(INDEX_FILE)).unwrap();
+    assert!(!index.to_lowercase().contains(&key.to_lowercase()));
+    let generated = root.join("src/gen.py").to_string_lossy().into_owned();
+    assert!(SearchIndex::load(&root.join(INDEX_FILE))
+        .search(root, "deploy upload", &[generated])
+        .is_empty());
+    assert_eq!(
+        fs::read_to_string(root.join(".snippy/.gitignore")).unwrap(),
+        "index.json\n"
+    );
+}
@@ -0,0 +1,320 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::selector::{parse_selector, FileSelector, LineRange, Selection};
+use snippy::symbols::{find_symbol, Language};
+use snippy::sink::SinkKind;
+use snippy::utils::{format_snippet, FormatOptions};
+use std::fs;
+use tempfile::tempdir;
+
+fn options(xml: bool) -> FormatOptions {
+    FormatOptions {
+        no_markdown: false,
+        line_number: Some(3),
+        prefix: "|".to_string(),
+        filename_format: "MarkdownHeading".to_string(),
+        xml,
+        outline: false,
+        ..FormatOptions::default()
+    }
+}
+
+#[test]
+fn test_parse_selector_line_ranges() {
+    assert_eq!(
+        parse_selector("src/watch.rs:120-260").selection,
+        Some(Selection::Lines {
+            start: 120,
+            end: Some(260)
+        })
+    );
+    assert_eq!(
+        parse_selector("src/watch.rs:120-").selection,
+        Some(Selection::Lines {
+            start: 120,
+            end: None
+        })
+    );
+    assert_eq!(
+        parse_selector("src/watch.rs:7").selection,
+        Some(Selection::Lines {
+            start: 7,
+            end: Some(7)
+        })
+    );
+
+    let last = parse_selector("src/llm.rs:-50");
+    assert_eq!(last.pattern, "src/llm.rs");
+    assert_eq!(last.selection, Some(Selection::LastLines(50)));
+}
+
+#[test]
+fn test_parse_selector_leaves_other_arguments_unchanged() {
+    for arg in ["src/**/*.rs", "C:\\src\\main.rs", "git@github.com:owner/repo.git", "a.rs:10-5"] {
+        assert_eq!(
+            parse_selector(arg),
+            FileSelector {
+                pattern: arg.to_string(),
+                selection: None
+            }
+        );
+    }
+}
+
+#[test]
+fn test_selection_resolve_clamps_to_content() {
+    let content = "1\n2\n3\n4\n5\n";
+    let lines = Selection::Lines {
+        start: 4,
+        end: Some(99),
+    };
+    assert_eq!(lines.resolve(content), Some(LineRange { start: 4, end: 5 }));
+    assert_eq!(
+        Selection::LastLines(2).resolve(content),
+        Some(LineRange { start: 4, end: 5 })
+    );
+    assert_eq!(
+        Selection::LastLines(50).resolve(content),
+        Some(LineRange { start: 1, end: 5 })
+    );
+    let past_end = Selection::Lines {
+        start: 9,
+        end: None,
+    };
+    assert_eq!(past_end.resolve(content), None);
+    assert_eq!(LineRange { start: 2, end: 3 }.slice(content), vec!["2", "3"]);
+}
+
+#[test]
+fn test_format_snippet_keeps_original_line_numbers() {
+    let formatted = format_snippet(
+        "let a = 1;\nlet b = 2;",
+        "src/lib.rs",
+        41,
+        Some("src/lib.rs:41-42"),
+        &options(false),
+    )
+    .unwrap();
+    assert_eq!(
+        formatted,
+        "### `src/lib.rs:41-42`\n```rs\n041|let a = 1;\n042|let b = 2;\n```\n"
+    );
+
+    let formatted = format_snippet("x", "src/lib.rs", 7, Some("src/lib.rs:7"), &options(true)).unwrap();
+    assert_eq!(
+        formatted,
+        "<file path=\"src/lib.rs:7\" type=\"rust\">\n<line number=\"007\">x</line>\n</file>\n"
+    );
+}
+
+const RUST_SOURCE: &str = r#"use std::fmt;
+
+/// A watcher.
+#[derive(Debug)]
+pub struct Watcher {
+    name: String,
+}
+
+impl Watcher {
+    /// Processes content.
+    pub async fn process(&mut self, content: &str) -> Result<(), String> {
+        let prompt = r"{ unbalanced";
+        let brace = '{';
+        if content.is_empty() {
+            return Err(format!("{}", "}"));
+        }
+        Ok(())
+    }
+
+    fn other<'a>(&self, s: &'a str) -> &'a str {
+        s
+    }
+}
+
+impl fmt::Display for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub const LIMIT: usize = 3;
+"#;
+
+#[test]
+fn test_parse_selector_symbols() {
+    assert_eq!(
+        parse_selector("src/watch.rs::ClipboardWatcher::process_with_ai"),
+        FileSelector {
+            pattern: "src/watch.rs".to_string(),
+            selection: Some(Selection::Symbol {
+                language: Language::Rust,
+                name: "ClipboardWatcher::process_with_ai".to_string(),
+            }),
+        }
+    );
+    assert_eq!(
+        parse_selector("app/models.py::User.save").selection,
+        Some(Selection::Symbol {
+            language: Language::Python,
+            name: "User.save".to_string(),
+        })
+    );
+    // Unsupported languages and empty names are left alone
+    assert_eq!(parse_selector("notes.md::Intro").selection, None);
+    assert_eq!(parse_selector("src/lib.rs::").selection, None);
+}
+
+#[test]
+fn test_find_rust_symbols_with_doc_comments() {
+    let find = |symbol| find_symbol(RUST_SOURCE, Language::Rust, symbol);
+
+    assert_eq!(find("Watcher"), Some(LineRange { start: 3, end: 7 }));
+    assert_eq!(
+        find("Watcher::process"),
+        Some(LineRange { start: 10, end: 18 })
+    );
+    assert_eq!(
+        find("Watcher::other"),
+        Some(LineRange { start: 20, end: 22 })
+    );
+    // Methods are searched in every impl block of the type
+    assert_eq!(find("Watcher::fmt"), Some(LineRange { start: 26, end: 28 }));
+    assert_eq!(find("LIMIT"), Some(LineRange { start: 31, end: 31 }));
+    assert_eq!(find("Watcher::missing"), None);
+}
+
+#[test]
+fn test_find_rust_impl_blocks() {
+    let find = |symbol| find_symbol(RUST_SOURCE, Language::Rust, symbol);
+
+    assert_eq!(find("impl Watcher"), Some(LineRange { start: 9, end: 23 }));
+    assert_eq!(
+        find("impl Display for Watcher"),
+        Some(LineRange { start: 25, end: 29 })
+    );
+    assert_eq!(
+        find("impl fmt::Display for Watcher"),
+        Some(LineRange { start: 25, end: 29 })
+    );
+    assert_eq!(find("impl Debug for Watcher"), None);
+    assert_eq!(
+        parse_selector("src/watch.rs::impl Watcher").selection,
+        Some(Selection::Symbol {
+            language: Language::Rust,
+            name: "impl Watcher".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_find_python_symbols() {
+    let source = r#"import os
+
+
+@dataclass
+class User:
+    name: str
+
+    # Saves the user.
+    def save(self,
+             force: bool = False) -> None:
+        """Persist."""
+        if force:
+            os.sync()
+
+        return None
+
+    def delete(self): pass
+
+
+def main():
+    User("a").save()
+"#;
+    let find = |symbol| find_symbol(source, Language::Python, symbol);
+
+    assert_eq!(find("User"), Some(LineRange { start: 4, end: 17 }));
+    assert_eq!(find("User.save"), Some(LineRange { start: 8, end: 15 }));
+    assert_eq!(find("User::delete"), Some(LineRange { start: 17, end: 17 }));
+    assert_eq!(find("main"), Some(LineRange { start: 20, end: 21 }));
+}
+
+#[test]
+fn test_find_typescript_and_go_symbols() {
+    let ts = r#"/**
+ * Parses input.
+ */
+export class Parser {
+  private readonly input: string;
+
+  async parse(text: string): Promise<Node> {
+    if (text === "}") {
+      return `{${text}`;
+    }
+    return this.next();
+  }
+}
+
+export type Id =
+  | string
+  | number
+
+export const handler = async (req: Request) => {
+  return req;
+};
+"#;
+    let find = |symbol| find_symbol(ts, Language::TypeScript, symbol);
+    assert_eq!(find("Parser"), Some(LineRange { start: 1, end: 13 }));
+    assert_eq!(find("Parser.parse"), Some(LineRange { start: 7, end: 12 }));
+    assert_eq!(find("Id"), Some(LineRange { start: 15, end: 17 }));
+    assert_eq!(find("handler"), Some(LineRange { start: 19, end: 21 }));
+
+    let go = r#"package main
+
+type Server struct {
+	addr string
+}
+
+// Start runs the server.
+func (s *Server) Start() error {
+	return nil
+}
+
+func main() {
+	_ = "}"
+}
+"#;
+    let find = |symbol| find_symbol(go, Language::Go, symbol);
+    assert_eq!(find("Server"), Some(LineRange { start: 3, end: 5 }));
+    assert_eq!(find("Server.Start"), Some(LineRange { start: 7, end: 10 }));
+    assert_eq!(find("Start"), Some(LineRange { start: 7, end: 10 }));
+    assert_eq!(find("main"), Some(LineRange { start: 12, end: 14 }));
+}
+
+#[tokio::test]
+async fn test_selector_on_glob_applies_to_every_match() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/a.rs"), "// a header\nfn a() {}\n").unwrap();
+    fs::write(root.join("src/b.rs"), "// b header\nfn b() {}\n").unwrap();
+    fs::write(root.join("src/c.py"), "# c header\ndef c(): pass\n").unwrap();
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    let pattern = format!("{}/src/*.rs:2", root.display());
+    copy_files_to_clipboard(config, vec![pattern])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("fn a() {}") && written.contains("fn b() {}"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("header") && !written.contains("def c()"));
+}
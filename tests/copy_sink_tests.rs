@@ -0,0 +1,97 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::errors::ClipboardError;
+use snippy::osc52::{
+    osc52_sequence, Multiplexer, Osc52Sink, MAX_OSC52_PAYLOAD, SCREEN_CHUNK_SIZE, TMUX_CHUNK_SIZE,
+};
+use snippy::sink::{build_sink, ClipboardBackend, OutputSink, SinkKind};
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn test_copy_files_to_file_sink() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("lib.rs");
+    fs::write(&source, "pub fn answer() -> u32 { 42 }\n")
+        .await
+        .unwrap();
+    let output = dir.path().join("out/context.md");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+
+    copy_files_to_clipboard(config, vec![source.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).await.unwrap();
+    assert!(written.starts_with("# Relevant Code\n"), "Unexpected output: {}", written);
+    assert!(written.contains("pub fn answer() -> u32 { 42 }\n"), "Unexpected output: {}", written);
+}
+
+#[test]
+fn test_build_sink_requires_output_path_for_file_sink() {
+    assert!(build_sink(SinkKind::File, None, ClipboardBackend::System).is_err());
+    assert_eq!(build_sink(SinkKind::Stdout, None, ClipboardBackend::System).unwrap().describe(), "stdout");
+    assert_eq!(
+        build_sink(SinkKind::Both, Some("context.md".into()), ClipboardBackend::Osc52)
+            .unwrap()
+            .describe(),
+        "clipboard (OSC 52) and context.md"
+    );
+}
+
+#[test]
+fn test_osc52_sequence_plain_and_tmux() {
+    assert_eq!(osc52_sequence("hi", Multiplexer::None), "\x1b]52;c;aGk=\x07");
+    assert_eq!(
+        osc52_sequence("hi", Multiplexer::Tmux),
+        "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+    );
+}
+
+#[test]
+fn test_osc52_sequence_chunks_for_screen() {
+    let content = "x".repeat(2000);
+    let sequence = osc52_sequence(&content, Multiplexer::Screen);
+    let plain = osc52_sequence(&content, Multiplexer::None);
+
+    let chunks: Vec<&str> = sequence
+        .split("\x1b\\")
+        .filter(|c| !c.is_empty())
+        .map(|c| c.strip_prefix("\x1bP").unwrap())
+        .collect();
+    assert_eq!(chunks.len(), plain.len().div_ceil(SCREEN_CHUNK_SIZE));
+    assert!(chunks.iter().all(|c| c.len() <= SCREEN_CHUNK_SIZE));
+    assert_eq!(chunks.concat(), plain);
+}
+
+#[test]
+fn test_osc52_sequence_chunks_for_tmux() {
+    let content = "x".repeat(MAX_OSC52_PAYLOAD);
+    let sequence = osc52_sequence(&content, Multiplexer::Tmux);
+    let plain = osc52_sequence(&content, Multiplexer::None);
+
+    let chunks: Vec<String> = sequence
+        .split("\x1b\\")
+        .filter(|c| !c.is_empty())
+        .map(|c| c.strip_prefix("\x1bPtmux;").unwrap().replace("\x1b\x1b", "\x1b"))
+        .collect();
+    assert_eq!(chunks.len(), plain.len().div_ceil(TMUX_CHUNK_SIZE));
+    assert!(chunks.iter().all(|c| c.len() <= TMUX_CHUNK_SIZE));
+    assert_eq!(chunks.concat(), plain);
+}
+
+#[tokio::test]
+async fn test_osc52_sink_refuses_payloads_over_the_limit() {
+    let content = "x".repeat(3000);
+    let result = Osc52Sink::new(Multiplexer::Tmux)
+        .with_max_payload(Some(1000))
+        .write(&content)
+        .await;
+    assert!(matches!(result, Err(ClipboardError::ClipboardWriteError(_))));
+}
@@ -0,0 +1,136 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::filter::SkipReason;
+use snippy::reporting::{stats_report, FileStats, StatsFormat, StatsOptions, StatsSort};
+use snippy::sink::SinkKind;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn file_stats() -> HashMap<PathBuf, FileStats> {
+    let stats = |tokens| FileStats {
+        tokens,
+        ..FileStats::default()
+    };
+    HashMap::from([
+        (PathBuf::from("src/copy.rs"), stats(500)),
+        (PathBuf::from("src/applier/mod.rs"), stats(100)),
+        (PathBuf::from("src/applier/diff.rs"), stats(300)),
+        (PathBuf::from("README.md"), stats(100)),
+        (
+            PathBuf::from("src/big.rs"),
+            FileStats {
+                omitted: true,
+                ..stats(900)
+            },
+        ),
+        (
+            PathBuf::from("logo.png"),
+            FileStats {
+                skipped: Some(SkipReason::Binary),
+                ..FileStats::default()
+            },
+        ),
+    ])
+}
+
+#[test]
+fn test_stats_report_totals_and_percentages() {
+    let report = stats_report(&file_stats(), &StatsOptions::default()).unwrap();
+    assert_eq!(report.total_tokens, 1000);
+    assert_eq!(report.copied_files, 4);
+    assert_eq!(report.omitted_files, 1);
+    assert_eq!(report.omitted_tokens, 900);
+    assert_eq!(report.skipped.get("binary"), Some(&1));
+    assert!(report.top.is_empty());
+
+    let paths: Vec<&str> = report.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "README.md",
+            "logo.png",
+            "src/applier/diff.rs",
+            "src/applier/mod.rs",
+            "src/big.rs",
+            "src/copy.rs"
+        ]
+    );
+    let copy = &report.files[5];
+    assert_eq!((copy.copied_tokens, copy.percent), (500, 50.0));
+    let big = &report.files[4];
+    assert_eq!((big.tokens, big.copied_tokens, big.percent), (900, 0, 0.0));
+
+    let directories: Vec<(&str, usize, f64, usize)> = report
+        .directories
+        .iter()
+        .map(|d| (d.path.as_str(), d.tokens, d.percent, d.files))
+        .collect();
+    assert_eq!(
+        directories,
+        vec![("src", 900, 90.0, 4), ("src/applier", 400, 40.0, 2)]
+    );
+}
+
+#[test]
+fn test_stats_report_sorting_top_and_depth() {
+    let options = StatsOptions {
+        sort: StatsSort::Tokens,
+        top: Some(2),
+        max_depth: Some(1),
+        ..StatsOptions::default()
+    };
+    let report = stats_report(&file_stats(), &options).unwrap();
+
+    let top: Vec<&str> = report.top.iter().map(|f| f.path.as_str()).collect();
+    // Omitted files copy nothing, so they rank below the copied ones
+    assert_eq!(top, vec!["src/copy.rs", "src/applier/diff.rs"]);
+    assert_eq!(report.files[0].path, "src/copy.rs");
+    assert_eq!(report.files[1].path, "src/applier/diff.rs");
+    // Nested directories are collapsed into their parent
+    assert_eq!(report.directories.len(), 1);
+    assert_eq!(report.directories[0].path, "src");
+}
+
+#[tokio::test]
+async fn test_stats_only_writes_nothing() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    let output = root.join("out.md");
+
+    let config = ClipboardCopierConfig {
+        stats: StatsOptions {
+            top: Some(1),
+            emoji: false,
+            ..StatsOptions::default()
+        },
+        stats_only: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to compute statistics: {:?}", e));
+    assert!(!output.exists());
+
+    // JSON statistics and copied content cannot both go to stdout
+    let config = ClipboardCopierConfig {
+        stats: StatsOptions {
+            format: StatsFormat::Json,
+            ..StatsOptions::default()
+        },
+        sink: SinkKind::Stdout,
+        ..Default::default()
+    };
+    let err = copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("stdout"),
+        "Unexpected error: {}",
+        err
+    );
+}
@@ -0,0 +1,148 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::sink::SinkKind;
+use snippy::template::{render, OutputTemplate};
+use snippy::utils::{format_snippet, FormatOptions};
+use std::fs;
+use tempfile::tempdir;
+
+fn options(template: OutputTemplate) -> FormatOptions {
+    FormatOptions {
+        no_markdown: false,
+        line_number: None,
+        prefix: "|".to_string(),
+        filename_format: "MarkdownHeading".to_string(),
+        xml: false,
+        outline: false,
+        template: Some(template),
+        ..FormatOptions::default()
+    }
+}
+
+#[test]
+fn test_render_substitutes_known_variables_once() {
+    assert_eq!(
+        render(
+            "{{path}}: {{ content }} {{unknown}}",
+            &[("path", "a.rs"), ("content", "{{path}}")]
+        ),
+        "a.rs: {{path}} {{unknown}}"
+    );
+    assert_eq!(render("{{open", &[("open", "x")]), "{{open");
+}
+
+#[test]
+fn test_presets_match_legacy_formats() {
+    for (format, xml, preset) in [
+        ("MarkdownHeading", false, "markdown"),
+        ("MarkdownFirstCodeLine", false, "markdown-first-line"),
+        ("None", false, "plain"),
+        ("MarkdownHeading", true, "xml"),
+    ] {
+        for markdown in [true, false] {
+            assert_eq!(
+                OutputTemplate::from_format(format, xml, markdown),
+                OutputTemplate::preset(preset, markdown).unwrap()
+            );
+        }
+    }
+
+    let first_line = options(OutputTemplate::preset("markdown-first-line", true).unwrap());
+    assert_eq!(
+        format_snippet("x = 1", "a.py", 1, None, &first_line).unwrap(),
+        "```py\n# filename: a.py\nx = 1\n```\n"
+    );
+    let numbered = FormatOptions {
+        no_markdown: true,
+        line_number: Some(2),
+        prefix: ": ".to_string(),
+        ..options(OutputTemplate::preset("markdown", false).unwrap())
+    };
+    assert_eq!(
+        format_snippet("x = 1", "a.py", 1, None, &numbered).unwrap(),
+        "### `a.py`\n01: x = 1\n"
+    );
+    assert!(OutputTemplate::preset("html", true).is_err());
+}
+
+#[test]
+fn test_documents_preset() {
+    let options = options(OutputTemplate::preset("documents", true).unwrap());
+    assert_eq!(
+        format_snippet("fn a() {}", "src/a.rs", 1, None, &options).unwrap(),
+        "<document index=\"1\">\n<source>src/a.rs</source>\n<document_content>\n<![CDATA[fn a() {}]]>\n</document_content>\n</document>\n"
+    );
+    // Paths are escaped and content cannot close the element early
+    assert_eq!(
+        format_snippet(
+            "a && b </document_content>",
+            "src/a&b.rs",
+            1,
+            None,
+            &options
+        )
+        .unwrap(),
+        "<document index=\"1\">\n<source>src/a&amp;b.rs</source>\n<document_content>\n<![CDATA[a && b </document_content>]]>\n</document_content>\n</document>\n"
+    );
+}
+
+#[test]
+fn test_parse_template_file() {
+    let template = OutputTemplate::parse(
+        "--- header ---\n# {{files}} files\n--- file ---\n## {{path}} ({{language}}, {{lines}} lines)\n{{content}}\n--- line ---\n{{number}} {{line}}\n",
+    )
+    .unwrap();
+    assert_eq!(template.header.as_deref(), Some("# {{files}} files\n"));
+    assert_eq!(
+        template.file,
+        "## {{path}} ({{language}}, {{lines}} lines)\n{{content}}\n"
+    );
+    assert_eq!(template.diff, None);
+    assert_eq!(template.footer, "");
+    assert_eq!(template.line, "{{number}} {{line}}");
+
+    // Without sections the whole file is the per-file template
+    let template = OutputTemplate::parse("<<{{path}}>>\n{{content}}").unwrap();
+    assert_eq!(template.file, "<<{{path}}>>\n{{content}}");
+    assert_eq!(template.header, None);
+
+    assert!(OutputTemplate::parse("--- body ---\n{{content}}").is_err());
+    assert!(OutputTemplate::parse("--- header ---\n# Files\n").is_err());
+}
+
+#[tokio::test]
+async fn test_copy_with_template_file() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("b.py"), "def b():\n    pass\n").unwrap();
+    let out_dir = tempdir().unwrap();
+    let template = out_dir.path().join("context.tmpl");
+    fs::write(
+        &template,
+        "--- header ---\n{{files}} files\n--- file ---\n[{{index}}] {{extension}} {{lines}} {{hash}}\n{{content}}\n--- footer ---\nend\n",
+    )
+    .unwrap();
+    let output = out_dir.path().join("context.txt");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        template: Some(template.to_string_lossy().into_owned()),
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert_eq!(
+        written,
+        format!(
+            "2 files\n[1] rs 1 {}\nfn a() {{}}\n\n[2] py 2 {}\ndef b():\n    pass\n\nend\n",
+            "509a0a5b5ce4e59f5039e30a39324342d7a161296bb8eba761983faaeebf6efd",
+            "dceca1f332cdd10881f6bc0a82032166d50479095e650a90ab0371faa227e363"
+        )
+    );
+}
@@ -0,0 +1,158 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::selector::LineRange;
+use snippy::sink::SinkKind;
+use snippy::symbols::Language;
+use snippy::usages::{find_references, find_usages, merge_ranges, UsageOptions};
+use std::fs;
+use tempfile::tempdir;
+
+const RUST_CALLER: &str = r#"use crate::llm::LLMClient;
+
+/// Sends the prompt.
+pub async fn send(prompt: &str) -> String {
+    let client = LLMClient::new();
+    // call the model
+    client.call(prompt).await
+}
+
+fn unrelated() {
+    let recall = 1;
+}
+
+pub fn twice(client: &LLMClient) {
+    client.call("a");
+    client.call("b");
+}
+"#;
+
+const PYTHON_CALLER: &str = r#"from app.parser import Parser
+
+
+@cached
+def load(path):
+    """Loads a file."""
+    text = open(path).read()
+    return Parser().parse(text)
+
+
+result = Parser.parse("x")
+"#;
+
+fn range(start: usize, end: usize) -> LineRange {
+    LineRange { start, end }
+}
+
+fn options(symbol: &str) -> UsageOptions {
+    UsageOptions {
+        symbols: vec![symbol.to_string()],
+        ..UsageOptions::default()
+    }
+}
+
+#[test]
+fn test_find_references() {
+    let lines: Vec<&str> = RUST_CALLER.lines().collect();
+    assert_eq!(
+        find_references(&lines, Language::Rust, "LLMClient::call"),
+        vec![6, 14, 15]
+    );
+    // Declarations are not references
+    let definition = ["impl LLMClient {", "    pub async fn call(&self) {}", "}"];
+    assert!(find_references(&definition, Language::Rust, "LLMClient::call").is_empty());
+    // Files that never mention the qualifier do not reference it
+    let other = ["fn f(x: Other) { x.call(); }"];
+    assert!(find_references(&other, Language::Rust, "LLMClient::call").is_empty());
+    assert_eq!(find_references(&other, Language::Rust, "call"), vec![0]);
+    // The qualifier has to be in scope of each reference, not just in the file
+    let mixed = [
+        "fn make() -> LLMClient { LLMClient::new() }",
+        "fn other(x: Other) { x.call(); }",
+        "impl LLMClient {",
+        "    fn retry(&self) { self.call(); }",
+        "}",
+    ];
+    assert_eq!(
+        find_references(&mixed, Language::Rust, "LLMClient::call"),
+        vec![3]
+    );
+
+    let lines: Vec<&str> = PYTHON_CALLER.lines().collect();
+    assert_eq!(
+        find_references(&lines, Language::Python, "Parser.parse"),
+        vec![7, 10]
+    );
+}
+
+#[test]
+fn test_find_usages_context_and_functions() {
+    let usages = find_usages(RUST_CALLER, Language::Rust, &options("LLMClient::call"));
+    assert_eq!(usages.counts, vec![("LLMClient::call".to_string(), 3)]);
+    assert_eq!(usages.ranges, vec![range(4, 10), range(12, 17)]);
+
+    let usages = find_usages(
+        RUST_CALLER,
+        Language::Rust,
+        &UsageOptions {
+            context: 0,
+            functions: true,
+            ..options("LLMClient::call")
+        },
+    );
+    assert_eq!(usages.ranges, vec![range(3, 8), range(14, 17)]);
+
+    let usages = find_usages(
+        PYTHON_CALLER,
+        Language::Python,
+        &UsageOptions {
+            context: 1,
+            functions: true,
+            ..options("Parser.parse")
+        },
+    );
+    // The module-level reference keeps its context lines
+    assert_eq!(usages.ranges, vec![range(4, 8), range(10, 11)]);
+}
+
+#[test]
+fn test_merge_ranges() {
+    assert_eq!(
+        merge_ranges(vec![range(10, 12), range(1, 3), range(4, 5), range(11, 20)]),
+        vec![range(1, 5), range(10, 20)]
+    );
+}
+
+#[tokio::test]
+async fn test_copy_usages() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("send.rs"), RUST_CALLER).unwrap();
+    fs::write(root.join("other.rs"), "fn other() {}\n").unwrap();
+    let output = root.join("out.md");
+    let config = ClipboardCopierConfig {
+        usages: UsageOptions {
+            context: 1,
+            ..options("LLMClient::call")
+        },
+        no_markdown: true,
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.contains("send.rs:6-8") && written.contains("0007|    client.call(prompt).await"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.contains("send.rs:14-17"),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(!written.contains("fn other()"));
+    assert!(!written.contains("fn unrelated()"));
+}
@@ -0,0 +1,143 @@
+use snippy::copy::ClipboardCopierConfig;
+use snippy::copy_files_to_clipboard;
+use snippy::extractor::auto::AutoExtractor;
+use snippy::extractor::xml::XmlExtractor;
+use snippy::extractor::{BlockType, Extractor};
+use snippy::sink::SinkKind;
+use snippy::utils::{format_diff_at, format_snippet, FormatOptions};
+use snippy::xml::{cdata, escape, unescape, XmlAttribute};
+use std::fs;
+use tempfile::tempdir;
+
+const TRICKY: &str = "if a < b && c > d {\n    s = \"]]></file>\";\n}\n<![CDATA[ ]]>";
+
+fn xml_options(line_number: Option<usize>) -> FormatOptions {
+    FormatOptions {
+        line_number,
+        xml: true,
+        ..FormatOptions::default()
+    }
+}
+
+#[test]
+fn test_escaping() {
+    assert_eq!(
+        escape(r#"a<b & "c" 'd'>"#),
+        "a&lt;b &amp; &quot;c&quot; &apos;d&apos;&gt;"
+    );
+    assert_eq!(
+        unescape("a&lt;b &amp;amp; &#65;&#x42; & &bogus;"),
+        "a<b &amp; AB & &bogus;"
+    );
+    assert_eq!(cdata("x]]>y"), "<![CDATA[x]]]]><![CDATA[>y]]>");
+}
+
+#[test]
+fn test_xml_output_is_escaped() {
+    let formatted =
+        format_snippet(TRICKY, "src/a&b \"q\".rs", 1, None, &xml_options(None)).unwrap();
+    assert_eq!(
+        formatted,
+        format!(
+            "<file path=\"src/a&amp;b &quot;q&quot;.rs\" type=\"rust\">\n{}\n</file>\n",
+            cdata(TRICKY)
+        )
+    );
+
+    let numbered = format_snippet("a < b", "a.rs", 1, None, &xml_options(Some(2))).unwrap();
+    assert_eq!(
+        numbered,
+        "<file path=\"a.rs\" type=\"rust\">\n<line number=\"01\">a &lt; b</line>\n</file>\n"
+    );
+
+    assert_eq!(
+        format_diff_at("-a\n+b]]>\n", "a.rs", 1, &xml_options(None)),
+        "<diff path=\"a.rs\">\n<![CDATA[-a\n+b]]]]><![CDATA[>\n]]>\n</diff>\n"
+    );
+}
+
+#[test]
+fn test_xml_extractor_round_trip() {
+    let extractor = XmlExtractor::new();
+    let output = format!(
+        "<files>\n{}{}{}</files>\n",
+        format_snippet(TRICKY, "src/a&b.rs", 1, None, &xml_options(None)).unwrap(),
+        format_snippet("x < 1\ny\n", "b.py", 1, None, &xml_options(Some(3))).unwrap(),
+        format_diff_at("--- a/c.rs\n+++ b/c.rs\n", "c.rs", 1, &xml_options(None)),
+    );
+
+    let blocks = extractor.extract(&output).unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].filename, "src/a&b.rs");
+    assert_eq!(blocks[0].content, TRICKY);
+    assert_eq!(blocks[0].block_type, BlockType::FullContent);
+    assert_eq!(blocks[1].filename, "b.py");
+    assert_eq!(blocks[1].content, "x < 1\ny\n");
+    assert_eq!(blocks[2].filename, "c.rs");
+    assert_eq!(blocks[2].content, "--- a/c.rs\n+++ b/c.rs\n");
+    assert_eq!(blocks[2].block_type, BlockType::UnifiedDiff);
+
+    // Hand-written elements with escaped text work too
+    let blocks = extractor
+        .extract("<file path='d.rs'>\nfn d() -&gt; u8 { 1 }\n</file>")
+        .unwrap();
+    assert_eq!(blocks[0].content, "fn d() -> u8 { 1 }\n");
+    assert!(extractor.extract("<file path=\"e.rs\"><![CDATA[x").is_err());
+
+    let auto = AutoExtractor::new();
+    assert_eq!(auto.extract(&output).unwrap().len(), 3);
+    let markdown = auto
+        .extract("```rust\n// filename: f.rs\nfn f() {}\n```\n")
+        .unwrap();
+    assert_eq!(markdown[0].filename, "f.rs");
+}
+
+#[tokio::test]
+async fn test_copy_xml_with_attributes() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+    let output_dir = tempdir().unwrap();
+    let output = output_dir.path().join("context.xml");
+
+    let config = ClipboardCopierConfig {
+        no_stats: true,
+        xml: true,
+        xml_attributes: vec![
+            XmlAttribute::Lines,
+            XmlAttribute::Bytes,
+            XmlAttribute::Tokens,
+            XmlAttribute::Hash,
+            XmlAttribute::Mtime,
+        ],
+        sink: SinkKind::File,
+        output: Some(output.clone()),
+        ..Default::default()
+    };
+    copy_files_to_clipboard(config, vec![root.to_string_lossy().into_owned()])
+        .await
+        .unwrap_or_else(|e| panic!("Failed to copy files: {:?}", e));
+
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(
+        written.starts_with("<files>\n<file path=\""),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.contains(
+            " type=\"rust\" lines=\"1\" bytes=\"10\" tokens=\"4\" sha256=\"509a0a5b5ce4e59f5039e30a39324342d7a161296bb8eba761983faaeebf6efd\" mtime=\"20"
+        ),
+        "Unexpected output: {}",
+        written
+    );
+    assert!(
+        written.ends_with("Z\">\n<![CDATA[fn a() {}\n]]>\n</file>\n</files>\n"),
+        "Unexpected output: {}",
+        written
+    );
+
+    let blocks = XmlExtractor::new().extract(&written).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].content, "fn a() {}\n");
+}